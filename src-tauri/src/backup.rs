@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tauri::Emitter;
+use tauri_plugin_shell::ShellExt;
+
+use crate::error::StoreError;
+use crate::queue::{self, OpKind, QueuedOp};
+use crate::InstallScope;
+
+// ─── Manifest ────────────────────────────────────────────────────────────────
+
+const MANIFEST_VERSION: u32 = 1;
+
+/// Installed apps written by `export_installed_apps`, one entry per app.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppManifest {
+    pub version: u32,
+    pub apps: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub app_id: String,
+    /// Remote the app was installed from, so an import restores from the same source.
+    pub remote: String,
+    pub branch: String,
+    pub scope: InstallScope,
+}
+
+// Helper function to parse `flatpak list --app --columns=application,origin,branch,installation`
+// Apps from custom installations are exported as user installs
+fn parse_manifest_entries(output: &str) -> Vec<ManifestEntry> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split('\t').map(str::trim).collect();
+            if parts.len() < 4 || parts[0].is_empty() {
+                return None;
+            }
+            Some(ManifestEntry {
+                app_id: parts[0].to_string(),
+                remote: parts[1].to_string(),
+                branch: parts[2].to_string(),
+                scope: if parts[3] == "system" {
+                    InstallScope::System
+                } else {
+                    InstallScope::User
+                },
+            })
+        })
+        .collect()
+}
+
+async fn list_installed_entries(app: &tauri::AppHandle) -> Result<Vec<ManifestEntry>, StoreError> {
+    let shell = app.shell();
+
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    let columns = "--columns=application,origin,branch,installation";
+    let output = if is_flatpak {
+        // Inside flatpak, use flatpak-spawn to execute on the host
+        shell
+            .command("flatpak-spawn")
+            .args(["--host", "flatpak", "list", "--app", columns])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
+    } else {
+        // Outside flatpak, use flatpak directly
+        shell
+            .command("flatpak")
+            .args(["list", "--app", columns])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak", e))?
+    };
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
+    }
+
+    Ok(parse_manifest_entries(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+// ─── Import events ───────────────────────────────────────────────────────────
+
+pub const IMPORT_PROGRESS_EVENT: &str = "import-progress";
+pub const IMPORT_COMPLETED_EVENT: &str = "import-completed";
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    Queued,
+    AlreadyInstalled,
+    Installed,
+    Failed,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ImportProgress {
+    pub app_id: String,
+    pub status: ImportStatus,
+}
+
+#[derive(Clone, Default, Serialize)]
+pub struct ImportSummary {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub already_installed: usize,
+}
+
+fn emit_progress(app: &tauri::AppHandle, app_id: &str, status: ImportStatus) {
+    let _ = app.emit(
+        IMPORT_PROGRESS_EVENT,
+        ImportProgress {
+            app_id: app_id.to_string(),
+            status,
+        },
+    );
+}
+
+// ─── Commands ────────────────────────────────────────────────────────────────
+
+/// Write every installed app with its origin remote to `path` as JSON.
+#[tauri::command]
+pub async fn export_installed_apps(app: tauri::AppHandle, path: String) -> Result<(), StoreError> {
+    let manifest = AppManifest {
+        version: MANIFEST_VERSION,
+        apps: list_installed_entries(&app).await?,
+    };
+    let json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    crate::write_atomic(std::path::Path::new(&path), json.as_bytes())
+        .map_err(|e| format!("Failed to write manifest: {}", e))?;
+
+    log::info!(
+        "[export_installed_apps] Exported {} app(s) to {}",
+        manifest.apps.len(),
+        path
+    );
+    Ok(())
+}
+
+/// Queue an install for every app in a manifest written by `export_installed_apps`.
+/// Returns once everything is queued; `import-progress` follows each app and
+/// `import-completed` carries the final counts.
+#[tauri::command]
+pub async fn import_app_manifest(app: tauri::AppHandle, path: String) -> Result<(), StoreError> {
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read manifest {}: {}", path, e))?;
+    let manifest: AppManifest = serde_json::from_str(&text)
+        .map_err(|e| StoreError::InvalidInput(format!("Invalid manifest: {}", e)))?;
+    if manifest.version > MANIFEST_VERSION {
+        return Err(StoreError::InvalidInput(format!(
+            "Unsupported manifest version {}",
+            manifest.version
+        )));
+    }
+
+    let installed: HashSet<String> = list_installed_entries(&app)
+        .await?
+        .into_iter()
+        .map(|entry| entry.app_id)
+        .collect();
+
+    let mut summary = ImportSummary::default();
+    let mut pending = Vec::new();
+    for entry in manifest.apps {
+        if installed.contains(&entry.app_id) {
+            summary.already_installed += 1;
+            emit_progress(&app, &entry.app_id, ImportStatus::AlreadyInstalled);
+            continue;
+        }
+
+        let op = QueuedOp {
+            id: 0,
+            kind: OpKind::Install,
+            app_id: entry.app_id.clone(),
+            remote: Some(entry.remote),
+            scope: Some(entry.scope),
+        };
+        match queue::enqueue_and_watch(&app, op).await {
+            Ok(outcome) => {
+                emit_progress(&app, &entry.app_id, ImportStatus::Queued);
+                pending.push((entry.app_id, outcome));
+            }
+            Err(e) => {
+                log::warn!("[import_app_manifest] Skipping {}: {}", entry.app_id, e);
+                summary.failed += 1;
+                emit_progress(&app, &entry.app_id, ImportStatus::Failed);
+            }
+        }
+    }
+
+    // The queue runs the installs one by one; report each as it finishes
+    tauri::async_runtime::spawn(async move {
+        for (app_id, outcome) in pending {
+            if outcome.await.unwrap_or(false) {
+                summary.succeeded += 1;
+                emit_progress(&app, &app_id, ImportStatus::Installed);
+            } else {
+                summary.failed += 1;
+                emit_progress(&app, &app_id, ImportStatus::Failed);
+            }
+        }
+        log::info!(
+            "[import_app_manifest] {} installed, {} failed, {} already installed",
+            summary.succeeded,
+            summary.failed,
+            summary.already_installed
+        );
+        let _ = app.emit(IMPORT_COMPLETED_EVENT, summary);
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_entries_keep_origin_and_scope() {
+        let output = "org.mozilla.firefox\tflathub\tstable\tsystem\n\
+                      org.gnome.Builder\tgnome-nightly\tmaster\tuser\n";
+        let entries = parse_manifest_entries(output);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].remote, "flathub");
+        assert_eq!(entries[0].scope, InstallScope::System);
+        assert_eq!(entries[1].remote, "gnome-nightly");
+        assert_eq!(entries[1].scope, InstallScope::User);
+    }
+}
@@ -0,0 +1,200 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::Manager;
+use tauri_plugin_shell::ShellExt;
+
+use crate::error::StoreError;
+
+// ─── Version ─────────────────────────────────────────────────────────────────
+
+type Version = (u32, u32, u32);
+
+/// Flatpak features the store uses that older releases lack.
+#[derive(Debug, Clone, Copy)]
+pub enum Feature {
+    /// `flatpak mask`
+    Mask,
+    /// `flatpak update --commit` with the history from `remote-info --log`
+    Downgrade,
+    /// `flatpak install --include-sdk`
+    IncludeSdk,
+    /// `flatpak uninstall --delete-data`
+    DeleteData,
+}
+
+impl Feature {
+    fn min_version(self) -> Version {
+        match self {
+            Feature::Mask => (1, 7, 1),
+            Feature::Downgrade => (1, 0, 0),
+            Feature::IncludeSdk => (1, 13, 1),
+            Feature::DeleteData => (1, 3, 2),
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Feature::Mask => "flatpak mask",
+            Feature::Downgrade => "flatpak update --commit",
+            Feature::IncludeSdk => "flatpak install --include-sdk",
+            Feature::DeleteData => "flatpak uninstall --delete-data",
+        }
+    }
+}
+
+// Helper function to read the version from `flatpak --version` ("Flatpak 1.14.4").
+// Missing or non-numeric parts count as 0, so "1.15" and "1.15.0~rc1" still parse.
+fn parse_flatpak_version(output: &str) -> Option<Version> {
+    let version = output.split_whitespace().last()?;
+    let mut parts = version.split('.').map(|part| {
+        let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+        digits.parse::<u32>().ok()
+    });
+    let major = parts.next()??;
+    let minor = parts.next().flatten().unwrap_or(0);
+    let patch = parts.next().flatten().unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+// ─── Capabilities ────────────────────────────────────────────────────────────
+
+/// What the host's flatpak supports, so callers can check before passing newer flags.
+#[derive(Debug, Clone, Serialize)]
+pub struct Capabilities {
+    pub version: String,
+    pub mask: bool,
+    pub downgrade: bool,
+    pub include_sdk: bool,
+    pub delete_data: bool,
+}
+
+impl Capabilities {
+    fn from_version(version: Version) -> Self {
+        let has = |feature: Feature| version >= feature.min_version();
+        Self {
+            version: format!("{}.{}.{}", version.0, version.1, version.2),
+            mask: has(Feature::Mask),
+            downgrade: has(Feature::Downgrade),
+            include_sdk: has(Feature::IncludeSdk),
+            delete_data: has(Feature::DeleteData),
+        }
+    }
+
+    pub fn supports(&self, feature: Feature) -> bool {
+        match feature {
+            Feature::Mask => self.mask,
+            Feature::Downgrade => self.downgrade,
+            Feature::IncludeSdk => self.include_sdk,
+            Feature::DeleteData => self.delete_data,
+        }
+    }
+}
+
+/// Capabilities detected this session; flatpak isn't upgraded under a running store.
+#[derive(Default)]
+pub struct CapabilitiesCache(Mutex<Option<Capabilities>>);
+
+async fn detect(app: &tauri::AppHandle) -> Result<Capabilities, StoreError> {
+    let cached = app.state::<CapabilitiesCache>().0.lock().unwrap().clone();
+    if let Some(capabilities) = cached {
+        return Ok(capabilities);
+    }
+
+    let shell = app.shell();
+
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    let output = if is_flatpak {
+        // Inside flatpak, use flatpak-spawn to execute on the host
+        shell
+            .command("flatpak-spawn")
+            .args(["--host", "flatpak", "--version"])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
+    } else {
+        // Outside flatpak, use flatpak directly
+        shell
+            .command("flatpak")
+            .args(["--version"])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak", e))?
+    };
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = parse_flatpak_version(&stdout).ok_or_else(|| {
+        StoreError::Internal(format!("Unrecognized flatpak version: {}", stdout.trim()))
+    })?;
+    let capabilities = Capabilities::from_version(version);
+    log::info!("[capabilities] Detected flatpak {}", capabilities.version);
+
+    *app.state::<CapabilitiesCache>().0.lock().unwrap() = Some(capabilities.clone());
+    Ok(capabilities)
+}
+
+/// Fail with `Unsupported` when the host's flatpak is too old for `feature`.
+/// If the version can't be detected the command runs anyway and reports its own error.
+pub async fn require(app: &tauri::AppHandle, feature: Feature) -> Result<(), StoreError> {
+    let capabilities = match detect(app).await {
+        Ok(capabilities) => capabilities,
+        Err(e) => {
+            log::warn!("[capabilities] Can't detect flatpak version: {}", e);
+            return Ok(());
+        }
+    };
+    if capabilities.supports(feature) {
+        return Ok(());
+    }
+
+    let (major, minor, patch) = feature.min_version();
+    Err(StoreError::Unsupported(format!(
+        "{} requires flatpak {}.{}.{} or newer, found {}",
+        feature.description(),
+        major,
+        minor,
+        patch,
+        capabilities.version
+    )))
+}
+
+// ─── Commands ────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_flatpak_capabilities(app: tauri::AppHandle) -> Result<Capabilities, StoreError> {
+    detect(&app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_parse_from_flatpak_output() {
+        assert_eq!(parse_flatpak_version("Flatpak 1.14.4\n"), Some((1, 14, 4)));
+        assert_eq!(parse_flatpak_version("Flatpak 1.15"), Some((1, 15, 0)));
+        assert_eq!(
+            parse_flatpak_version("Flatpak 1.15.0~rc1"),
+            Some((1, 15, 0))
+        );
+        assert_eq!(parse_flatpak_version("Flatpak"), None);
+        assert_eq!(parse_flatpak_version(""), None);
+    }
+
+    #[test]
+    fn old_flatpak_lacks_newer_features() {
+        let old = Capabilities::from_version((1, 6, 5));
+        assert!(old.downgrade && old.delete_data);
+        assert!(!old.mask && !old.include_sdk);
+
+        let current = Capabilities::from_version((1, 14, 4));
+        assert!(current.mask && current.include_sdk);
+        assert_eq!(current.version, "1.14.4");
+    }
+}
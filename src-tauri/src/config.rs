@@ -0,0 +1,282 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use crate::error::StoreError;
+
+// ─── Schema ──────────────────────────────────────────────────────────────────
+
+const CONFIG_FILE: &str = "appConf.json";
+pub const CONFIG_VERSION: &str = "1.8.0";
+
+const DEFAULT_UPDATE_CHECK_INTERVAL_MINUTES: u64 = 360;
+const DEFAULT_HTTP_CONNECT_TIMEOUT_SECS: u64 = 5;
+const DEFAULT_HTTP_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_FLATHUB_REQUESTS_PER_SECOND: u32 = 10;
+
+/// Contents of appConf.json. Keys are camelCase to match the file written by
+/// earlier releases; missing keys take their default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Config {
+    pub initialized: bool,
+    pub version: String,
+    pub first_launch_completed: bool,
+    /// Minutes between background update checks, 0 disables them.
+    pub update_check_interval_minutes: u64,
+    /// Default cap on installs/updates downloading at once, None for no limit.
+    pub max_parallel_downloads: Option<u32>,
+    /// Proxy for the backend's HTTP requests, None to use the environment.
+    pub proxy_url: Option<String>,
+    /// Seconds to wait for an HTTP connection to open.
+    pub http_connect_timeout_secs: u64,
+    /// Seconds an HTTP request may take in total, body included.
+    pub http_timeout_secs: u64,
+    /// Show a desktop notification when an install or update finishes in the background.
+    pub notifications_enabled: bool,
+    /// Closing the window hides it to the tray instead of quitting. Ignored where
+    /// there is no tray, such as the Flatpak build, which lacks libayatana-appindicator.
+    pub minimize_to_tray: bool,
+    /// Directory for the image cache, None for cacheImages in the app data directory.
+    pub cache_dir_override: Option<String>,
+    /// Cap on requests to the flathub API per second, 0 for no limit.
+    pub flathub_requests_per_second: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            initialized: false,
+            version: CONFIG_VERSION.to_string(),
+            first_launch_completed: false,
+            update_check_interval_minutes: DEFAULT_UPDATE_CHECK_INTERVAL_MINUTES,
+            max_parallel_downloads: None,
+            proxy_url: None,
+            http_connect_timeout_secs: DEFAULT_HTTP_CONNECT_TIMEOUT_SECS,
+            http_timeout_secs: DEFAULT_HTTP_TIMEOUT_SECS,
+            notifications_enabled: true,
+            minimize_to_tray: false,
+            cache_dir_override: None,
+            flathub_requests_per_second: DEFAULT_FLATHUB_REQUESTS_PER_SECOND,
+        }
+    }
+}
+
+// ─── Migrations ──────────────────────────────────────────────────────────────
+
+type Migration = fn(&mut Map<String, Value>);
+
+// Applied in order; each entry upgrades a config at `from` to `to`
+const MIGRATIONS: &[(&str, &str, Migration)] = &[
+    ("1.0.0", "1.1.0", migrate_1_0_0),
+    ("1.1.0", "1.2.0", migrate_1_1_0),
+    ("1.2.0", "1.3.0", migrate_1_2_0),
+    ("1.3.0", "1.4.0", migrate_1_3_0),
+    ("1.4.0", "1.5.0", migrate_1_4_0),
+    ("1.5.0", "1.6.0", migrate_1_5_0),
+    ("1.6.0", "1.7.0", migrate_1_6_0),
+    ("1.7.0", "1.8.0", migrate_1_7_0),
+];
+
+// 1.1.0 added the background update check
+fn migrate_1_0_0(config: &mut Map<String, Value>) {
+    config
+        .entry("updateCheckIntervalMinutes")
+        .or_insert(json!(DEFAULT_UPDATE_CHECK_INTERVAL_MINUTES));
+}
+
+// 1.2.0 added the download limit, unlimited by default
+fn migrate_1_1_0(config: &mut Map<String, Value>) {
+    config.entry("maxParallelDownloads").or_insert(Value::Null);
+}
+
+// 1.3.0 added the proxy setting
+fn migrate_1_2_0(config: &mut Map<String, Value>) {
+    config.entry("proxyUrl").or_insert(Value::Null);
+}
+
+// 1.4.0 added the HTTP timeouts
+fn migrate_1_3_0(config: &mut Map<String, Value>) {
+    config
+        .entry("httpConnectTimeoutSecs")
+        .or_insert(json!(DEFAULT_HTTP_CONNECT_TIMEOUT_SECS));
+    config
+        .entry("httpTimeoutSecs")
+        .or_insert(json!(DEFAULT_HTTP_TIMEOUT_SECS));
+}
+
+// 1.5.0 added completion notifications, on by default
+fn migrate_1_4_0(config: &mut Map<String, Value>) {
+    config.entry("notificationsEnabled").or_insert(json!(true));
+}
+
+// 1.6.0 added the tray, closing still quits by default
+fn migrate_1_5_0(config: &mut Map<String, Value>) {
+    config.entry("minimizeToTray").or_insert(json!(false));
+}
+
+// 1.7.0 made the image cache relocatable
+fn migrate_1_6_0(config: &mut Map<String, Value>) {
+    config.entry("cacheDirOverride").or_insert(Value::Null);
+}
+
+// 1.8.0 rate-limited the flathub API
+fn migrate_1_7_0(config: &mut Map<String, Value>) {
+    config
+        .entry("flathubRequestsPerSecond")
+        .or_insert(json!(DEFAULT_FLATHUB_REQUESTS_PER_SECOND));
+}
+
+/// Bring a config written by an older release up to `CONFIG_VERSION`.
+/// Returns whether anything changed. Files without a version predate it and
+/// count as 1.0.0.
+fn migrate_config(config: &mut Map<String, Value>) -> bool {
+    let mut migrated = false;
+    for (from, to, migrate) in MIGRATIONS {
+        let version = config
+            .get("version")
+            .and_then(Value::as_str)
+            .unwrap_or("1.0.0");
+        if version == *from {
+            migrate(config);
+            config.insert("version".to_string(), json!(to));
+            migrated = true;
+        }
+    }
+    migrated
+}
+
+// ─── File access ─────────────────────────────────────────────────────────────
+
+fn config_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data_dir.join(CONFIG_FILE))
+}
+
+/// Read the config, writing defaults when the file is missing and migrating
+/// older versions in place. A file that doesn't parse is kept as
+/// `appConf.json.bak` and replaced with defaults.
+pub fn load(app: &tauri::AppHandle) -> Result<Config, String> {
+    let path = config_path(app)?;
+
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let config = Config::default();
+            save(app, &config)?;
+            return Ok(config);
+        }
+        Err(e) => return Err(format!("Failed to read config file: {}", e)),
+    };
+
+    let parsed = serde_json::from_str::<Value>(&text).and_then(|value| match value {
+        Value::Object(mut map) => {
+            let migrated = migrate_config(&mut map);
+            serde_json::from_value::<Config>(Value::Object(map)).map(|c| (c, migrated))
+        }
+        _ => Err(serde::de::Error::custom("config is not a JSON object")),
+    });
+
+    match parsed {
+        Ok((config, migrated)) => {
+            if migrated {
+                log::info!("[config] Migrated {} to {}", CONFIG_FILE, config.version);
+                save(app, &config)?;
+            }
+            Ok(config)
+        }
+        Err(e) => {
+            log::warn!(
+                "[config] Malformed {}, resetting to defaults: {}",
+                CONFIG_FILE,
+                e
+            );
+            let backup = path.with_extension("json.bak");
+            fs::rename(&path, &backup)
+                .map_err(|e| format!("Failed to back up config file: {}", e))?;
+            let config = Config::default();
+            save(app, &config)?;
+            Ok(config)
+        }
+    }
+}
+
+pub fn save(app: &tauri::AppHandle, config: &Config) -> Result<(), String> {
+    let path = config_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    crate::write_atomic(&path, content.as_bytes())
+        .map_err(|e| format!("Failed to write config file: {}", e))
+}
+
+// ─── Commands ────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_config(app: tauri::AppHandle) -> Result<Config, StoreError> {
+    Ok(load(&app)?)
+}
+
+#[tauri::command]
+pub fn update_config(app: tauri::AppHandle, config: Config) -> Result<(), StoreError> {
+    // Rejects a bad proxy URL before anything is saved
+    crate::http::apply(&app, &config).map_err(StoreError::InvalidInput)?;
+    save(&app, &config)?;
+    // The update interval may have changed
+    app.state::<crate::scheduler::UpdateScheduler>()
+        .reschedule();
+    app.state::<crate::flathub::RateLimiter>()
+        .set_rate(config.flathub_requests_per_second);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_1_0_0_config() {
+        let mut map = match json!({
+            "initialized": true,
+            "version": "1.0.0",
+            "firstLaunchCompleted": true
+        }) {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        };
+
+        assert!(migrate_config(&mut map));
+        let config: Config = serde_json::from_value(Value::Object(map)).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert!(config.first_launch_completed);
+        assert_eq!(
+            config.update_check_interval_minutes,
+            DEFAULT_UPDATE_CHECK_INTERVAL_MINUTES
+        );
+        assert_eq!(config.max_parallel_downloads, None);
+        assert_eq!(config.http_timeout_secs, DEFAULT_HTTP_TIMEOUT_SECS);
+        assert!(config.notifications_enabled);
+        assert!(!config.minimize_to_tray);
+        assert_eq!(config.cache_dir_override, None);
+        assert_eq!(
+            config.flathub_requests_per_second,
+            DEFAULT_FLATHUB_REQUESTS_PER_SECOND
+        );
+    }
+
+    #[test]
+    fn current_config_is_left_alone() {
+        let mut map = Map::new();
+        map.insert("version".to_string(), json!(CONFIG_VERSION));
+        assert!(!migrate_config(&mut map));
+    }
+}
@@ -0,0 +1,176 @@
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::Emitter;
+
+// ─── appstream:// links ──────────────────────────────────────────────────────
+
+pub const OPEN_APP_EVENT: &str = "open-app";
+
+const SOCKET_NAME: &str = "klia-store.sock";
+
+// Set once init has bound the socket, so only its owner removes it on exit
+static OWNS_SOCKET: AtomicBool = AtomicBool::new(false);
+
+/// App id from an `appstream://org.example.App` link. Some sites append the
+/// desktop file suffix or a trailing slash; both are dropped.
+pub fn parse_appstream_url(url: &str) -> Option<String> {
+    let id = url
+        .strip_prefix("appstream://")
+        .or_else(|| url.strip_prefix("appstream:"))?
+        .trim_end_matches('/');
+    let id = id.strip_suffix(".desktop").unwrap_or(id);
+    if crate::is_valid_flatpak_id(id) && id.contains('.') && !id.starts_with('.') {
+        Some(id.to_string())
+    } else {
+        None
+    }
+}
+
+/// The desktop file launches us with %u, so files opened from a file manager
+/// may arrive as file:// URIs; turn those back into paths.
+pub fn local_path_arg(arg: &str) -> String {
+    let Some(path) = arg.strip_prefix("file://") else {
+        return arg.to_string();
+    };
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn find_appstream_arg(args: &[String]) -> Option<String> {
+    args.iter().skip(1).find_map(|arg| parse_appstream_url(arg))
+}
+
+// Inside the sandbox every instance gets its own XDG_RUNTIME_DIR; only the
+// app/<FLATPAK_ID> subdirectory is shared between them
+fn socket_path() -> PathBuf {
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| std::env::temp_dir());
+    match std::env::var("FLATPAK_ID") {
+        Ok(flatpak_id) => runtime_dir.join("app").join(flatpak_id).join(SOCKET_NAME),
+        Err(_) => runtime_dir.join(SOCKET_NAME),
+    }
+}
+
+// ─── Single instance ─────────────────────────────────────────────────────────
+
+/// When launched with an appstream:// link while the store is already open,
+/// hand the link to the running instance. Returns true if it took it, in which
+/// case this process should exit.
+pub fn forward_to_running_instance() -> bool {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(app_id) = find_appstream_arg(&args) else {
+        return false;
+    };
+    match UnixStream::connect(socket_path()) {
+        Ok(mut stream) => writeln!(stream, "{}", app_id).is_ok(),
+        // Nothing is listening: this is the first instance
+        Err(_) => false,
+    }
+}
+
+fn open_app(app: &tauri::AppHandle, app_id: String) {
+    log::info!("[deeplink] Opening {}", app_id);
+    crate::tray::show_main_window(app);
+    let _ = app.emit(OPEN_APP_EVENT, app_id);
+}
+
+/// Remove the socket on exit so the next launch doesn't try to forward to it.
+/// An instance that couldn't bind it leaves the running one's socket alone.
+pub fn cleanup() {
+    if OWNS_SOCKET.load(Ordering::SeqCst) {
+        let _ = std::fs::remove_file(socket_path());
+    }
+}
+
+/// Accept links from later launches and handle the one this instance was
+/// started with. Called once from setup.
+pub fn init(app: &tauri::AppHandle) {
+    let path = socket_path();
+    // A socket left behind by a crashed instance refuses connections
+    if UnixStream::connect(&path).is_err() {
+        let _ = std::fs::remove_file(&path);
+    }
+    match UnixListener::bind(&path) {
+        Ok(listener) => {
+            OWNS_SOCKET.store(true, Ordering::SeqCst);
+            let app = app.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let mut line = String::new();
+                    let read = BufReader::new(stream).read_line(&mut line).is_ok();
+                    if read && crate::is_valid_flatpak_id(line.trim()) {
+                        open_app(&app, line.trim().to_string());
+                    }
+                }
+            });
+        }
+        Err(e) => log::warn!("[deeplink] Can't listen on {}: {}", path.display(), e),
+    }
+
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(app_id) = find_appstream_arg(&args) {
+        let app = app.clone();
+        // Delay slightly so the frontend has time to mount listeners
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(800));
+            open_app(&app, app_id);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn appstream_urls() {
+        assert_eq!(
+            parse_appstream_url("appstream://org.mozilla.firefox").as_deref(),
+            Some("org.mozilla.firefox")
+        );
+        assert_eq!(
+            parse_appstream_url("appstream:com.github.tchx84.Flatseal.desktop").as_deref(),
+            Some("com.github.tchx84.Flatseal")
+        );
+        assert_eq!(
+            parse_appstream_url("appstream://org.gnome.Builder/").as_deref(),
+            Some("org.gnome.Builder")
+        );
+        assert_eq!(parse_appstream_url("appstream://../etc/passwd"), None);
+        assert_eq!(parse_appstream_url("https://flathub.org"), None);
+    }
+
+    #[test]
+    fn file_uris_become_paths() {
+        assert_eq!(
+            local_path_arg("file:///home/me/My%20Apps/app.flatpak"),
+            "/home/me/My Apps/app.flatpak"
+        );
+        assert_eq!(local_path_arg("/tmp/app.flatpakref"), "/tmp/app.flatpakref");
+        assert_eq!(
+            local_path_arg("file:///tmp/100%25.flatpak"),
+            "/tmp/100%.flatpak"
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use tauri_plugin_http::reqwest;
+use tauri::State;
+
+use crate::error::StoreError;
+use crate::http::HttpClient;
 
 // ─── BTC ─────────────────────────────────────────────────────────────────────
 
@@ -6,17 +9,18 @@ use tauri_plugin_http::reqwest;
 /// Checks recent transactions to `wallet` for an output matching `amount_btc`.
 #[tauri::command]
 pub async fn verify_btc_donation(
+    http: State<'_, HttpClient>,
     wallet: String,
     amount_btc: String,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, StoreError> {
     let amount_f64: f64 = amount_btc
         .parse()
-        .map_err(|_| "Invalid BTC amount".to_string())?;
+        .map_err(|_| StoreError::InvalidInput("Invalid BTC amount".to_string()))?;
 
     // 1 BTC = 100_000_000 satoshis
     let target_satoshis = (amount_f64 * 100_000_000.0).round() as u64;
 
-    let client = reqwest::Client::new();
+    let client = http.client();
 
     let endpoints = [
         format!("https://blockstream.info/api/address/{}/txs", wallet),
@@ -72,7 +76,7 @@ pub async fn verify_btc_donation(
         return Ok(serde_json::json!({ "found": false }));
     }
 
-    Err("All BTC API endpoints failed".to_string())
+    Err(StoreError::NetworkError("All BTC API endpoints failed".to_string()))
 }
 
 // ─── USDT on Ethereum ────────────────────────────────────────────────────────
@@ -81,12 +85,13 @@ pub async fn verify_btc_donation(
 /// Checks Transfer events to `wallet` in the last ~17 h of blocks (~5000).
 #[tauri::command]
 pub async fn verify_usdt_eth_donation(
+    http: State<'_, HttpClient>,
     wallet: String,
     amount_usdt: String,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, StoreError> {
     let amount_f64: f64 = amount_usdt
         .parse()
-        .map_err(|_| "Invalid USDT amount".to_string())?;
+        .map_err(|_| StoreError::InvalidInput("Invalid USDT amount".to_string()))?;
 
     // USDT has 6 decimals
     let target_raw = (amount_f64 * 1_000_000.0).round() as u128;
@@ -108,7 +113,7 @@ pub async fn verify_usdt_eth_donation(
         wallet.trim_start_matches("0x").to_lowercase()
     );
 
-    let client = reqwest::Client::new();
+    let client = http.client();
 
     for rpc_url in &rpc_endpoints {
         // 1. Get latest block number
@@ -215,5 +220,5 @@ pub async fn verify_usdt_eth_donation(
         return Ok(serde_json::json!({ "found": false }));
     }
 
-    Err("All Ethereum RPC endpoints failed".to_string())
+    Err(StoreError::NetworkError("All Ethereum RPC endpoints failed".to_string()))
 }
@@ -0,0 +1,148 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use tauri_plugin_http::reqwest;
+
+// ─── StoreError ──────────────────────────────────────────────────────────────
+
+/// Error returned by every store command. Serializes as `{ code, message }`
+/// where `code` is stable and meant for the frontend to map to translations.
+#[derive(Debug)]
+pub enum StoreError {
+    /// flatpak (or flatpak-spawn inside the sandbox) isn't installed.
+    FlatpakNotFound,
+    NetworkError(String),
+    /// An HTTP request hit the client's connect or overall timeout.
+    Timeout(String),
+    PermissionDenied(String),
+    NotInstalled(String),
+    /// Flathub doesn't know the requested app.
+    NotFound(String),
+    InvalidInput(String),
+    /// A remote can't be removed while these refs are installed from it.
+    RemoteInUse(Vec<String>),
+    /// HTTP error status that isn't worth retrying (404 and friends).
+    HttpError(u16),
+    /// A flatpak command exited with this code (plus whatever it printed to stderr).
+    CommandFailed(i32, String),
+    /// The user cancelled the operation before it finished.
+    Cancelled(String),
+    /// The installed flatpak is too old for the requested feature.
+    Unsupported(String),
+    Internal(String),
+}
+
+impl StoreError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            StoreError::FlatpakNotFound => "FLATPAK_NOT_FOUND",
+            StoreError::NetworkError(_) => "NETWORK_ERROR",
+            StoreError::Timeout(_) => "TIMEOUT",
+            StoreError::PermissionDenied(_) => "PERMISSION_DENIED",
+            StoreError::NotInstalled(_) => "NOT_INSTALLED",
+            StoreError::NotFound(_) => "NOT_FOUND",
+            StoreError::InvalidInput(_) => "INVALID_INPUT",
+            StoreError::RemoteInUse(_) => "REMOTE_IN_USE",
+            StoreError::HttpError(_) => "HTTP_ERROR",
+            StoreError::CommandFailed(..) => "COMMAND_FAILED",
+            StoreError::Cancelled(_) => "CANCELLED",
+            StoreError::Unsupported(_) => "UNSUPPORTED",
+            StoreError::Internal(_) => "INTERNAL",
+        }
+    }
+
+    /// Classify a failed HTTP request, keeping timeouts apart from other network errors.
+    pub fn request(context: &str, e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            StoreError::Timeout(format!("{}: {}", context, e))
+        } else {
+            StoreError::NetworkError(format!("{}: {}", context, e))
+        }
+    }
+
+    /// Classify a failure to spawn flatpak/flatpak-spawn. A missing binary
+    /// shows up as "No such file or directory" from the OS.
+    pub fn spawn(program: &str, e: impl fmt::Display) -> Self {
+        let message = e.to_string();
+        if message.contains("No such file or directory") || message.contains("os error 2") {
+            StoreError::FlatpakNotFound
+        } else {
+            StoreError::Internal(format!("Failed to execute {}: {}", program, message))
+        }
+    }
+
+    /// Classify a flatpak command that ran but exited unsuccessfully.
+    pub fn flatpak_failed(exit_code: Option<i32>, stderr: &str) -> Self {
+        let stderr = stderr.trim().to_string();
+        let lower = stderr.to_lowercase();
+        if lower.contains("not installed") {
+            StoreError::NotInstalled(stderr)
+        } else if lower.contains("not allowed")
+            || lower.contains("not authorized")
+            || lower.contains("permission denied")
+        {
+            StoreError::PermissionDenied(stderr)
+        } else if lower.contains("could not resolve")
+            || lower.contains("unable to connect")
+            || lower.contains("while fetching")
+        {
+            StoreError::NetworkError(stderr)
+        } else {
+            StoreError::CommandFailed(exit_code.unwrap_or(-1), stderr)
+        }
+    }
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::FlatpakNotFound => write!(f, "Flatpak is not installed or not reachable"),
+            StoreError::NetworkError(msg) => write!(f, "Network error: {}", msg),
+            StoreError::Timeout(msg) => write!(f, "Timed out: {}", msg),
+            StoreError::PermissionDenied(msg) => write!(f, "Permission denied: {}", msg),
+            StoreError::NotInstalled(msg) => write!(f, "Not installed: {}", msg),
+            StoreError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            StoreError::InvalidInput(msg) => write!(f, "{}", msg),
+            StoreError::RemoteInUse(ids) => {
+                write!(f, "Still installed from this remote: {}", ids.join(", "))
+            }
+            StoreError::HttpError(status) => write!(f, "HTTP Error: {}", status),
+            StoreError::CommandFailed(code, stderr) => {
+                write!(f, "Flatpak command failed ({}): {}", code, stderr)
+            }
+            StoreError::Cancelled(msg) => write!(f, "Cancelled: {}", msg),
+            StoreError::Unsupported(msg) => write!(f, "Not supported: {}", msg),
+            StoreError::Internal(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl Serialize for StoreError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("StoreError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+// Helpers still return Result<_, String>; `?` turns those messages into Internal.
+impl From<String> for StoreError {
+    fn from(message: String) -> Self {
+        StoreError::Internal(message)
+    }
+}
+
+impl From<&str> for StoreError {
+    fn from(message: &str) -> Self {
+        StoreError::Internal(message.to_string())
+    }
+}
+
+impl From<StoreError> for String {
+    fn from(error: StoreError) -> Self {
+        error.to_string()
+    }
+}
@@ -0,0 +1,80 @@
+use sqlx::sqlite::SqlitePool;
+use tauri::State;
+
+use crate::error::StoreError;
+use crate::history::{self, HistoryDb};
+
+// ─── Database ────────────────────────────────────────────────────────────────
+
+/// The `favorites` table, created here if the first-run setup hasn't already.
+/// Same definition as the frontend's setup so either side can create it.
+async fn pool<'a>(app: &tauri::AppHandle, db: &'a HistoryDb) -> Result<&'a SqlitePool, StoreError> {
+    let pool = history::pool(app, db).await?;
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS favorites (
+            app_id TEXT PRIMARY KEY,
+            added_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to create favorites table: {}", e))?;
+    Ok(pool)
+}
+
+fn check_app_id(app_id: &str) -> Result<(), StoreError> {
+    if crate::is_valid_flatpak_id(app_id) {
+        Ok(())
+    } else {
+        Err(StoreError::InvalidInput(format!(
+            "Invalid app id: {}",
+            app_id
+        )))
+    }
+}
+
+// ─── Commands ────────────────────────────────────────────────────────────────
+
+/// Favorite an app, installed or not. Favoriting it again keeps the original date.
+#[tauri::command]
+pub async fn add_favorite(
+    app: tauri::AppHandle,
+    db: State<'_, HistoryDb>,
+    app_id: String,
+) -> Result<(), StoreError> {
+    check_app_id(&app_id)?;
+    sqlx::query("INSERT OR IGNORE INTO favorites (app_id) VALUES (?)")
+        .bind(&app_id)
+        .execute(pool(&app, db.inner()).await?)
+        .await
+        .map_err(|e| format!("Failed to add favorite: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_favorite(
+    app: tauri::AppHandle,
+    db: State<'_, HistoryDb>,
+    app_id: String,
+) -> Result<(), StoreError> {
+    sqlx::query("DELETE FROM favorites WHERE app_id = ?")
+        .bind(&app_id)
+        .execute(pool(&app, db.inner()).await?)
+        .await
+        .map_err(|e| format!("Failed to remove favorite: {}", e))?;
+    Ok(())
+}
+
+/// Favorite app ids, most recently added first.
+#[tauri::command]
+pub async fn get_favorites(
+    app: tauri::AppHandle,
+    db: State<'_, HistoryDb>,
+) -> Result<Vec<String>, StoreError> {
+    let rows: Vec<(String,)> =
+        sqlx::query_as("SELECT app_id FROM favorites ORDER BY added_at DESC, rowid DESC")
+            .fetch_all(pool(&app, db.inner()).await?)
+            .await
+            .map_err(|e| format!("Failed to read favorites: {}", e))?;
+    Ok(rows.into_iter().map(|(app_id,)| app_id).collect())
+}
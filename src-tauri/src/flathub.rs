@@ -0,0 +1,877 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Manager, State};
+use tauri_plugin_http::reqwest;
+
+use crate::error::StoreError;
+use crate::http::HttpClient;
+
+const FLATHUB_API_URL: &str = "https://flathub.org/api/v2";
+
+fn check_app_id(app_id: &str) -> Result<(), StoreError> {
+    if crate::is_valid_flatpak_id(app_id) {
+        Ok(())
+    } else {
+        Err(StoreError::InvalidInput(format!(
+            "Invalid app id: {}",
+            app_id
+        )))
+    }
+}
+
+// ─── Rate limiting ───────────────────────────────────────────────────────────
+
+struct Bucket {
+    /// Requests per second, 0 for no limit. Also the burst size.
+    rate: u32,
+    /// Negative when callers are already waiting for future tokens.
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    // Take a token, possibly one that only refills later, and return how long
+    // the caller has to wait for it. Reserving keeps waiting callers in order.
+    fn reserve(&mut self, now: Instant) -> Duration {
+        if self.rate == 0 {
+            return Duration::ZERO;
+        }
+        let rate = f64::from(self.rate);
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * rate).min(rate);
+        self.refilled_at = now;
+
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / rate)
+        }
+    }
+}
+
+/// Token bucket every flathub API request goes through, so quick navigation
+/// queues requests instead of getting the client rate-limited by flathub.
+/// The rate comes from `flathubRequestsPerSecond` in the config.
+pub struct RateLimiter(Mutex<Bucket>);
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        let rate = crate::config::Config::default().flathub_requests_per_second;
+        Self(Mutex::new(Bucket {
+            rate,
+            tokens: f64::from(rate),
+            refilled_at: Instant::now(),
+        }))
+    }
+}
+
+impl RateLimiter {
+    pub fn set_rate(&self, rate: u32) {
+        let mut bucket = self.0.lock().unwrap();
+        bucket.rate = rate;
+        bucket.tokens = bucket.tokens.min(f64::from(rate));
+    }
+
+    async fn acquire(&self) {
+        let wait = self.0.lock().unwrap().reserve(Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Apply the rate saved in the config. Called once from setup.
+pub fn init(app: &tauri::AppHandle) {
+    let config = crate::config::load(app).unwrap_or_default();
+    app.state::<RateLimiter>()
+        .set_rate(config.flathub_requests_per_second);
+}
+
+async fn api_send(
+    limiter: &RateLimiter,
+    request: reqwest::RequestBuilder,
+    url: &str,
+) -> Result<reqwest::Response, StoreError> {
+    limiter.acquire().await;
+    request
+        .send()
+        .await
+        .map_err(|e| StoreError::request(&format!("Failed to query {}", url), e))
+}
+
+async fn api_get(
+    client: &reqwest::Client,
+    limiter: &RateLimiter,
+    url: &str,
+) -> Result<reqwest::Response, StoreError> {
+    api_send(limiter, client.get(url), url).await
+}
+
+// ─── Verification ────────────────────────────────────────────────────────────
+
+/// Whether flathub has verified the developer of an app, as returned by
+/// `/verification/<app_id>/status`. Fields other than `verified` depend on
+/// the method used (website or a login on GitHub, GitLab...).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerificationStatus {
+    pub verified: bool,
+    pub method: Option<String>,
+    pub website: Option<String>,
+    pub login_name: Option<String>,
+    pub login_provider: Option<String>,
+    pub login_is_organization: Option<bool>,
+    pub timestamp: Option<String>,
+}
+
+/// Verification results already fetched this session, keyed by app id.
+#[derive(Default)]
+pub struct VerificationCache(Mutex<HashMap<String, VerificationStatus>>);
+
+async fn fetch_verification_status(
+    client: &reqwest::Client,
+    limiter: &RateLimiter,
+    app_id: &str,
+) -> Result<VerificationStatus, StoreError> {
+    let url = format!("{}/verification/{}/status", FLATHUB_API_URL, app_id);
+    let response = api_get(client, limiter, &url).await?;
+
+    // Apps flathub doesn't know about can't be verified
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(VerificationStatus::default());
+    }
+    if !response.status().is_success() {
+        return Err(StoreError::HttpError(response.status().as_u16()));
+    }
+
+    let text = response
+        .text()
+        .await
+        .map_err(|e| StoreError::request("Failed to read response", e))?;
+    Ok(serde_json::from_str(&text)
+        .map_err(|e| format!("Failed to parse verification status: {}", e))?)
+}
+
+// ─── Screenshots ─────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Screenshot {
+    pub thumb_url: String,
+    pub full_url: String,
+    pub caption: Option<String>,
+}
+
+// Each size as (pixels, url). Flathub lists sizes either as objects with
+// width/height/src or, in older responses, as a "WIDTHxHEIGHT": url map.
+fn screenshot_sizes(sizes: &Value) -> Vec<(u64, String)> {
+    let dimension = |v: &Value| {
+        v.as_u64()
+            .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+            .unwrap_or(0)
+    };
+
+    match sizes {
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|item| {
+                let src = item["src"].as_str()?;
+                Some((
+                    dimension(&item["width"]) * dimension(&item["height"]),
+                    src.to_string(),
+                ))
+            })
+            .collect(),
+        Value::Object(map) => map
+            .iter()
+            .filter_map(|(size, url)| {
+                let (w, h) = size.split_once('x')?;
+                let pixels = w.parse::<u64>().ok()? * h.parse::<u64>().ok()?;
+                Some((pixels, url.as_str()?.to_string()))
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Helper function to turn the `screenshots` array of an appstream entry into
+// thumbnail/full-size pairs: the smallest size is the thumbnail, the largest the full image
+fn parse_screenshots(appstream: &Value) -> Vec<Screenshot> {
+    let Some(screenshots) = appstream["screenshots"].as_array() else {
+        return Vec::new();
+    };
+
+    screenshots
+        .iter()
+        .filter_map(|shot| {
+            let sizes = screenshot_sizes(&shot["sizes"]);
+            let thumb = sizes.iter().min_by_key(|(pixels, _)| *pixels)?;
+            let full = sizes.iter().max_by_key(|(pixels, _)| *pixels)?;
+            Some(Screenshot {
+                thumb_url: thumb.1.clone(),
+                full_url: full.1.clone(),
+                caption: shot["caption"]
+                    .as_str()
+                    .filter(|c| !c.is_empty())
+                    .map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+// ─── Releases ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Release {
+    pub version: String,
+    /// Unix timestamp (seconds).
+    pub date: Option<i64>,
+    /// Appstream markup (<p>, <ul>, <li>...), None when the release has no notes.
+    pub description: Option<String>,
+}
+
+// Helper function to read the `releases` array of an appstream entry, newest first.
+// Timestamps come as strings or numbers depending on the app.
+fn parse_releases(appstream: &Value) -> Vec<Release> {
+    let Some(releases) = appstream["releases"].as_array() else {
+        return Vec::new();
+    };
+
+    let mut parsed: Vec<Release> = releases
+        .iter()
+        .filter_map(|release| {
+            let version = release["version"].as_str()?.to_string();
+            let date = release["timestamp"]
+                .as_i64()
+                .or_else(|| release["timestamp"].as_str()?.parse().ok());
+            let description = release["description"]
+                .as_str()
+                .map(str::trim)
+                .filter(|d| !d.is_empty())
+                .map(str::to_string);
+            Some(Release {
+                version,
+                date,
+                description,
+            })
+        })
+        .collect();
+
+    parsed.sort_by_key(|release| std::cmp::Reverse(release.date));
+    parsed
+}
+
+async fn fetch_appstream(
+    client: &reqwest::Client,
+    limiter: &RateLimiter,
+    app_id: &str,
+) -> Result<Value, StoreError> {
+    let url = format!("{}/appstream/{}", FLATHUB_API_URL, app_id);
+    let response = api_get(client, limiter, &url).await?;
+
+    if !response.status().is_success() {
+        return Err(StoreError::HttpError(response.status().as_u16()));
+    }
+
+    let text = response
+        .text()
+        .await
+        .map_err(|e| StoreError::request("Failed to read response", e))?;
+    Ok(serde_json::from_str(&text).map_err(|e| format!("Failed to parse appstream: {}", e))?)
+}
+
+// ─── Details ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AppUrls {
+    pub homepage: Option<String>,
+    pub bugtracker: Option<String>,
+    pub donation: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppDetails {
+    pub app_id: String,
+    pub name: String,
+    pub summary: String,
+    /// Appstream markup (<p>, <ul>, <li>...).
+    pub description: Option<String>,
+    pub developer: Option<String>,
+    pub license: Option<String>,
+    pub categories: Vec<String>,
+    pub urls: AppUrls,
+    pub icon_url: Option<String>,
+}
+
+// Helper function to pick the fields of an appstream entry the detail page
+// shows. Older entries only have `developer_name`, newer ones a `developer` object.
+fn parse_app_details(app_id: &str, appstream: &Value) -> AppDetails {
+    let text = |v: &Value| {
+        v.as_str()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let icon_url = text(&appstream["icon"]).or_else(|| {
+        appstream["icons"]
+            .as_array()?
+            .iter()
+            .filter_map(|icon| Some((icon["width"].as_u64().unwrap_or(0), text(&icon["url"])?)))
+            .max_by_key(|(width, _)| *width)
+            .map(|(_, url)| url)
+    });
+
+    AppDetails {
+        app_id: app_id.to_string(),
+        name: text(&appstream["name"]).unwrap_or_else(|| app_id.to_string()),
+        summary: text(&appstream["summary"]).unwrap_or_default(),
+        description: text(&appstream["description"]),
+        developer: text(&appstream["developer"]["name"])
+            .or_else(|| text(&appstream["developer_name"])),
+        license: text(&appstream["project_license"]),
+        categories: appstream["categories"]
+            .as_array()
+            .map(|categories| categories.iter().filter_map(text).collect())
+            .unwrap_or_default(),
+        urls: AppUrls {
+            homepage: text(&appstream["urls"]["homepage"]),
+            bugtracker: text(&appstream["urls"]["bugtracker"]),
+            donation: text(&appstream["urls"]["donation"]),
+        },
+        icon_url,
+    }
+}
+
+// ─── Collections ─────────────────────────────────────────────────────────────
+
+const COLLECTION_PAGE_SIZE: u32 = 50;
+const COLLECTION_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppSummary {
+    pub app_id: String,
+    pub name: String,
+    pub summary: String,
+    pub icon_url: Option<String>,
+}
+
+// Category names are single words like "AudioVideo" or "Game"
+fn check_category(category: &str) -> Result<(), StoreError> {
+    if !category.is_empty() && category.chars().all(|c| c.is_ascii_alphanumeric()) {
+        Ok(())
+    } else {
+        Err(StoreError::InvalidInput(format!(
+            "Invalid category: {}",
+            category
+        )))
+    }
+}
+
+// Helper function to read the `hits` of a collection response, skipping
+// entries without an app id
+fn parse_app_summaries(collection: &Value) -> Vec<AppSummary> {
+    let Some(hits) = collection["hits"].as_array() else {
+        return Vec::new();
+    };
+
+    hits.iter()
+        .filter_map(|hit| {
+            let app_id = hit["app_id"].as_str().or_else(|| hit["id"].as_str())?;
+            let text = |key: &str| hit[key].as_str().unwrap_or_default().to_string();
+            Some(AppSummary {
+                app_id: app_id.to_string(),
+                name: text("name"),
+                summary: text("summary"),
+                icon_url: hit["icon"]
+                    .as_str()
+                    .filter(|icon| !icon.is_empty())
+                    .map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+async fn fetch_collection_page(
+    client: &reqwest::Client,
+    limiter: &RateLimiter,
+    collection: &str,
+    page: u32,
+) -> Result<Value, StoreError> {
+    let url = format!(
+        "{}/collection/{}?page={}&per_page={}",
+        FLATHUB_API_URL, collection, page, COLLECTION_PAGE_SIZE
+    );
+    let response = api_get(client, limiter, &url).await?;
+
+    if !response.status().is_success() {
+        return Err(StoreError::HttpError(response.status().as_u16()));
+    }
+
+    let text = response
+        .text()
+        .await
+        .map_err(|e| StoreError::request("Failed to read response", e))?;
+    Ok(serde_json::from_str(&text).map_err(|e| format!("Failed to parse collection: {}", e))?)
+}
+
+// (collection, page) -> when it was fetched and its apps
+type CollectionPages = HashMap<(String, u32), (Instant, Vec<AppSummary>)>;
+
+/// Collection pages fetched in the last few minutes, keyed by collection and
+/// page, so going back and forth on the home page doesn't refetch them.
+#[derive(Default)]
+pub struct CollectionCache(Mutex<CollectionPages>);
+
+async fn collection_page(
+    http: &HttpClient,
+    limiter: &RateLimiter,
+    cache: &CollectionCache,
+    collection: &str,
+    page: u32,
+) -> Result<Vec<AppSummary>, StoreError> {
+    let key = (collection.to_string(), page.max(1));
+    let cached = cache.0.lock().unwrap().get(&key).cloned();
+    if let Some((fetched_at, apps)) = cached {
+        if fetched_at.elapsed() < COLLECTION_CACHE_TTL {
+            return Ok(apps);
+        }
+    }
+
+    let response = fetch_collection_page(&http.client(), limiter, &key.0, key.1).await?;
+    let apps = parse_app_summaries(&response);
+    let mut cache = cache.0.lock().unwrap();
+    cache.retain(|_, (fetched_at, _)| fetched_at.elapsed() < COLLECTION_CACHE_TTL);
+    cache.insert(key, (Instant::now(), apps.clone()));
+    Ok(apps)
+}
+
+// ─── Search ──────────────────────────────────────────────────────────────────
+
+const SEARCH_RESULTS: u32 = 50;
+const LOCAL_STATE_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub app_id: String,
+    pub name: String,
+    pub summary: String,
+    pub icon_url: Option<String>,
+    pub installed: bool,
+    pub update_available: bool,
+}
+
+#[derive(Clone, Default)]
+struct LocalState {
+    installed: HashSet<String>,
+    updates: HashSet<String>,
+}
+
+/// Installed apps and pending updates, read from flatpak at most once a minute
+/// so searching stays fast. Dropped whenever an operation completes.
+#[derive(Default)]
+pub struct LocalStateCache(Mutex<Option<(Instant, LocalState)>>);
+
+impl LocalStateCache {
+    pub fn invalidate(&self) {
+        *self.0.lock().unwrap() = None;
+    }
+}
+
+async fn local_state(app: &tauri::AppHandle) -> Result<LocalState, StoreError> {
+    let cache = app.state::<LocalStateCache>();
+    let cached = cache.0.lock().unwrap().clone();
+    if let Some((read_at, state)) = cached {
+        if read_at.elapsed() < LOCAL_STATE_TTL {
+            return Ok(state);
+        }
+    }
+
+    let installed = crate::get_installed_flatpaks(app.clone(), None, None).await?;
+    // Search still works offline, just without update badges
+    let updates = match crate::get_available_updates(app.clone()).await {
+        Ok(updates) => updates.into_iter().map(|update| update.app_id).collect(),
+        Err(e) => {
+            log::warn!("[search] Failed to list updates: {}", e);
+            HashSet::new()
+        }
+    };
+    let state = LocalState {
+        installed: installed.apps.into_iter().map(|app| app.app_id).collect(),
+        updates,
+    };
+    *cache.0.lock().unwrap() = Some((Instant::now(), state.clone()));
+    Ok(state)
+}
+
+// Annotate results with the local state, keeping the API's relevance order
+fn annotate_hits(apps: Vec<AppSummary>, state: &LocalState) -> Vec<SearchHit> {
+    apps.into_iter()
+        .map(|app| SearchHit {
+            installed: state.installed.contains(&app.app_id),
+            update_available: state.updates.contains(&app.app_id),
+            app_id: app.app_id,
+            name: app.name,
+            summary: app.summary,
+            icon_url: app.icon_url,
+        })
+        .collect()
+}
+
+async fn fetch_search(
+    client: &reqwest::Client,
+    limiter: &RateLimiter,
+    query: &str,
+) -> Result<Value, StoreError> {
+    let url = format!("{}/search", FLATHUB_API_URL);
+    // Sent by hand: the http plugin's reqwest is built without the `json` feature
+    let body = serde_json::json!({
+        "query": query,
+        "hits_per_page": SEARCH_RESULTS,
+    });
+    let request = client
+        .post(&url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body.to_string());
+    let response = api_send(limiter, request, &url).await?;
+
+    if !response.status().is_success() {
+        return Err(StoreError::HttpError(response.status().as_u16()));
+    }
+
+    let text = response
+        .text()
+        .await
+        .map_err(|e| StoreError::request("Failed to read response", e))?;
+    Ok(serde_json::from_str(&text).map_err(|e| format!("Failed to parse search: {}", e))?)
+}
+
+// ─── Commands ────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_verification_status(
+    http: State<'_, HttpClient>,
+    limiter: State<'_, RateLimiter>,
+    cache: State<'_, VerificationCache>,
+    app_id: String,
+) -> Result<VerificationStatus, StoreError> {
+    check_app_id(&app_id)?;
+
+    let cached = cache.0.lock().unwrap().get(&app_id).cloned();
+    if let Some(status) = cached {
+        return Ok(status);
+    }
+
+    let status = fetch_verification_status(&http.client(), &limiter, &app_id).await?;
+    cache.0.lock().unwrap().insert(app_id, status.clone());
+    Ok(status)
+}
+
+/// Screenshots listed in the app's flathub appstream data, empty when it has none.
+#[tauri::command]
+pub async fn get_app_screenshots(
+    http: State<'_, HttpClient>,
+    limiter: State<'_, RateLimiter>,
+    app_id: String,
+) -> Result<Vec<Screenshot>, StoreError> {
+    check_app_id(&app_id)?;
+    let appstream = fetch_appstream(&http.client(), &limiter, &app_id).await?;
+    Ok(parse_screenshots(&appstream))
+}
+
+/// Release notes from the app's flathub appstream data, newest first. Empty when
+/// the app publishes none.
+#[tauri::command]
+pub async fn get_app_releases(
+    http: State<'_, HttpClient>,
+    limiter: State<'_, RateLimiter>,
+    app_id: String,
+) -> Result<Vec<Release>, StoreError> {
+    check_app_id(&app_id)?;
+    let appstream = fetch_appstream(&http.client(), &limiter, &app_id).await?;
+    Ok(parse_releases(&appstream))
+}
+
+/// Everything the detail page shows about an app, from its flathub appstream
+/// data. NOT_FOUND when flathub doesn't have the app.
+#[tauri::command]
+pub async fn get_app_details(
+    http: State<'_, HttpClient>,
+    limiter: State<'_, RateLimiter>,
+    app_id: String,
+) -> Result<AppDetails, StoreError> {
+    check_app_id(&app_id)?;
+    match fetch_appstream(&http.client(), &limiter, &app_id).await {
+        Ok(appstream) => Ok(parse_app_details(&app_id, &appstream)),
+        Err(StoreError::HttpError(404)) => Err(StoreError::NotFound(app_id)),
+        Err(e) => Err(e),
+    }
+}
+
+/// One page of the apps flathub lists under `category`, starting at page 1.
+/// An empty list means there are no more pages. Network failures come back as
+/// NETWORK_ERROR/TIMEOUT, a category flathub doesn't have as INVALID_INPUT.
+#[tauri::command]
+pub async fn get_apps_by_category(
+    http: State<'_, HttpClient>,
+    limiter: State<'_, RateLimiter>,
+    cache: State<'_, CollectionCache>,
+    category: String,
+    page: u32,
+) -> Result<Vec<AppSummary>, StoreError> {
+    check_category(&category)?;
+    let collection = format!("category/{}", category);
+    match collection_page(&http, &limiter, &cache, &collection, page).await {
+        // Flathub answers 404 for categories it doesn't have
+        Err(StoreError::HttpError(404)) => Err(StoreError::InvalidInput(format!(
+            "Unknown category: {}",
+            category
+        ))),
+        result => result,
+    }
+}
+
+/// One page of the most installed apps on flathub, starting at page 1.
+#[tauri::command]
+pub async fn get_popular_apps(
+    http: State<'_, HttpClient>,
+    limiter: State<'_, RateLimiter>,
+    cache: State<'_, CollectionCache>,
+    page: u32,
+) -> Result<Vec<AppSummary>, StoreError> {
+    collection_page(&http, &limiter, &cache, "popular", page).await
+}
+
+/// One page of the apps trending on flathub, starting at page 1.
+#[tauri::command]
+pub async fn get_trending_apps(
+    http: State<'_, HttpClient>,
+    limiter: State<'_, RateLimiter>,
+    cache: State<'_, CollectionCache>,
+    page: u32,
+) -> Result<Vec<AppSummary>, StoreError> {
+    collection_page(&http, &limiter, &cache, "trending", page).await
+}
+
+/// Search flathub and mark which results are installed or have an update
+/// pending. Results keep flathub's relevance order.
+#[tauri::command]
+pub async fn search_apps(
+    app: tauri::AppHandle,
+    http: State<'_, HttpClient>,
+    limiter: State<'_, RateLimiter>,
+    query: String,
+) -> Result<Vec<SearchHit>, StoreError> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let results = fetch_search(&http.client(), &limiter, query).await?;
+    let state = local_state(&app).await?;
+    Ok(annotate_hits(parse_app_summaries(&results), &state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn screenshots_pick_smallest_and_largest_size() {
+        let appstream = json!({
+            "screenshots": [
+                {
+                    "caption": "Main window",
+                    "sizes": [
+                        {"width": "1248", "height": "702", "src": "https://dl.flathub.org/full.png"},
+                        {"width": "224", "height": "126", "src": "https://dl.flathub.org/thumb.png"},
+                        {"width": "624", "height": "351", "src": "https://dl.flathub.org/mid.png"}
+                    ]
+                },
+                {
+                    "sizes": {
+                        "112x63": "https://dl.flathub.org/old-thumb.png",
+                        "1248x702": "https://dl.flathub.org/old-full.png"
+                    }
+                },
+                {"caption": "No images", "sizes": []}
+            ]
+        });
+
+        let screenshots = parse_screenshots(&appstream);
+        assert_eq!(screenshots.len(), 2);
+        assert_eq!(screenshots[0].thumb_url, "https://dl.flathub.org/thumb.png");
+        assert_eq!(screenshots[0].full_url, "https://dl.flathub.org/full.png");
+        assert_eq!(screenshots[0].caption.as_deref(), Some("Main window"));
+        assert_eq!(
+            screenshots[1].thumb_url,
+            "https://dl.flathub.org/old-thumb.png"
+        );
+        assert_eq!(
+            screenshots[1].full_url,
+            "https://dl.flathub.org/old-full.png"
+        );
+        assert_eq!(screenshots[1].caption, None);
+    }
+
+    #[test]
+    fn no_screenshots_is_empty() {
+        assert!(parse_screenshots(&json!({"id": "org.example.App"})).is_empty());
+    }
+
+    #[test]
+    fn releases_newest_first() {
+        let appstream = json!({
+            "releases": [
+                {"version": "1.0", "timestamp": "1690000000", "description": "<p>First</p>"},
+                {"version": "1.2", "timestamp": 1710000000, "description": ""},
+                {"version": "1.1", "timestamp": "1700000000"},
+                {"timestamp": "1720000000"}
+            ]
+        });
+
+        let releases = parse_releases(&appstream);
+        let versions: Vec<&str> = releases.iter().map(|r| r.version.as_str()).collect();
+        assert_eq!(versions, ["1.2", "1.1", "1.0"]);
+        assert_eq!(releases[0].date, Some(1710000000));
+        assert_eq!(releases[0].description, None);
+        assert_eq!(releases[2].description.as_deref(), Some("<p>First</p>"));
+        assert!(parse_releases(&json!({})).is_empty());
+    }
+
+    #[test]
+    fn category_hits_become_summaries() {
+        let collection = json!({
+            "hits": [
+                {
+                    "app_id": "org.gnome.Builder",
+                    "name": "Builder",
+                    "summary": "An IDE for GNOME",
+                    "icon": "https://dl.flathub.org/builder.png"
+                },
+                {"id": "org.example.NoIcon", "name": "No Icon", "icon": ""},
+                {"name": "No id"}
+            ],
+            "page": 1,
+            "totalPages": 3
+        });
+
+        let apps = parse_app_summaries(&collection);
+        assert_eq!(apps.len(), 2);
+        assert_eq!(apps[0].app_id, "org.gnome.Builder");
+        assert_eq!(apps[0].summary, "An IDE for GNOME");
+        assert_eq!(
+            apps[0].icon_url.as_deref(),
+            Some("https://dl.flathub.org/builder.png")
+        );
+        assert_eq!(apps[1].app_id, "org.example.NoIcon");
+        assert_eq!(apps[1].summary, "");
+        assert_eq!(apps[1].icon_url, None);
+        assert!(parse_app_summaries(&json!({})).is_empty());
+
+        assert!(check_category("AudioVideo").is_ok());
+        assert!(check_category("../verification").is_err());
+        assert!(check_category("").is_err());
+    }
+
+    #[test]
+    fn app_details_from_appstream() {
+        let appstream = json!({
+            "name": "Builder",
+            "summary": "An IDE for GNOME",
+            "description": "<p>Write software</p>",
+            "developer_name": "GNOME",
+            "project_license": "GPL-3.0-or-later",
+            "categories": ["Development", "IDE", ""],
+            "urls": {
+                "homepage": "https://apps.gnome.org/Builder",
+                "bugtracker": "https://gitlab.gnome.org/GNOME/gnome-builder/issues"
+            },
+            "icons": [
+                {"url": "https://dl.flathub.org/64.png", "width": 64},
+                {"url": "https://dl.flathub.org/128.png", "width": 128}
+            ]
+        });
+
+        let details = parse_app_details("org.gnome.Builder", &appstream);
+        assert_eq!(details.name, "Builder");
+        assert_eq!(
+            details.description.as_deref(),
+            Some("<p>Write software</p>")
+        );
+        assert_eq!(details.developer.as_deref(), Some("GNOME"));
+        assert_eq!(details.license.as_deref(), Some("GPL-3.0-or-later"));
+        assert_eq!(details.categories, ["Development", "IDE"]);
+        assert_eq!(
+            details.urls.homepage.as_deref(),
+            Some("https://apps.gnome.org/Builder")
+        );
+        assert_eq!(details.urls.donation, None);
+        assert_eq!(
+            details.icon_url.as_deref(),
+            Some("https://dl.flathub.org/128.png")
+        );
+
+        let bare = parse_app_details(
+            "org.example.App",
+            &json!({"icon": "https://dl.flathub.org/icon.png", "developer": {"name": "Example"}}),
+        );
+        assert_eq!(bare.name, "org.example.App");
+        assert_eq!(bare.developer.as_deref(), Some("Example"));
+        assert_eq!(
+            bare.icon_url.as_deref(),
+            Some("https://dl.flathub.org/icon.png")
+        );
+    }
+
+    #[test]
+    fn rate_limiter_queues_past_the_burst() {
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            rate: 2,
+            tokens: 2.0,
+            refilled_at: start,
+        };
+
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        assert_eq!(bucket.reserve(start), Duration::from_millis(500));
+        assert_eq!(bucket.reserve(start), Duration::from_secs(1));
+
+        // Two seconds later the queue is paid back and the burst is available again
+        let later = start + Duration::from_secs(2);
+        assert_eq!(bucket.reserve(later), Duration::ZERO);
+        assert_eq!(bucket.reserve(later), Duration::ZERO);
+        assert_eq!(bucket.reserve(later), Duration::from_millis(500));
+
+        bucket.rate = 0;
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+    }
+
+    #[test]
+    fn search_hits_keep_order_and_local_state() {
+        let results = json!({
+            "hits": [
+                {"app_id": "org.gimp.GIMP", "name": "GIMP"},
+                {"app_id": "org.inkscape.Inkscape", "name": "Inkscape"},
+                {"app_id": "org.kde.krita", "name": "Krita"}
+            ]
+        });
+        let state = LocalState {
+            installed: HashSet::from(["org.kde.krita".to_string(), "org.gimp.GIMP".to_string()]),
+            updates: HashSet::from(["org.kde.krita".to_string()]),
+        };
+
+        let hits = annotate_hits(parse_app_summaries(&results), &state);
+        let ids: Vec<&str> = hits.iter().map(|hit| hit.app_id.as_str()).collect();
+        assert_eq!(
+            ids,
+            ["org.gimp.GIMP", "org.inkscape.Inkscape", "org.kde.krita"]
+        );
+        assert!(hits[0].installed && !hits[0].update_available);
+        assert!(!hits[1].installed && !hits[1].update_available);
+        assert!(hits[2].installed && hits[2].update_available);
+    }
+}
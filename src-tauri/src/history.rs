@@ -0,0 +1,163 @@
+use serde::Serialize;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use std::sync::Arc;
+use tauri::{Manager, State};
+
+use crate::error::StoreError;
+
+// ─── Database ────────────────────────────────────────────────────────────────
+
+// Same file the frontend opens through tauri-plugin-sql.
+const DB_FILE: &str = "kliastore.db";
+
+/// Lazily opened pool for the install history, kept in managed state.
+pub type HistoryDb = Arc<tokio::sync::OnceCell<SqlitePool>>;
+
+/// Open the database and create the `install_history` table on first use.
+pub async fn pool<'a>(
+    app: &tauri::AppHandle,
+    db: &'a HistoryDb,
+) -> Result<&'a SqlitePool, StoreError> {
+    db.get_or_try_init(|| async {
+        let app_data_dir = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+        std::fs::create_dir_all(&app_data_dir)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+
+        let options = SqliteConnectOptions::new()
+            .filename(app_data_dir.join(DB_FILE))
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .map_err(|e| format!("Failed to open database: {}", e))?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS install_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                app_id TEXT NOT NULL,
+                action TEXT NOT NULL,
+                version TEXT,
+                success INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to create install_history table: {}", e))?;
+
+        Ok::<_, StoreError>(pool)
+    })
+    .await
+}
+
+async fn insert_event(
+    app: &tauri::AppHandle,
+    db: &HistoryDb,
+    app_id: &str,
+    action: &str,
+    version: Option<&str>,
+    success: bool,
+) -> Result<(), StoreError> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    sqlx::query(
+        "INSERT INTO install_history (app_id, action, version, success, timestamp)
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(app_id)
+    .bind(action)
+    .bind(version)
+    .bind(success)
+    .bind(timestamp)
+    .execute(pool(app, db).await?)
+    .await
+    .map_err(|e| format!("Failed to record install event: {}", e))?;
+
+    Ok(())
+}
+
+/// Record the outcome of a finished operation. Called from the completion
+/// handlers; a failure here is logged and never fails the operation itself.
+pub async fn record(
+    app: &tauri::AppHandle,
+    app_id: &str,
+    action: &str,
+    version: Option<&str>,
+    success: bool,
+) {
+    let db = app.state::<HistoryDb>();
+    if let Err(e) = insert_event(app, db.inner(), app_id, action, version, success).await {
+        log::warn!("[history] {}", e);
+    }
+}
+
+// ─── Commands ────────────────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub app_id: String,
+    pub action: String,
+    pub version: Option<String>,
+    pub success: bool,
+    /// Unix timestamp (seconds).
+    pub timestamp: i64,
+}
+
+#[tauri::command]
+pub async fn record_install_event(
+    app: tauri::AppHandle,
+    db: State<'_, HistoryDb>,
+    app_id: String,
+    action: String,
+    version: Option<String>,
+    success: bool,
+) -> Result<(), StoreError> {
+    insert_event(
+        &app,
+        db.inner(),
+        &app_id,
+        &action,
+        version.as_deref(),
+        success,
+    )
+    .await
+}
+
+/// Most recent events first.
+#[tauri::command]
+pub async fn get_install_history(
+    app: tauri::AppHandle,
+    db: State<'_, HistoryDb>,
+    limit: u32,
+) -> Result<Vec<HistoryEntry>, StoreError> {
+    let rows: Vec<(i64, String, String, Option<String>, bool, i64)> = sqlx::query_as(
+        "SELECT id, app_id, action, version, success, timestamp
+         FROM install_history ORDER BY timestamp DESC, id DESC LIMIT ?",
+    )
+    .bind(limit)
+    .fetch_all(pool(&app, db.inner()).await?)
+    .await
+    .map_err(|e| format!("Failed to read install history: {}", e))?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(id, app_id, action, version, success, timestamp)| HistoryEntry {
+                id,
+                app_id,
+                action,
+                version,
+                success,
+                timestamp,
+            },
+        )
+        .collect())
+}
@@ -0,0 +1,89 @@
+use std::sync::RwLock;
+use std::time::Duration;
+use tauri::{Manager, State};
+use tauri_plugin_http::reqwest;
+
+use crate::config::{self, Config};
+use crate::error::StoreError;
+
+const USER_AGENT: &str = "klia-store";
+// Icon URLs may bounce through a CDN; more hops than this is a loop
+const MAX_REDIRECTS: usize = 5;
+
+/// Build a client with the proxy and timeouts from `config`. The proxy is used
+/// for everything while still honoring NO_PROXY; without one, reqwest reads
+/// HTTP_PROXY/HTTPS_PROXY/NO_PROXY from the environment.
+fn build_client(config: &Config) -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(Duration::from_secs(config.http_connect_timeout_secs))
+        .timeout(Duration::from_secs(config.http_timeout_secs))
+        .redirect(reqwest::redirect::Policy::limited(MAX_REDIRECTS));
+    if let Some(url) = &config.proxy_url {
+        let proxy = reqwest::Proxy::all(url)
+            .map_err(|e| format!("Invalid proxy URL {}: {}", url, e))?
+            .no_proxy(reqwest::NoProxy::from_env());
+        builder = builder.proxy(proxy);
+    }
+    builder
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))
+}
+
+/// HTTP client shared by every backend request so connections and TLS sessions
+/// are reused. Created once in `run()` and replaced as a whole when the proxy
+/// or timeouts change; `client()` hands out cheap clones.
+pub struct HttpClient(RwLock<reqwest::Client>);
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self(RwLock::new(
+            build_client(&Config::default()).unwrap_or_default(),
+        ))
+    }
+}
+
+impl HttpClient {
+    pub fn client(&self) -> reqwest::Client {
+        self.0.read().unwrap().clone()
+    }
+
+    fn replace(&self, client: reqwest::Client) {
+        *self.0.write().unwrap() = client;
+    }
+}
+
+/// Rebuild the shared client from `config`.
+pub fn apply(app: &tauri::AppHandle, config: &Config) -> Result<(), String> {
+    let client = build_client(config)?;
+    app.state::<HttpClient>().replace(client);
+    Ok(())
+}
+
+/// Apply the proxy and timeouts saved in the config. Called once from setup.
+pub fn init(app: &tauri::AppHandle) {
+    let config = config::load(app).unwrap_or_default();
+    if let Err(e) = apply(app, &config) {
+        log::warn!("[http] Ignoring configured proxy: {}", e);
+    }
+}
+
+// ─── Commands ────────────────────────────────────────────────────────────────
+
+/// Save the proxy used for the backend's HTTP requests and rebuild the shared
+/// client. An empty `url` goes back to the environment proxy.
+#[tauri::command]
+pub fn set_proxy(
+    app: tauri::AppHandle,
+    http: State<'_, HttpClient>,
+    url: String,
+) -> Result<(), StoreError> {
+    let mut config = config::load(&app)?;
+    config.proxy_url = Some(url.trim().to_string()).filter(|url| !url.is_empty());
+    let client = build_client(&config).map_err(StoreError::InvalidInput)?;
+    config::save(&app, &config)?;
+
+    http.replace(client);
+    log::info!("[http] Proxy updated");
+    Ok(())
+}
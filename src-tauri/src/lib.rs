@@ -1,16 +1,36 @@
+mod backup;
+mod capabilities;
+mod config;
+mod deeplink;
 mod donations;
+mod error;
+mod favorites;
+mod flathub;
+mod history;
+mod http;
+mod logging;
+mod notifications;
+mod queue;
+mod scheduler;
+mod status;
+mod tray;
 
 use once_cell::sync::Lazy;
-use serde::Serialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::io::{BufRead, BufReader, Write};
 use std::process::{Child, ChildStdin, Command, Stdio};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tauri::{Emitter, Manager, State};
 use tauri_plugin_http::reqwest;
+use tauri_plugin_shell::process::CommandChild;
 use tauri_plugin_shell::ShellExt;
 
+use error::StoreError;
+
 // Regex compilado una sola vez para extraer owner/repo
 static GITHUB_HTTPS_REGEX: Lazy<regex::Regex> = Lazy::new(|| {
     regex::Regex::new(r"https?://github\.com/([^/]+)/([^/]+?)(?:\.git)?$").unwrap()
@@ -28,6 +48,13 @@ static GITLAB_SSH_REGEX: Lazy<regex::Regex> = Lazy::new(|| {
     // Captures: (1) domain, (2) full path
     regex::Regex::new(r"git@(gitlab\.[^:]+):(.+?)(?:\.git)?$").unwrap()
 });
+// Progress percentage in flatpak output, e.g. "Installing 1/3… 45%"
+static PROGRESS_REGEX: Lazy<regex::Regex> =
+    Lazy::new(|| regex::Regex::new(r"\b(\d{1,3})(?:[.,]\d+)?\s*%").unwrap());
+// ANSI escapes emitted under `script`: CSI (colors, cursor moves), OSC (titles), two-byte escapes
+static ANSI_ESCAPE_REGEX: Lazy<regex::Regex> = Lazy::new(|| {
+    regex::Regex::new(r"\x1b(?:\[[0-?]*[ -/]*[@-~]|\][^\x07\x1b]*(?:\x07|\x1b\\)|[@-_])").unwrap()
+});
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -40,7 +67,7 @@ enum GitPlatform {
 #[cfg(target_os = "linux")]
 use std::fs::File;
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 struct InstalledApp {
     app_id: String,
     name: String,
@@ -48,10 +75,63 @@ struct InstalledApp {
     summary: Option<String>,
     developer: Option<String>,
     permissions: Option<Vec<String>>,
+    // On-disk size in bytes from the `size` column, None when flatpak leaves it empty
     installed_size: Option<u64>,
+    // End-of-life reason when the app is no longer maintained
+    eol: Option<String>,
+    // Remote the app was installed from
+    origin: String,
 }
 
-#[derive(Serialize)]
+#[derive(Debug, Serialize)]
+struct InstalledAppDetail {
+    app_id: String,
+    name: Option<String>,
+    app_ref: String,
+    version: Option<String>,
+    branch: Option<String>,
+    origin: Option<String>,
+    installation: Option<String>,
+    installed_size: Option<u64>,
+    runtime: Option<String>,
+    commit: Option<String>,
+    eol: Option<String>,
+    // Replacement id suggested by an end-of-life app
+    eol_rebase: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct EolPackage {
+    // App id or full ref of the end-of-life package
+    id: String,
+    reason: String,
+    rebase: Option<String>,
+}
+
+// Sandbox permissions from the [Context] and bus policy groups of an app's metadata
+#[derive(Debug, Default, Serialize)]
+struct Permissions {
+    shared: Vec<String>,
+    sockets: Vec<String>,
+    devices: Vec<String>,
+    filesystems: Vec<String>,
+    features: Vec<String>,
+    persistent: Vec<String>,
+    // Bus name -> policy (talk, own, see...)
+    session_bus: BTreeMap<String, String>,
+    system_bus: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct AppMetadata {
+    runtime: String,
+    sdk: String,
+    command: String,
+    permissions: Permissions,
+    extension_points: Vec<String>,
+}
+
+#[derive(Clone, Serialize)]
 struct InstalledExtension {
     extension_id: String,
     name: String,
@@ -59,13 +139,64 @@ struct InstalledExtension {
     parent_app_id: String,
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 struct InstalledPackagesResponse {
     apps: Vec<InstalledApp>,
     runtimes: Vec<String>,
     extensions: Vec<InstalledExtension>,
 }
 
+#[derive(Default)]
+struct InstalledCacheState {
+    packages: Option<InstalledPackagesResponse>,
+    dirty: bool,
+    // Bumped by every mark_dirty, so a listing that started before an
+    // operation finished isn't stored as fresh
+    generation: u64,
+}
+
+// Last get_installed_flatpaks result, reused until an operation marks it dirty
+#[derive(Default)]
+struct InstalledCache(Mutex<InstalledCacheState>);
+
+impl InstalledCache {
+    fn get(&self) -> Option<InstalledPackagesResponse> {
+        let state = self.0.lock().unwrap();
+        if state.dirty {
+            None
+        } else {
+            state.packages.clone()
+        }
+    }
+
+    fn generation(&self) -> u64 {
+        self.0.lock().unwrap().generation
+    }
+
+    fn store(&self, generation: u64, packages: InstalledPackagesResponse) {
+        let mut state = self.0.lock().unwrap();
+        if state.generation == generation {
+            state.packages = Some(packages);
+            state.dirty = false;
+        }
+    }
+
+    fn mark_dirty(&self) {
+        let mut state = self.0.lock().unwrap();
+        state.dirty = true;
+        state.generation += 1;
+    }
+}
+
+// Something may have been installed, removed or updated: drop what's cached
+// about the installation
+fn mark_installed_changed<R: tauri::Runtime>(app: &tauri::AppHandle<R>) {
+    app.state::<InstalledCache>().mark_dirty();
+    app.state::<flathub::LocalStateCache>().invalidate();
+    // Which dependencies are already installed may have changed for any app
+    app.state::<DependencyCache>().lock().unwrap().clear();
+}
+
 // Persistent PTY process manager
 struct PtyProcess {
     child: Child,
@@ -74,1380 +205,5242 @@ struct PtyProcess {
 
 type ProcessMap = Arc<Mutex<HashMap<String, PtyProcess>>>;
 
-// Helper function to build interactive flatpak PTY command with -y flag (automatic confirmation)
-fn build_flatpak_interactive_cmd(is_flatpak: bool, app_id: &str) -> String {
-    let base_cmd = format!("flatpak install -y --user flathub {}", app_id);
-    if is_flatpak {
-        format!(
-            "LANG=C script -q /dev/null -c \"flatpak-spawn --host {}\"",
-            base_cmd
-        )
-    } else {
-        format!("LANG=C script -q /dev/null -c \"{}\"", base_cmd)
-    }
+// Payload of the "pty-terminated" event. code is None when the process was killed by a
+// signal or its status couldn't be read; timed_out is set when it was killed for
+// producing no output within its idle timeout
+#[derive(Clone, Serialize)]
+struct PtyTerminated {
+    app_id: String,
+    code: Option<i32>,
+    success: bool,
+    timed_out: bool,
 }
 
-// Helper function for dependency checking (with auto-yes responses)
-fn build_flatpak_dependency_check_cmd(is_flatpak: bool, app_id: &str) -> String {
-    let base_cmd = format!("flatpak install --user flathub {}", app_id);
-    if is_flatpak {
-        format!(
-            "LANG=C printf \"y\\nn\\n\" | script -q /dev/null -c \"flatpak-spawn --host {}\"",
-            base_cmd
-        )
-    } else {
-        format!(
-            "LANG=C printf \"y\\nn\\n\" | script -q /dev/null -c \"{}\"",
-            base_cmd
-        )
-    }
+fn emit_pty_terminated(
+    app: &tauri::AppHandle,
+    app_id: &str,
+    status: Option<std::process::ExitStatus>,
+    timed_out: bool,
+) {
+    mark_installed_changed(app);
+    let _ = app.emit(
+        "pty-terminated",
+        PtyTerminated {
+            app_id: app_id.to_string(),
+            code: status.and_then(|status| status.code()),
+            success: !timed_out && status.is_some_and(|status| status.success()),
+            timed_out,
+        },
+    );
 }
 
-// Helper function to parse size string from flatpak list output
-// Format examples: "715,3 MB", "1,2 GB", "16,9 MB", "2,5 kB"
-fn parse_size_string(size_str: &str) -> Option<u64> {
-    let parts: Vec<&str> = size_str.trim().split_whitespace().collect();
-    if parts.len() != 2 {
+// A prompt still waiting for its answer ends with the choices: "[Y/n]: " or "[0-2]: ".
+// flatpak doesn't translate these, so they match whatever the host locale is
+static PTY_CONFIRM_REGEX: Lazy<regex::Regex> =
+    Lazy::new(|| regex::Regex::new(r"\[[Yy]/[Nn]\]:?\s*$").unwrap());
+static PTY_CHOICE_REGEX: Lazy<regex::Regex> =
+    Lazy::new(|| regex::Regex::new(r"\[\d+-\d+\]:?\s*$").unwrap());
+
+// Questions about trusting a remote or its GPG key, e.g. "Configure this as new
+// remote 'flathub' [Y/n]:" when a .flatpakref brings its own remote
+const PTY_TRUST_PHRASES: &[&str] = &[
+    "configure this as new remote",
+    "should the remote be kept",
+    "gpg",
+    "import",
+    "trust",
+];
+
+// Payload of the "pty-prompt" event, sent alongside the pty-output line that asks.
+// prompt_type is "trust", "confirm" (yes/no) or "choice" (pick a number);
+// answers go back through send_to_pty
+#[derive(Clone, Serialize)]
+struct PtyPrompt {
+    app_id: String,
+    prompt_type: &'static str,
+    text: String,
+}
+
+fn detect_pty_prompt(line: &str) -> Option<&'static str> {
+    let text = line.trim_start_matches('\r');
+    if PTY_CHOICE_REGEX.is_match(text) {
+        return Some("choice");
+    }
+    if !PTY_CONFIRM_REGEX.is_match(text) {
         return None;
     }
+    let lower = text.to_lowercase();
+    let trust = PTY_TRUST_PHRASES.iter().any(|p| lower.contains(p));
+    Some(if trust { "trust" } else { "confirm" })
+}
 
-    // Replace comma with dot for parsing (locale-specific decimal separator)
-    let size_value = parts[0].replace(',', ".").parse::<f64>().ok()?;
-    let unit = parts[1];
+// One numbered entry of a choice prompt, `index` is what to send back
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct PtyChoiceOption {
+    index: u32,
+    #[serde(rename = "ref")]
+    flatpak_ref: String,
+}
 
-    let bytes = match unit {
-        "B" | "bytes" => size_value as u64,
-        "kB" | "KB" => (size_value * 1_000.0) as u64,
-        "MB" => (size_value * 1_000_000.0) as u64,
-        "GB" => (size_value * 1_000_000_000.0) as u64,
-        "TB" => (size_value * 1_000_000_000_000.0) as u64,
-        _ => return None,
-    };
+// Payload of the "pty-choice" event, sent with the pty-prompt of a numbered
+// choice. default is what flatpak picks on an empty answer
+#[derive(Clone, Serialize)]
+struct PtyChoice {
+    app_id: String,
+    options: Vec<PtyChoiceOption>,
+    default: Option<u32>,
+}
 
-    Some(bytes)
+// "   1) app/org.gimp.GIMP/x86_64/stable" under "Similar refs found for ..."
+static PTY_CHOICE_OPTION_REGEX: Lazy<regex::Regex> =
+    Lazy::new(|| regex::Regex::new(r"^\s*(\d+)\)\s+(\S.*?)\s*$").unwrap());
+
+// Collects the numbered list printed before a choice prompt, one PTY stream each
+#[derive(Default)]
+struct PtyChoiceCollector {
+    options: Vec<PtyChoiceOption>,
 }
 
-// Helper function to extract developer name from app_id
-// Takes the second-to-last segment (penultimate)
-// Example: io.github.N3kosempai.klia-store -> N3kosempai
-// Example: org.mozilla.firefox -> mozilla
-// Example: com.her01n.BatteryInfo -> her01n
-fn extract_developer(app_id: &str) -> Option<String> {
-    let parts: Vec<&str> = app_id.split('.').collect();
-    if parts.len() >= 2 {
-        // Always take the penultimate (second from the end)
-        Some(parts[parts.len() - 2].to_string())
-    } else {
-        None
+impl PtyChoiceCollector {
+    // Feed one output line; returns the options once their prompt shows up
+    fn feed(&mut self, line: &str) -> Option<Vec<PtyChoiceOption>> {
+        let text = line.trim_start_matches('\r');
+        if let Some(caps) = PTY_CHOICE_OPTION_REGEX.captures(text) {
+            if let Ok(index) = caps[1].parse() {
+                self.options.push(PtyChoiceOption {
+                    index,
+                    flatpak_ref: caps[2].to_string(),
+                });
+            }
+            return None;
+        }
+        if text.trim().is_empty() {
+            return None;
+        }
+        let options = std::mem::take(&mut self.options);
+        (detect_pty_prompt(text) == Some("choice") && !options.is_empty()).then_some(options)
     }
 }
 
-// Helper function to get app permissions from flatpak
-fn get_app_permissions(app_id: &str, is_flatpak: bool) -> Option<Vec<String>> {
-    let output = if is_flatpak {
-        Command::new("flatpak-spawn")
-            .args(&["--host", "flatpak", "info", "--show-permissions", app_id])
-            .output()
-            .ok()?
-    } else {
-        Command::new("flatpak")
-            .args(&["info", "--show-permissions", app_id])
-            .output()
-            .ok()?
-    };
-
-    if !output.status.success() {
-        return None;
+// Emit one line of PTY output, plus pty-prompt (and pty-choice for numbered
+// lists) when it's a question
+fn emit_pty_line(
+    app: &tauri::AppHandle,
+    key: &str,
+    line: String,
+    choices: &mut PtyChoiceCollector,
+) {
+    let prompt = detect_pty_prompt(&line).map(|prompt_type| PtyPrompt {
+        app_id: key.to_string(),
+        prompt_type,
+        text: line.trim_start_matches('\r').trim().to_string(),
+    });
+    let options = choices.feed(&line);
+    let _ = app.emit("pty-output", (key.to_string(), line));
+    if let Some(prompt) = prompt {
+        let _ = app.emit("pty-prompt", prompt);
+    }
+    if let Some(options) = options {
+        // flatpak only accepts an empty answer when there is a single option
+        let default = (options.len() == 1).then(|| options[0].index);
+        let _ = app.emit(
+            "pty-choice",
+            PtyChoice {
+                app_id: key.to_string(),
+                options,
+                default,
+            },
+        );
     }
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut permissions = Vec::new();
-    let mut has_camera = false;
-    let mut has_files = false;
-    let mut has_storage = false;
+// Minimum time between two pty-output events for a line that is still being rewritten
+const PTY_OUTPUT_THROTTLE: Duration = Duration::from_millis(100);
+
+// Groups raw PTY stdout into pty-output lines. Finished lines are emitted as soon as
+// their \n arrives; a line still being rewritten with \r is emitted at most once per
+// throttle interval, showing only its latest state. Once part of a line has been shown,
+// later emissions for it start with \r so the frontend overwrites it.
+struct PtyLineCoalescer {
+    pending: String,
+    throttle: Duration,
+    last_emit: Option<Instant>,
+    // Part of the pending line was already emitted
+    shown: bool,
+    // The pending line changed since it was last emitted
+    dirty: bool,
+}
 
-    for line in stdout.lines() {
-        let line = line.trim();
-        if line.is_empty() || line.starts_with('[') {
-            continue;
+impl PtyLineCoalescer {
+    fn new(throttle: Duration) -> Self {
+        Self {
+            pending: String::new(),
+            throttle,
+            last_emit: None,
+            shown: false,
+            dirty: false,
         }
+    }
 
-        // Look for specific permissions we care about
-        // Camera: devices=all (full device access) or specific video devices
-        if !has_camera && line.contains("devices=") {
-            let devices_part = line.split("devices=").nth(1).unwrap_or("");
-            // Check if "all" is in the devices list (e.g., "dri;all;" or "all;")
-            if devices_part.split(';').any(|d| d.trim() == "all") {
-                permissions.push("camera".to_string());
-                has_camera = true;
-            }
+    // Start of the last non-empty \r-separated segment, i.e. what a terminal would show
+    fn visible_start(text: &str) -> Option<usize> {
+        let trimmed = text.trim_end_matches('\r');
+        if trimmed.is_empty() {
+            return None;
         }
+        Some(trimmed.rfind('\r').map_or(0, |i| i + 1))
+    }
 
-        // Files: filesystems= with any value
-        if !has_files && line.contains("filesystems=") {
-            let filesystems_part = line.split("filesystems=").nth(1).unwrap_or("");
-            let filesystems_value = filesystems_part.split(';').next().unwrap_or("").trim();
-            if !filesystems_value.is_empty() {
-                permissions.push("files".to_string());
-                has_files = true;
-            }
+    fn render(&self, text: &str) -> Option<String> {
+        let start = Self::visible_start(text)?;
+        let visible = strip_ansi(text[start..].trim_end_matches('\r'));
+        if visible.is_empty() {
+            return None;
         }
+        Some(if self.shown {
+            format!("\r{}", visible)
+        } else {
+            visible
+        })
+    }
 
-        // Storage: persist= or filesystems containing home/host
-        if !has_storage {
-            if line.contains("persist=") {
-                let persist_part = line.split("persist=").nth(1).unwrap_or("");
-                let persist_value = persist_part.split(';').next().unwrap_or("").trim();
-                if !persist_value.is_empty() {
-                    permissions.push("storage".to_string());
-                    has_storage = true;
-                }
-            } else if line.contains("filesystems=") {
-                if line.contains("home") || line.contains("host") || line.contains("xdg-download") {
-                    permissions.push("storage".to_string());
-                    has_storage = true;
-                }
+    fn push(&mut self, chunk: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        self.pending.push_str(chunk);
+
+        while let Some(pos) = self.pending.find('\n') {
+            let rest = self.pending.split_off(pos + 1);
+            let mut line = std::mem::replace(&mut self.pending, rest);
+            line.pop();
+            if let Some(out) = self.render(&line) {
+                lines.push(out);
             }
+            self.shown = false;
+        }
+
+        self.dirty = !self.pending.is_empty();
+        let due = self
+            .last_emit
+            .is_none_or(|last| last.elapsed() >= self.throttle);
+        if due {
+            lines.extend(self.flush());
         }
+        lines
     }
 
-    if permissions.is_empty() {
-        None
-    } else {
-        Some(permissions)
+    // Emit the current state of an unfinished line, e.g. an interactive prompt
+    fn flush(&mut self) -> Option<String> {
+        if !self.dirty {
+            return None;
+        }
+        self.dirty = false;
+        let out = self.render(&self.pending)?;
+        // Older \r frames are never shown again
+        if let Some(start) = Self::visible_start(&self.pending) {
+            self.pending.drain(..start);
+        }
+        self.shown = true;
+        self.last_emit = Some(Instant::now());
+        Some(out)
     }
 }
 
-#[derive(Serialize)]
-struct UpdateAvailable {
-    app_id: String,
-    new_version: String,
-    branch: String,
-}
+// Shell-plugin children of running update/uninstall commands, keyed by app_id
+type ShellChildMap = Arc<Mutex<HashMap<String, CommandChild>>>;
 
-#[derive(Serialize)]
-struct Dependency {
-    name: String,
-    download_size: String,
-    installed_size: String,
-}
+// Key used for `update_system_flatpaks`, which has no single app_id
+const SYSTEM_UPDATE_KEY: &str = "system";
 
-// System Analytics Struct
-#[derive(Serialize)]
-struct SystemAnalytics {
-    disk_usage: DiskUsage,
-    flatpak_stats: FlatpakStats,
-    system_info: SystemInfo,
-}
+// Keys used for `uninstall_flatpaks` and `update_flatpaks`, one transaction for many apps
+const BATCH_UNINSTALL_KEY: &str = "batch::uninstall";
+const BATCH_UPDATE_KEY: &str = "batch::update";
 
-#[derive(Serialize)]
-struct DiskUsage {
-    total_gb: f64,
-    used_gb: f64,
-    available_gb: f64,
-    usage_percent: f64,
+// install-completed code sent when the user cancels an operation
+const CANCELLED_EXIT_CODE: i32 = -2;
+
+fn track_shell_child(app: &tauri::AppHandle, key: &str, child: CommandChild) {
+    let children = app.state::<ShellChildMap>();
+    children.lock().unwrap().insert(key.to_string(), child);
 }
 
-#[derive(Serialize)]
-struct FlatpakStats {
-    total_apps: usize,
-    total_runtimes: usize,
-    total_extensions: usize,
-    apps_with_updates: usize,
+// Ask the command tracked under `key` to stop with SIGTERM. The child stays tracked
+// unless the signal was delivered, so the command still reports its own end otherwise
+fn terminate_shell_child(children: &ShellChildMap, key: &str) -> Result<(), StoreError> {
+    let mut map = children.lock().unwrap();
+    let child = map
+        .get(key)
+        .ok_or_else(|| StoreError::InvalidInput(format!("No running operation for {}", key)))?;
+
+    let status = std::process::Command::new("kill")
+        .args(["-TERM", &child.pid().to_string()])
+        .status()
+        .map_err(|e| format!("Failed to run kill: {}", e))?;
+    if !status.success() {
+        return Err(StoreError::Internal(format!(
+            "Failed to cancel operation for {}",
+            key
+        )));
+    }
+    map.remove(key);
+    Ok(())
 }
 
-#[derive(Serialize)]
-struct SystemInfo {
-    os_name: String,
-    kernel_version: String,
-    hostname: String,
+// Forget a finished command's child. Returns false if `cancel_operation` already took it,
+// in which case the completion event has been sent there.
+fn untrack_shell_child<R: tauri::Runtime>(app: &tauri::AppHandle<R>, key: &str) -> bool {
+    let children = app.state::<ShellChildMap>();
+    let removed = children.lock().unwrap().remove(key);
+    removed.is_some()
 }
 
-// Get system analytics data
-#[tauri::command]
-async fn get_app_permissions_batch(
-    _app: tauri::AppHandle,
-    app_ids: Vec<String>,
-) -> Result<std::collections::HashMap<String, Vec<String>>, String> {
-    use std::collections::HashMap;
-    use std::sync::{Arc, Mutex};
-    use std::thread;
+// Limit on how many installs/updates download at the same time.
+// flatpak (ostree) has no option to cap the parallel fetches inside one transaction, so
+// `max_parallel_downloads` is applied here instead: an install or update waits for a free
+// slot before flatpak is started. No flatpak flags or environment variables are changed.
+#[derive(Default)]
+struct DownloadSlots {
+    active: Mutex<usize>,
+    freed: tokio::sync::Notify,
+}
 
-    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
-    let result = Arc::new(Mutex::new(HashMap::new()));
+type DownloadSlotsState = Arc<DownloadSlots>;
 
-    // Parallelize permission fetching using native threads
-    // Limit concurrent threads to avoid overwhelming the system
-    let chunk_size = 10; // Process 10 apps at a time
-    let mut app_id_chunks: Vec<Vec<String>> = Vec::new();
+// Held while a flatpak download runs; frees the slot when dropped
+struct DownloadSlot(DownloadSlotsState);
 
-    for chunk in app_ids.chunks(chunk_size) {
-        app_id_chunks.push(chunk.to_vec());
+impl Drop for DownloadSlot {
+    fn drop(&mut self) {
+        *self.0.active.lock().unwrap() -= 1;
+        self.0.freed.notify_waiters();
     }
+}
 
-    for chunk in app_id_chunks.iter() {
-        let mut handles = Vec::new();
-
-        for app_id in chunk {
-            let result_clone = Arc::clone(&result);
-            let app_id_clone = app_id.clone();
-            let handle = thread::spawn(move || {
-                if let Some(perms) = get_app_permissions(&app_id_clone, is_flatpak) {
-                    result_clone.lock().unwrap().insert(app_id_clone, perms);
-                }
-            });
-            handles.push(handle);
-        }
+// Wait until fewer than `max_parallel_downloads` downloads are running. The argument
+// overrides the configured default; None or 0 means no limit.
+async fn acquire_download_slot(
+    app: &tauri::AppHandle,
+    max_parallel_downloads: Option<u32>,
+) -> DownloadSlot {
+    let limit = max_parallel_downloads
+        .or_else(|| {
+            config::load(app)
+                .ok()
+                .and_then(|config| config.max_parallel_downloads)
+        })
+        .filter(|&limit| limit > 0);
+    let slots = app.state::<DownloadSlotsState>().inner().clone();
 
-        // Wait for this chunk to complete
-        for handle in handles {
-            let _ = handle.join();
+    loop {
+        // Registered before checking so a slot freed in between still wakes us
+        let freed = slots.freed.notified();
+        {
+            let mut active = slots.active.lock().unwrap();
+            if limit.is_none_or(|limit| *active < limit as usize) {
+                *active += 1;
+                return DownloadSlot(slots.clone());
+            }
         }
+        freed.await;
     }
+}
 
-    let final_result = result.lock().unwrap().clone();
-    Ok(final_result)
+// Cached get_install_dependencies results, keyed by app_id, remote and scope
+type DependencyCache = Arc<Mutex<HashMap<String, (Instant, InstallDependencies)>>>;
+
+// How long a dependency check stays fresh
+const DEPENDENCY_CACHE_TTL: Duration = Duration::from_secs(60);
+
+// How long get_install_dependencies waits for the dependency list by default
+const DEPENDENCY_CHECK_TIMEOUT_SECS: u64 = 7;
+
+// Running get_install_dependencies probes, keyed like DependencyCache so they can be cancelled
+type DependencyCheckMap = Arc<Mutex<HashMap<String, Child>>>;
+
+fn dependency_cache_key(app_id: &str, remote: &str, scope: InstallScope) -> String {
+    format!("{}|{}|{}", app_id, remote, scope.flag())
 }
 
-#[tauri::command]
-async fn get_system_analytics(
-    app: tauri::AppHandle,
-    total_apps: Option<usize>,
-    total_runtimes: Option<usize>,
-    total_extensions: Option<usize>,
-    apps_with_updates: Option<usize>,
-) -> Result<SystemAnalytics, String> {
-    // Get disk usage
-    let disk_usage = get_disk_usage().await?;
-
-    // Use provided flatpak stats or fetch them if not provided
-    let flatpak_stats = if let (Some(apps), Some(runtimes), Some(extensions), Some(updates)) =
-        (total_apps, total_runtimes, total_extensions, apps_with_updates)
-    {
-        FlatpakStats {
-            total_apps: apps,
-            total_runtimes: runtimes,
-            total_extensions: extensions,
-            apps_with_updates: updates,
-        }
-    } else {
-        get_flatpak_stats(app.clone()).await?
-    };
+// Flatpak IDs only ever contain ASCII letters, digits, dots, underscores and dashes.
+// Anything else is rejected before it gets anywhere near a `sh -c` string, and so is
+// a leading '-', which flatpak would read as an option.
+fn is_valid_flatpak_id(id: &str) -> bool {
+    !id.is_empty()
+        && !id.starts_with('-')
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-')
+}
 
-    // Get system info
-    let system_info = get_system_info().await?;
-    Ok(SystemAnalytics {
-        disk_usage,
-        flatpak_stats,
-        system_info,
-    })
+// Write a file by writing `<path>.<pid>.<n>.tmp` and renaming it into place. The rename is
+// atomic within a directory, so a crash leaves either the old file or the new one, never half
+// of it. The temp name is unique so concurrent writers of one path don't share a temp file
+fn write_atomic(path: &std::path::Path, bytes: &[u8]) -> std::io::Result<()> {
+    static NEXT_TMP: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    let n = NEXT_TMP.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(format!(".{}.{}.tmp", std::process::id(), n));
+    let tmp_path = std::path::PathBuf::from(tmp_path);
+
+    let written = fs::File::create(&tmp_path).and_then(|mut file| {
+        file.write_all(bytes)?;
+        // Data on disk before the rename makes it visible
+        file.sync_all()
+    });
+    written
+        .and_then(|_| fs::rename(&tmp_path, path))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&tmp_path);
+        })
 }
 
-#[cfg(target_os = "linux")]
-async fn get_disk_usage() -> Result<DiskUsage, String> {
-    use std::io::BufRead;
+// Helper function to check whether `flatpak info <app_id>` finds an installed ref
+async fn is_app_installed(app: &tauri::AppHandle, app_id: &str) -> bool {
+    let shell = app.shell();
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
 
-    // Read /proc/mounts to find home partition
-    let mounts_file = File::open("/proc/mounts")
-        .map_err(|e| format!("Failed to open /proc/mounts: {}", e))?;
-    let reader = BufReader::new(mounts_file);
+    let output = if is_flatpak {
+        shell
+            .command("flatpak-spawn")
+            .args(["--host", "flatpak", "info", app_id])
+            .output()
+            .await
+    } else {
+        shell
+            .command("flatpak")
+            .args(["info", app_id])
+            .output()
+            .await
+    };
+    output.map(|o| o.status.success()).unwrap_or(false)
+}
 
-    let home_dir = std::env::var("HOME").unwrap_or_else(|_| "/home".to_string());
-    let mut home_device = String::new();
+// Which flatpak installation an operation targets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum InstallScope {
+    #[default]
+    User,
+    System,
+}
 
-    for line in reader.lines() {
-        if let Ok(line) = line {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                let mount_point = parts[1];
-                if home_dir.starts_with(mount_point) && mount_point.len() > home_device.len() {
-                    home_device = parts[0].to_string();
-                }
-            }
+impl InstallScope {
+    fn flag(self) -> &'static str {
+        match self {
+            InstallScope::User => "--user",
+            InstallScope::System => "--system",
         }
     }
+}
 
-    // Use statvfs to get filesystem stats
-    let output = Command::new("df")
-        .args(["-B1", &home_dir])
-        .output()
-        .map_err(|e| format!("Failed to execute df: {}", e))?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let lines: Vec<&str> = stdout.lines().collect();
+// Helper function returning the program and full argument list to run flatpak with `args`
+// System-wide changes need elevated privileges, so they go through pkexec on the host
+fn flatpak_command_parts(
+    is_flatpak: bool,
+    scope: InstallScope,
+    args: &[&str],
+) -> (&'static str, Vec<String>) {
+    let (program, prefix): (&'static str, &[&str]) = match (is_flatpak, scope) {
+        (true, InstallScope::User) => ("flatpak-spawn", &["--host", "flatpak"]),
+        (true, InstallScope::System) => ("flatpak-spawn", &["--host", "pkexec", "flatpak"]),
+        (false, InstallScope::User) => ("flatpak", &[]),
+        (false, InstallScope::System) => ("pkexec", &["flatpak"]),
+    };
 
-    if lines.len() >= 2 {
-        let parts: Vec<&str> = lines[1].split_whitespace().collect();
-        if parts.len() >= 5 {
-            let total = parts[1].parse::<f64>().unwrap_or(0.0) / 1_073_741_824.0; // Convert to GB
-            let used = parts[2].parse::<f64>().unwrap_or(0.0) / 1_073_741_824.0;
-            let available = parts[3].parse::<f64>().unwrap_or(0.0) / 1_073_741_824.0;
-            let usage_percent = if total > 0.0 { (used / total) * 100.0 } else { 0.0 };
+    let full_args = prefix
+        .iter()
+        .chain(args.iter())
+        .copied()
+        .map(String::from)
+        .collect();
+    (program, full_args)
+}
 
-            return Ok(DiskUsage {
-                total_gb: total,
-                used_gb: used,
-                available_gb: available,
-                usage_percent,
-            });
-        }
+// Remote used when the caller doesn't ask for a specific one
+const DEFAULT_REMOTE: &str = "flathub";
+
+// Repo file used to add flathub on systems that don't have it yet
+const FLATHUB_REPO_URL: &str = "https://flathub.org/repo/flathub.flatpakrepo";
+
+// Helper function to pick the remote to install from, falling back to flathub when empty
+fn resolve_remote(remote: Option<String>) -> Result<String, StoreError> {
+    let remote = remote
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty())
+        .unwrap_or_else(|| DEFAULT_REMOTE.to_string());
+    // Remote names follow the same character rules as app ids
+    if !is_valid_flatpak_id(&remote) {
+        return Err(StoreError::InvalidInput("invalid remote name".to_string()));
     }
-
-    Err("Failed to parse disk usage".to_string())
+    Ok(remote)
 }
 
-#[cfg(not(target_os = "linux"))]
-async fn get_disk_usage() -> Result<DiskUsage, String> {
-    Ok(DiskUsage {
-        total_gb: 0.0,
-        used_gb: 0.0,
-        available_gb: 0.0,
-        usage_percent: 0.0,
-    })
+// Helper function to turn the requested architecture into an `--arch=` flag
+// None (or empty) leaves it to flatpak, which installs the host's default arch
+fn resolve_arch_flag(arch: Option<String>) -> Result<Option<String>, StoreError> {
+    let Some(arch) = arch.map(|a| a.trim().to_string()).filter(|a| !a.is_empty()) else {
+        return Ok(None);
+    };
+    // Arch names ("x86_64", "aarch64") use a subset of the app id characters
+    if !is_valid_flatpak_id(&arch) {
+        return Err(StoreError::InvalidInput(format!(
+            "Invalid architecture: {}",
+            arch
+        )));
+    }
+    Ok(Some(format!("--arch={}", arch)))
 }
 
-async fn get_flatpak_stats(app: tauri::AppHandle) -> Result<FlatpakStats, String> {
-    let installed = get_installed_flatpaks(app.clone()).await?;
-    let updates = get_available_updates(app).await?;
+// Helper function to build interactive flatpak PTY command with -y flag (automatic confirmation)
+fn build_flatpak_interactive_cmd(
+    is_flatpak: bool,
+    app_id: &str,
+    remote: &str,
+    scope: InstallScope,
+    arch_flag: Option<&str>,
+) -> Result<String, StoreError> {
+    if !is_valid_flatpak_id(app_id) {
+        return Err(StoreError::InvalidInput("invalid app id".to_string()));
+    }
+    // System-wide installs need elevated privileges
+    let elevate = if scope == InstallScope::System {
+        "pkexec "
+    } else {
+        ""
+    };
+    let arch = arch_flag
+        .map(|flag| format!("{} ", flag))
+        .unwrap_or_default();
+    let base_cmd = format!(
+        "{}flatpak install -y {} {}{} {}",
+        elevate,
+        scope.flag(),
+        arch,
+        remote,
+        app_id
+    );
+    if is_flatpak {
+        Ok(format!(
+            "LANG=C script -q /dev/null -c \"flatpak-spawn --host {}\"",
+            base_cmd
+        ))
+    } else {
+        Ok(format!("LANG=C script -q /dev/null -c \"{}\"", base_cmd))
+    }
+}
 
-    Ok(FlatpakStats {
-        total_apps: installed.apps.len(),
-        total_runtimes: installed.runtimes.len(),
-        total_extensions: installed.extensions.len(),
-        apps_with_updates: updates.len(),
-    })
+// Helper function for dependency checking (with auto-yes responses)
+// No pkexec here even for system scope: flatpak resolves the list before asking for authorization
+fn build_flatpak_dependency_check_cmd(
+    is_flatpak: bool,
+    app_id: &str,
+    remote: &str,
+    scope: InstallScope,
+) -> Result<String, StoreError> {
+    if !is_valid_flatpak_id(app_id) {
+        return Err(StoreError::InvalidInput("invalid app id".to_string()));
+    }
+    let base_cmd = format!("flatpak install {} {} {}", scope.flag(), remote, app_id);
+    if is_flatpak {
+        Ok(format!(
+            "LANG=C printf \"y\\nn\\n\" | script -q /dev/null -c \"flatpak-spawn --host {}\"",
+            base_cmd
+        ))
+    } else {
+        Ok(format!(
+            "LANG=C printf \"y\\nn\\n\" | script -q /dev/null -c \"{}\"",
+            base_cmd
+        ))
+    }
 }
 
-async fn get_system_info() -> Result<SystemInfo, String> {
-    #[cfg(target_os = "linux")]
-    {
-        let os_name = std::fs::read_to_string("/etc/os-release")
-            .ok()
-            .and_then(|content| {
-                content.lines()
-                    .find(|line| line.starts_with("PRETTY_NAME="))
-                    .map(|line| line.trim_start_matches("PRETTY_NAME=").trim_matches('"').to_string())
-            })
-            .unwrap_or_else(|| "Linux".to_string());
+// Helper function to parse one numbered row of flatpak's install summary
+// Example: " 1.  org.kde.Platform  5.15-23.08  i  flathub  < 346,1 MB (partial)"
+fn parse_dependency_line(line: &str) -> Option<Dependency> {
+    // Look for lines that start with a number followed by a dot
+    // Format can be tabs or multiple spaces (especially when using 'script')
+    let rest = line.trim().strip_prefix(|c: char| c.is_ascii_digit())?;
+    if !rest.starts_with('.') {
+        return None;
+    }
 
-        let kernel_version = std::fs::read_to_string("/proc/version")
-            .ok()
-            .and_then(|content| {
-                content.split_whitespace().nth(2).map(|s| s.to_string())
-            })
-            .unwrap_or_else(|| "Unknown".to_string());
+    // Normalize line: replace non-breaking spaces and tabs with regular spaces
+    let normalized = line.replace(['\u{a0}', '\t'], " ");
 
-        let hostname = std::fs::read_to_string("/etc/hostname")
-            .unwrap_or_else(|_| "localhost".to_string())
-            .trim()
-            .to_string();
+    // Split by multiple spaces and filter out empty parts
+    let parts: Vec<&str> = normalized.split(' ').filter(|s| !s.is_empty()).collect();
 
-        Ok(SystemInfo {
-            os_name,
-            kernel_version,
-            hostname,
-        })
+    // A valid dependency line usually has: index, ID, branch, op, remote, size
+    // Example: ["1.", "org.kde.Platform", "5.15-23.08", "i", "flathub", "<", "346,1", "MB"]
+    if parts.len() < 5 {
+        return None;
     }
-
-    #[cfg(not(target_os = "linux"))]
-    {
-        Ok(SystemInfo {
-            os_name: "Unknown".to_string(),
-            kernel_version: "Unknown".to_string(),
-            hostname: "localhost".to_string(),
-        })
+    let name = parts[1].trim().to_string();
+
+    // The size is usually at the end. We join the last few parts if they look like a size.
+    // e.g., ["<", "346,1", "MB"] or ["2,2", "MB"]
+    let mut size_parts = Vec::new();
+    let mut found_size_start = false;
+
+    for &p in &parts[4..] {
+        if p == "<"
+            || p.chars().next().unwrap_or(' ').is_ascii_digit()
+            || p == "MB"
+            || p == "GB"
+            || p == "kB"
+            || p == "B"
+            || p == "bytes"
+        {
+            found_size_start = true;
+            size_parts.push(p);
+        } else if found_size_start {
+            // If we already started finding size parts and find something else, stop
+            break;
+        }
     }
-}
-
-// Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-#[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}! You've been greeted from Rust!", name)
-}
 
-#[tauri::command]
-fn check_first_launch(app: tauri::AppHandle) -> Result<bool, String> {
-    // Get app data directory (compatible with Flatpak)
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    // Pair each number with the unit that follows it ("346,1" "MB")
+    // The first size is the download, a second one (if printed) is the installed size
+    let sizes: Vec<String> = size_parts
+        .windows(2)
+        .filter(|w| {
+            w[0].starts_with(|c: char| c.is_ascii_digit())
+                && matches!(w[1], "B" | "bytes" | "kB" | "MB" | "GB")
+        })
+        .map(|w| format!("{} {}", w[0], w[1]))
+        .collect();
 
-    // Path for config file
-    let config_path = app_data_dir.join("appConf.json");
+    if name.is_empty() || sizes.is_empty() {
+        return None;
+    }
 
-    // Check if app has been initialized before
-    Ok(!config_path.exists())
+    let download_size = sizes[0].clone();
+    let installed_size = sizes
+        .get(1)
+        .cloned()
+        .unwrap_or_else(|| "Unknown".to_string());
+    Some(Dependency {
+        name,
+        download_bytes: parse_size_string(&download_size).unwrap_or(0),
+        installed_bytes: parse_size_string(&installed_size).unwrap_or(0),
+        download_size,
+        installed_size,
+        approximate: size_parts.contains(&"<"),
+        partial: normalized.contains("(partial)") || normalized.contains("(parcial)"),
+    })
 }
 
-#[tauri::command]
-fn initialize_app(app: tauri::AppHandle) -> Result<(), String> {
-    // Get app data directory (compatible with Flatpak)
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+// Helper function to parse size string from flatpak list/install output
+// Format examples: "715,3 MB", "1,2 GB", "16,9 MB", "2,5 kB", "346,1 MB"
+fn parse_size_string(size_str: &str) -> Option<u64> {
+    let parts: Vec<&str> = size_str.trim().split_whitespace().collect();
+    if parts.len() != 2 {
+        return None;
+    }
 
-    // Create app data directory if it doesn't exist
-    fs::create_dir_all(&app_data_dir)
-        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    // Replace comma with dot for parsing (locale-specific decimal separator)
+    let size_value = parts[0].replace(',', ".").parse::<f64>().ok()?;
+    let unit = parts[1];
 
-    // Create cacheImages directory
-    let cache_images_dir = app_data_dir.join("cacheImages");
-    fs::create_dir_all(&cache_images_dir)
-        .map_err(|e| format!("Failed to create cacheImages directory: {}", e))?;
+    let bytes = match unit {
+        "B" | "bytes" => size_value as u64,
+        "kB" | "KB" => (size_value * 1_000.0) as u64,
+        "MB" => (size_value * 1_000_000.0) as u64,
+        "GB" => (size_value * 1_000_000_000.0) as u64,
+        "TB" => (size_value * 1_000_000_000_000.0) as u64,
+        _ => return None,
+    };
 
-    // Path for config file
-    let config_path = app_data_dir.join("appConf.json");
+    Some(bytes)
+}
 
-    // Create config file with JSON format
-    let config_content = r#"{
-  "initialized": true,
-  "version": "1.0.0",
-  "firstLaunchCompleted": true
-}"#;
+// Helper function to read the per-ref download sizes from a flatpak transaction table
+// Format: " 1.     org.gnome.Platform    46    u    flathub    < 123,4 MB"
+fn parse_transaction_download_sizes(output: &str) -> Vec<UpdateSize> {
+    let mut sizes = Vec::new();
+    for line in output.lines() {
+        let normalized = line.replace(['\u{a0}', '\t'], " ");
+        let parts: Vec<&str> = normalized.split_whitespace().collect();
+        let is_list_entry = parts
+            .first()
+            .and_then(|p| p.strip_suffix('.'))
+            .map(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+            .unwrap_or(false);
+        if !is_list_entry {
+            continue;
+        }
 
-    fs::write(&config_path, config_content)
-        .map_err(|e| format!("Failed to create config file: {}", e))?;
+        // Skip markers such as "[✓]" between the index and the ID
+        let Some(app_id) = parts[1..].iter().find(|p| !p.starts_with('[')) else {
+            continue;
+        };
+        // The first number followed by a unit is the download size
+        let download_bytes = parts
+            .windows(2)
+            .find_map(|w| parse_size_string(&format!("{} {}", w[0], w[1])))
+            .unwrap_or(0);
+
+        sizes.push(UpdateSize {
+            app_id: app_id.to_string(),
+            download_bytes,
+        });
+    }
+    sizes
+}
 
-    Ok(())
+// Helper function to extract the progress percentage from a flatpak output line
+// A chunk may hold several \r-separated updates, the last one wins
+fn parse_flatpak_progress(line: &str) -> Option<u8> {
+    let percent = PROGRESS_REGEX
+        .captures_iter(line)
+        .last()?
+        .get(1)?
+        .as_str()
+        .parse::<u32>()
+        .ok()?;
+    Some(percent.min(100) as u8)
 }
 
-#[tauri::command]
-fn get_app_data_path(app: tauri::AppHandle, subpath: String) -> Result<String, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+// Helper function to emit a structured progress event when the output carries a percentage
+fn emit_install_progress<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    app_id: Option<&str>,
+    output: &str,
+) {
+    if let Some(percent) = parse_flatpak_progress(output) {
+        let _ = app.emit(
+            "install-progress",
+            InstallProgress {
+                app_id: app_id.map(String::from),
+                percent,
+            },
+        );
+    }
+}
 
-    let full_path = app_data_dir.join(subpath);
-    Ok(full_path.to_string_lossy().to_string())
+// Remove terminal escape sequences from PTY output. Plain \r is kept so the
+// frontend can still overwrite progress lines.
+fn strip_ansi(text: &str) -> String {
+    ANSI_ESCAPE_REGEX.replace_all(text, "").into_owned()
 }
 
-#[tauri::command]
-fn get_cache_image_dir(app: tauri::AppHandle) -> Result<String, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+// Length of a UTF-8 sequence cut off at the end of `bytes` (0 if the last character is whole)
+fn incomplete_utf8_suffix(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - back];
+        // Skip continuation bytes until the sequence's lead byte
+        if byte & 0xC0 == 0x80 {
+            continue;
+        }
+        let needed = match byte {
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => 1,
+        };
+        return if needed > back { back } else { 0 };
+    }
+    0
+}
 
-    let cache_images_dir = app_data_dir.join("cacheImages");
-    Ok(cache_images_dir.to_string_lossy().to_string())
+// Decode a PTY read. A multibyte character split across reads is held back in `carry`
+// and completed by the next call instead of turning into replacement characters.
+fn decode_pty_chunk(carry: &mut Vec<u8>, bytes: &[u8]) -> String {
+    carry.extend_from_slice(bytes);
+    let rest = carry.split_off(carry.len() - incomplete_utf8_suffix(carry));
+    let text = String::from_utf8_lossy(carry).into_owned();
+    *carry = rest;
+    text
 }
 
-#[tauri::command]
-fn clear_old_cache(app: tauri::AppHandle) -> Result<(), String> {
+// Leading reverse-DNS segments that never name the developer
+const ID_TLD_PREFIXES: &[&str] = &["com", "org", "io", "net", "dev", "app"];
+// Code hosts whose ids carry the account name after the host: io.github.<user>.<app>
+const ID_CODE_HOSTS: &[&str] = &["github", "gitlab"];
+
+// Helper function to extract developer name from app_id
+// Example: io.github.N3kosempai.klia-store -> N3kosempai
+// Example: org.mozilla.firefox -> mozilla
+// Example: com.github.tchx84.Flatseal -> tchx84
+// Example: io.Firefox -> None (only the app name is left once the TLD is removed)
+fn extract_developer(app_id: &str) -> Option<String> {
+    let parts: Vec<&str> = app_id.split('.').collect();
+    if parts.len() < 2 {
+        return None;
+    }
+
+    if !ID_TLD_PREFIXES.contains(&parts[0]) {
+        // Unknown prefix: fall back to the penultimate segment
+        return Some(parts[parts.len() - 2].to_string());
+    }
+
+    let rest = &parts[1..];
+    if rest.len() >= 3 && ID_CODE_HOSTS.contains(&rest[0]) {
+        return Some(rest[1].to_string());
+    }
+
+    // The last segment is the app itself; the first one left is its owner
+    if rest.len() >= 2 {
+        Some(rest[0].to_string())
+    } else {
+        None
+    }
+}
+
+// Helper function to parse `flatpak info <app_id>` (run with LANG=C)
+// The first line is "Name - summary", then "Key: value" fields right-aligned on the colon
+fn parse_flatpak_info(app_id: &str, output: &str) -> InstalledAppDetail {
+    let mut fields: HashMap<&str, &str> = HashMap::new();
+    for line in output.lines() {
+        if let Some((key, value)) = line.trim().split_once(": ") {
+            // Keep the first occurrence; later sections repeat nothing we need
+            fields.entry(key.trim()).or_insert(value.trim());
+        }
+    }
+
+    let name = output
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.contains(": "))
+        .map(|line| line.split(" - ").next().unwrap_or(line).trim().to_string());
+    let field = |key: &str| fields.get(key).map(|v| v.to_string());
+
+    InstalledAppDetail {
+        app_id: app_id.to_string(),
+        name,
+        app_ref: field("Ref").unwrap_or_default(),
+        version: field("Version"),
+        branch: field("Branch"),
+        origin: field("Origin"),
+        installation: field("Installation"),
+        installed_size: fields.get("Installed").and_then(|v| parse_size_string(v)),
+        runtime: field("Runtime"),
+        commit: field("Commit"),
+        eol: field("End-of-life"),
+        eol_rebase: field("End-of-life-rebase"),
+    }
+}
+
+// Helper function to split a runtime or sdk ref ("org.kde.Platform/x86_64/6.7")
+fn parse_required_ref(value: &str) -> Option<RequiredRef> {
+    let mut parts = value.trim().split('/');
+    let id = parts.next().filter(|id| !id.is_empty())?;
+    Some(RequiredRef {
+        id: id.to_string(),
+        arch: parts.next().unwrap_or_default().to_string(),
+        branch: parts.next().unwrap_or_default().to_string(),
+    })
+}
+
+// Helper function to parse `flatpak remote-info <remote> <app_id>` (run with LANG=C)
+// Same "Key: value" layout as `flatpak info`, the commit fields follow a blank line
+fn parse_requirements(app_id: &str, output: &str) -> Requirements {
+    let mut fields: HashMap<&str, &str> = HashMap::new();
+    for line in output.lines() {
+        if let Some((key, value)) = line.trim().split_once(": ") {
+            fields.entry(key.trim()).or_insert(value.trim());
+        }
+    }
+
+    Requirements {
+        app_id: app_id.to_string(),
+        runtime: fields.get("Runtime").and_then(|v| parse_required_ref(v)),
+        sdk: fields.get("Sdk").and_then(|v| parse_required_ref(v)),
+        download_bytes: fields.get("Download").and_then(|v| parse_size_string(v)),
+        installed_bytes: fields.get("Installed").and_then(|v| parse_size_string(v)),
+    }
+}
+
+// Helper function to parse `flatpak remote-info --log` (run with LANG=C)
+// Every commit block starts with a "Commit:" line followed by its Subject and Date
+fn parse_commit_log(output: &str) -> Vec<CommitEntry> {
+    let mut entries: Vec<CommitEntry> = Vec::new();
+    for line in output.lines() {
+        let Some((key, value)) = line.trim().split_once(':') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key.trim() {
+            "Commit" => entries.push(CommitEntry {
+                commit: value,
+                subject: String::new(),
+                date: String::new(),
+            }),
+            "Subject" => {
+                if let Some(entry) = entries.last_mut() {
+                    entry.subject = value;
+                }
+            }
+            "Date" => {
+                if let Some(entry) = entries.last_mut() {
+                    entry.date = value;
+                }
+            }
+            _ => {}
+        }
+    }
+    entries
+}
+
+// Helper function to find the installed app an extension belongs to
+// `sorted_app_ids` must be sorted; each dotted prefix of the extension id is binary searched,
+// longest first, so org.kde.kdenlive.Codecs resolves to org.kde.kdenlive
+fn find_parent_app_id<'a>(sorted_app_ids: &[&'a str], extension_id: &str) -> Option<&'a str> {
+    extension_id
+        .rmatch_indices('.')
+        .map(|(i, _)| &extension_id[..i])
+        .find_map(|prefix| {
+            sorted_app_ids
+                .binary_search(&prefix)
+                .ok()
+                .map(|idx| sorted_app_ids[idx])
+        })
+}
+
+// Helper function to get a short summary of app permissions (camera/files/storage) from flatpak
+fn summarize_app_permissions(app_id: &str, is_flatpak: bool) -> Option<Vec<String>> {
+    let output = if is_flatpak {
+        Command::new("flatpak-spawn")
+            .args(&["--host", "flatpak", "info", "--show-permissions", app_id])
+            .output()
+            .ok()?
+    } else {
+        Command::new("flatpak")
+            .args(&["info", "--show-permissions", app_id])
+            .output()
+            .ok()?
+    };
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut permissions = Vec::new();
+    let mut has_camera = false;
+    let mut has_files = false;
+    let mut has_storage = false;
+
+    for line in stdout.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('[') {
+            continue;
+        }
+
+        // Look for specific permissions we care about
+        // Camera: devices=all (full device access) or specific video devices
+        if !has_camera && line.contains("devices=") {
+            let devices_part = line.split("devices=").nth(1).unwrap_or("");
+            // Check if "all" is in the devices list (e.g., "dri;all;" or "all;")
+            if devices_part.split(';').any(|d| d.trim() == "all") {
+                permissions.push("camera".to_string());
+                has_camera = true;
+            }
+        }
+
+        // Files: filesystems= with any value
+        if !has_files && line.contains("filesystems=") {
+            let filesystems_part = line.split("filesystems=").nth(1).unwrap_or("");
+            let filesystems_value = filesystems_part.split(';').next().unwrap_or("").trim();
+            if !filesystems_value.is_empty() {
+                permissions.push("files".to_string());
+                has_files = true;
+            }
+        }
+
+        // Storage: persist= or filesystems containing home/host
+        if !has_storage {
+            if line.contains("persist=") {
+                let persist_part = line.split("persist=").nth(1).unwrap_or("");
+                let persist_value = persist_part.split(';').next().unwrap_or("").trim();
+                if !persist_value.is_empty() {
+                    permissions.push("storage".to_string());
+                    has_storage = true;
+                }
+            } else if line.contains("filesystems=") {
+                if line.contains("home") || line.contains("host") || line.contains("xdg-download") {
+                    permissions.push("storage".to_string());
+                    has_storage = true;
+                }
+            }
+        }
+    }
+
+    if permissions.is_empty() {
+        None
+    } else {
+        Some(permissions)
+    }
+}
+
+#[derive(Serialize)]
+struct AppPermissions {
+    filesystems: Vec<String>,
+    devices: Vec<String>,
+    sockets: Vec<String>,
+    shared: Vec<String>,
+    session_bus: Vec<String>,
+}
+
+// Helper function to parse `flatpak info --show-permissions` output
+// [Context] holds "key=a;b;" lists, [Session Bus Policy] holds "name=policy" lines
+fn parse_app_permissions(output: &str) -> AppPermissions {
+    let mut permissions = AppPermissions {
+        filesystems: Vec::new(),
+        devices: Vec::new(),
+        sockets: Vec::new(),
+        shared: Vec::new(),
+        session_bus: Vec::new(),
+    };
+    let mut section = "";
+
+    for line in output.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            section = match line {
+                "[Context]" => "context",
+                "[Session Bus Policy]" => "session_bus",
+                _ => "",
+            };
+            continue;
+        }
+
+        match section {
+            "context" => {
+                let Some((key, value)) = line.split_once('=') else {
+                    continue;
+                };
+                let values: Vec<String> = value
+                    .split(';')
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(String::from)
+                    .collect();
+                match key.trim() {
+                    "filesystems" => permissions.filesystems = values,
+                    "devices" => permissions.devices = values,
+                    "sockets" => permissions.sockets = values,
+                    "shared" => permissions.shared = values,
+                    _ => {}
+                }
+            }
+            "session_bus" => permissions.session_bus.push(line.to_string()),
+            _ => {}
+        }
+    }
+
+    permissions
+}
+
+#[derive(Serialize)]
+struct UpdateAvailable {
+    app_id: String,
+    new_version: String,
+    branch: String,
+}
+
+#[derive(Serialize)]
+struct UpdateSize {
+    app_id: String,
+    download_bytes: u64,
+}
+
+// One entry of `flatpak remote-info --log`, newest first
+#[derive(Debug, Serialize)]
+struct CommitEntry {
+    commit: String,
+    subject: String,
+    date: String,
+}
+
+// A runtime or sdk ref an app is built against
+#[derive(Debug, Serialize)]
+struct RequiredRef {
+    id: String,
+    arch: String,
+    branch: String,
+}
+
+// What installing an app pulls in, from `flatpak remote-info`
+#[derive(Debug, Serialize)]
+struct Requirements {
+    app_id: String,
+    runtime: Option<RequiredRef>,
+    sdk: Option<RequiredRef>,
+    download_bytes: Option<u64>,
+    installed_bytes: Option<u64>,
+}
+
+// Payload of the "install-progress" event (app_id is None for system-wide updates)
+#[derive(Clone, Serialize)]
+struct InstallProgress {
+    app_id: Option<String>,
+    percent: u8,
+}
+
+// Sent next to install-completed so listeners can tell which operation finished
+// when several share the channel. app_id is "system" for system-wide updates.
+const OPERATION_COMPLETED_EVENT: &str = "operation-completed";
+
+#[derive(Clone, Serialize)]
+struct OperationCompleted {
+    app_id: String,
+    action: &'static str,
+    code: i32,
+    success: bool,
+}
+
+fn emit_operation_completed(app: &tauri::AppHandle, app_id: &str, action: &'static str, code: i32) {
+    mark_installed_changed(app);
+    let _ = app.emit(
+        OPERATION_COMPLETED_EVENT,
+        OperationCompleted {
+            app_id: app_id.to_string(),
+            action,
+            code,
+            success: code == 0,
+        },
+    );
+}
+
+#[derive(Serialize)]
+struct FlatpakRemote {
+    name: String,
+    title: String,
+    url: String,
+    is_user: bool,
+}
+
+#[derive(Serialize)]
+struct SearchResult {
+    app_id: String,
+    name: String,
+    description: String,
+    version: String,
+    branch: String,
+    // Every configured remote that carries the app
+    remotes: Vec<String>,
+}
+
+// Order of search_flatpak results; relevance keeps flatpak's own ordering
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SearchSort {
+    #[default]
+    Relevance,
+    Name,
+}
+
+#[derive(Serialize)]
+struct FlatpakStatus {
+    available: bool,
+    version: Option<String>,
+    flathub_configured: bool,
+}
+
+#[derive(Serialize)]
+struct HostAccess {
+    can_spawn_host: bool,
+    detail: String,
+}
+
+#[derive(Clone, Serialize)]
+struct Dependency {
+    name: String,
+    download_size: String,
+    installed_size: String,
+    download_bytes: u64,
+    installed_bytes: u64,
+    // The size had a "<", flatpak only knows an upper bound
+    approximate: bool,
+    // Marked "(partial)", only part of the ref will be downloaded
+    partial: bool,
+}
+
+#[derive(Clone, Serialize)]
+struct InstallDependencies {
+    items: Vec<Dependency>,
+    total_download_bytes: u64,
+    total_installed_bytes: u64,
+    // True when flatpak only gave an upper bound ("<") or a partial download size
+    is_partial: bool,
+    // Nothing to install, the empty list doesn't mean a free install
+    already_installed: bool,
+}
+
+// System Analytics Struct
+#[derive(Serialize)]
+struct SystemAnalytics {
+    disk_usage: DiskUsage,
+    flatpak_stats: FlatpakStats,
+    system_info: SystemInfo,
+}
+
+#[derive(Serialize)]
+struct DiskUsage {
+    total_gb: f64,
+    used_gb: f64,
+    available_gb: f64,
+    usage_percent: f64,
+}
+
+#[derive(Serialize)]
+struct FlatpakStats {
+    total_apps: usize,
+    total_runtimes: usize,
+    total_extensions: usize,
+    apps_with_updates: usize,
+}
+
+#[derive(Serialize)]
+struct DiskSpaceInfo {
+    available_bytes: u64,
+    sufficient: bool,
+}
+
+#[derive(Serialize)]
+struct SystemInfo {
+    os_name: String,
+    kernel_version: String,
+    hostname: String,
+}
+
+// Get system analytics data
+#[tauri::command]
+async fn get_app_permissions_batch(
+    _app: tauri::AppHandle,
+    app_ids: Vec<String>,
+) -> Result<std::collections::HashMap<String, Vec<String>>, StoreError> {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+    let result = Arc::new(Mutex::new(HashMap::new()));
+
+    // Parallelize permission fetching using native threads
+    // Limit concurrent threads to avoid overwhelming the system
+    let chunk_size = 10; // Process 10 apps at a time
+    let mut app_id_chunks: Vec<Vec<String>> = Vec::new();
+
+    for chunk in app_ids.chunks(chunk_size) {
+        app_id_chunks.push(chunk.to_vec());
+    }
+
+    for chunk in app_id_chunks.iter() {
+        let mut handles = Vec::new();
+
+        for app_id in chunk {
+            let result_clone = Arc::clone(&result);
+            let app_id_clone = app_id.clone();
+            let handle = thread::spawn(move || {
+                if let Some(perms) = summarize_app_permissions(&app_id_clone, is_flatpak) {
+                    result_clone.lock().unwrap().insert(app_id_clone, perms);
+                }
+            });
+            handles.push(handle);
+        }
+
+        // Wait for this chunk to complete
+        for handle in handles {
+            let _ = handle.join();
+        }
+    }
+
+    let final_result = result.lock().unwrap().clone();
+    Ok(final_result)
+}
+
+#[tauri::command]
+async fn get_system_analytics(
+    app: tauri::AppHandle,
+    total_apps: Option<usize>,
+    total_runtimes: Option<usize>,
+    total_extensions: Option<usize>,
+    apps_with_updates: Option<usize>,
+) -> Result<SystemAnalytics, StoreError> {
+    // Get disk usage
+    let disk_usage = get_disk_usage().await?;
+
+    // Use provided flatpak stats or fetch them if not provided
+    let flatpak_stats = if let (Some(apps), Some(runtimes), Some(extensions), Some(updates)) =
+        (total_apps, total_runtimes, total_extensions, apps_with_updates)
+    {
+        FlatpakStats {
+            total_apps: apps,
+            total_runtimes: runtimes,
+            total_extensions: extensions,
+            apps_with_updates: updates,
+        }
+    } else {
+        get_flatpak_stats(app.clone()).await?
+    };
+
+    // Get system info
+    let system_info = get_system_info().await?;
+    Ok(SystemAnalytics {
+        disk_usage,
+        flatpak_stats,
+        system_info,
+    })
+}
+
+#[cfg(target_os = "linux")]
+async fn get_disk_usage() -> Result<DiskUsage, String> {
+    use std::io::BufRead;
+
+    // Read /proc/mounts to find home partition
+    let mounts_file = File::open("/proc/mounts")
+        .map_err(|e| format!("Failed to open /proc/mounts: {}", e))?;
+    let reader = BufReader::new(mounts_file);
+
+    let home_dir = std::env::var("HOME").unwrap_or_else(|_| "/home".to_string());
+    let mut home_device = String::new();
+
+    for line in reader.lines() {
+        if let Ok(line) = line {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() >= 2 {
+                let mount_point = parts[1];
+                if home_dir.starts_with(mount_point) && mount_point.len() > home_device.len() {
+                    home_device = parts[0].to_string();
+                }
+            }
+        }
+    }
+
+    // Use statvfs to get filesystem stats
+    let output = Command::new("df")
+        .args(["-B1", &home_dir])
+        .output()
+        .map_err(|e| format!("Failed to execute df: {}", e))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    if lines.len() >= 2 {
+        let parts: Vec<&str> = lines[1].split_whitespace().collect();
+        if parts.len() >= 5 {
+            let total = parts[1].parse::<f64>().unwrap_or(0.0) / 1_073_741_824.0; // Convert to GB
+            let used = parts[2].parse::<f64>().unwrap_or(0.0) / 1_073_741_824.0;
+            let available = parts[3].parse::<f64>().unwrap_or(0.0) / 1_073_741_824.0;
+            let usage_percent = if total > 0.0 { (used / total) * 100.0 } else { 0.0 };
+
+            return Ok(DiskUsage {
+                total_gb: total,
+                used_gb: used,
+                available_gb: available,
+                usage_percent,
+            });
+        }
+    }
+
+    Err("Failed to parse disk usage".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn get_disk_usage() -> Result<DiskUsage, String> {
+    Ok(DiskUsage {
+        total_gb: 0.0,
+        used_gb: 0.0,
+        available_gb: 0.0,
+        usage_percent: 0.0,
+    })
+}
+
+// Helper function to get the free bytes of the filesystem holding `path` via `df`
+// Inside the sandbox df runs on the host, so the path is resolved against the host filesystem
+fn available_bytes_at(path: &str, is_flatpak: bool) -> Option<u64> {
+    let output = if is_flatpak {
+        Command::new("flatpak-spawn")
+            .args(["--host", "df", "-B1", "--output=avail", path])
+            .output()
+            .ok()?
+    } else {
+        Command::new("df")
+            .args(["-B1", "--output=avail", path])
+            .output()
+            .ok()?
+    };
+
+    if !output.status.success() {
+        return None;
+    }
+
+    // First line is the "Avail" header
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .nth(1)?
+        .trim()
+        .parse::<u64>()
+        .ok()
+}
+
+#[tauri::command]
+async fn check_disk_space(
+    _app: tauri::AppHandle,
+    required_bytes: u64,
+) -> Result<DiskSpaceInfo, StoreError> {
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    // HOME is the real host home both inside and outside the sandbox
+    let home_dir = std::env::var("HOME").unwrap_or_else(|_| "/home".to_string());
+    let flatpak_user_dir = format!("{}/.local/share/flatpak", home_dir);
+
+    // The user installation may not exist yet, fall back to the home partition
+    let available_bytes = available_bytes_at(&flatpak_user_dir, is_flatpak)
+        .or_else(|| available_bytes_at(&home_dir, is_flatpak))
+        .ok_or_else(|| "Failed to read available disk space".to_string())?;
+
+    Ok(DiskSpaceInfo {
+        available_bytes,
+        sufficient: available_bytes >= required_bytes,
+    })
+}
+
+#[derive(Debug, Default, Serialize)]
+struct SizeSummary {
+    user_bytes: u64,
+    system_bytes: u64,
+    // Split by kind, so apps_bytes + runtime_bytes == total_bytes
+    apps_bytes: u64,
+    runtime_bytes: u64,
+    total_bytes: u64,
+}
+
+// Helper function to add up `flatpak list --columns=ref,installation,options,size`.
+// Each ref is counted once per installation: a runtime shared by many apps is
+// listed (and counted) a single time, while a copy installed both per-user and
+// system-wide really does take space twice. Files ostree deduplicates between
+// refs of the same installation are still counted per ref, so the total is an
+// upper bound on what's on disk.
+fn parse_installation_sizes(output: &str) -> SizeSummary {
+    let mut seen: HashSet<(&str, &str)> = HashSet::new();
+    let mut summary = SizeSummary::default();
+    for line in output.lines() {
+        let parts: Vec<&str> = line.split('\t').map(str::trim).collect();
+        let [flatpak_ref, installation, options, size, ..] = parts[..] else {
+            continue;
+        };
+        if flatpak_ref.is_empty() || !seen.insert((installation, flatpak_ref)) {
+            continue;
+        }
+        let Some(bytes) = parse_size_string(size) else {
+            continue;
+        };
+
+        // Custom installations are system-wide too
+        if installation == "user" {
+            summary.user_bytes += bytes;
+        } else {
+            summary.system_bytes += bytes;
+        }
+        if options.contains("runtime") {
+            summary.runtime_bytes += bytes;
+        } else {
+            summary.apps_bytes += bytes;
+        }
+        summary.total_bytes += bytes;
+    }
+    summary
+}
+
+/// Space used by every installed ref, per installation and split between apps
+/// and runtimes. See parse_installation_sizes for how shared runtimes count.
+#[tauri::command]
+async fn get_total_flatpak_size(app: tauri::AppHandle) -> Result<SizeSummary, StoreError> {
+    let shell = app.shell();
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    let list_args = ["list", "--columns=ref,installation,options,size"];
+    let output = if is_flatpak {
+        shell
+            .command("flatpak-spawn")
+            .args(["--host", "flatpak"])
+            .args(list_args)
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
+    } else {
+        shell
+            .command("flatpak")
+            .args(list_args)
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak", e))?
+    };
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
+    }
+
+    Ok(parse_installation_sizes(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+async fn get_flatpak_stats(app: tauri::AppHandle) -> Result<FlatpakStats, StoreError> {
+    let installed = get_installed_flatpaks(app.clone(), None, None).await?;
+    let updates = get_available_updates(app).await?;
+
+    Ok(FlatpakStats {
+        total_apps: installed.apps.len(),
+        total_runtimes: installed.runtimes.len(),
+        total_extensions: installed.extensions.len(),
+        apps_with_updates: updates.len(),
+    })
+}
+
+async fn get_system_info() -> Result<SystemInfo, String> {
+    #[cfg(target_os = "linux")]
+    {
+        let os_name = std::fs::read_to_string("/etc/os-release")
+            .ok()
+            .and_then(|content| {
+                content.lines()
+                    .find(|line| line.starts_with("PRETTY_NAME="))
+                    .map(|line| line.trim_start_matches("PRETTY_NAME=").trim_matches('"').to_string())
+            })
+            .unwrap_or_else(|| "Linux".to_string());
+
+        let kernel_version = std::fs::read_to_string("/proc/version")
+            .ok()
+            .and_then(|content| {
+                content.split_whitespace().nth(2).map(|s| s.to_string())
+            })
+            .unwrap_or_else(|| "Unknown".to_string());
+
+        let hostname = std::fs::read_to_string("/etc/hostname")
+            .unwrap_or_else(|_| "localhost".to_string())
+            .trim()
+            .to_string();
+
+        Ok(SystemInfo {
+            os_name,
+            kernel_version,
+            hostname,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        Ok(SystemInfo {
+            os_name: "Unknown".to_string(),
+            kernel_version: "Unknown".to_string(),
+            hostname: "localhost".to_string(),
+        })
+    }
+}
+
+// Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+#[tauri::command]
+fn greet(name: &str) -> String {
+    format!("Hello, {}! You've been greeted from Rust!", name)
+}
+
+#[tauri::command]
+fn check_first_launch(app: tauri::AppHandle) -> Result<bool, StoreError> {
+    // Check if app has been initialized before
+    Ok(!config::load(&app)?.first_launch_completed)
+}
+
+#[tauri::command]
+fn initialize_app(app: tauri::AppHandle) -> Result<(), StoreError> {
+    // Get app data directory (compatible with Flatpak)
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    // Create app data directory if it doesn't exist
+    fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+
+    // Create the image cache directory
+    let cache_images_dir = image_cache_dir(&app)?;
+    fs::create_dir_all(&cache_images_dir)
+        .map_err(|e| format!("Failed to create cacheImages directory: {}", e))?;
+
+    // Mark the first launch as done, keeping any settings already saved
+    let mut config = config::load(&app)?;
+    config.initialized = true;
+    config.first_launch_completed = true;
+    config::save(&app, &config)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_app_data_path(app: tauri::AppHandle, subpath: String) -> Result<String, StoreError> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    let full_path = app_data_dir.join(subpath);
+    Ok(full_path.to_string_lossy().to_string())
+}
+
+// Default image cache, cacheImages in the app data directory
+fn default_image_cache_dir(app: &tauri::AppHandle) -> Result<std::path::PathBuf, StoreError> {
     let app_data_dir = app
         .path()
         .app_data_dir()
         .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data_dir.join("cacheImages"))
+}
+
+// Subdirectory the cache gets inside a chosen directory, so pruning, clearing and
+// moving the cache never touch the user's own files next to it
+const IMAGE_CACHE_SUBDIR: &str = "klia-store-images";
+
+// Where cached images live: a subdirectory of the configured override, or the default
+fn resolve_image_cache_dir(app: &tauri::AppHandle) -> Result<std::path::PathBuf, StoreError> {
+    match config::load(app)?.cache_dir_override {
+        Some(dir) => Ok(std::path::PathBuf::from(dir).join(IMAGE_CACHE_SUBDIR)),
+        None => default_image_cache_dir(app),
+    }
+}
+
+// The image cache directory, read from the config on first use and then kept here so
+// icon requests don't load (and possibly rewrite) the config. set_cache_dir updates it
+#[derive(Default)]
+struct ImageCacheDir(std::sync::RwLock<Option<std::path::PathBuf>>);
+
+fn image_cache_dir(app: &tauri::AppHandle) -> Result<std::path::PathBuf, StoreError> {
+    let state = app.state::<ImageCacheDir>();
+    if let Some(dir) = state.0.read().unwrap().clone() {
+        return Ok(dir);
+    }
+    let dir = resolve_image_cache_dir(app)?;
+    *state.0.write().unwrap() = Some(dir.clone());
+    Ok(dir)
+}
+
+// Cached files are named after the key's hash in hex
+fn is_cache_hash(stem: &str) -> bool {
+    !stem.is_empty() && stem.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// "{hash:x}.{ext}" with one of the cached image extensions
+fn is_cached_image_name(name: &str) -> bool {
+    name.split_once('.')
+        .is_some_and(|(hash, ext)| is_cache_hash(hash) && CACHED_IMAGE_EXTENSIONS.contains(&ext))
+}
+
+// A cached image or its "{hash:x}.meta" validators, the only files the cache owns
+fn is_cache_file_name(name: &str) -> bool {
+    is_cached_image_name(name) || name.strip_suffix(".meta").is_some_and(is_cache_hash)
+}
+
+// Helper function to move every cached image and .meta file from `from` into `to`
+// rename() fails across filesystems, those files are copied and then removed
+fn move_cached_files(from: &std::path::Path, to: &std::path::Path) -> u64 {
+    let Ok(entries) = fs::read_dir(from) else {
+        return 0;
+    };
+    let mut moved = 0;
+    for entry in entries.flatten() {
+        let is_cache_file = entry.file_name().to_str().is_some_and(is_cache_file_name);
+        if !is_cache_file || !entry.path().is_file() {
+            continue;
+        }
+        let target = to.join(entry.file_name());
+        let ok = fs::rename(entry.path(), &target).is_ok()
+            || (fs::copy(entry.path(), &target).is_ok() && fs::remove_file(entry.path()).is_ok());
+        if ok {
+            moved += 1;
+        }
+    }
+    moved
+}
+
+// Move the image cache to another directory (another drive, say), None goes back to the default
+// Images go in a klia-store-images subdirectory, which is what gets returned. The directory
+// must be writable; inside the sandbox that needs a filesystem override
+#[tauri::command]
+fn set_cache_dir(app: tauri::AppHandle, path: Option<String>) -> Result<String, StoreError> {
+    let path = path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+    if let Some(path) = &path {
+        if !std::path::Path::new(path).is_absolute() {
+            return Err(StoreError::InvalidInput(format!(
+                "Cache directory must be an absolute path: {}",
+                path
+            )));
+        }
+    }
+
+    let old_dir = image_cache_dir(&app)?;
+    let new_dir = match &path {
+        Some(path) => std::path::PathBuf::from(path).join(IMAGE_CACHE_SUBDIR),
+        None => default_image_cache_dir(&app)?,
+    };
+
+    // Check the new directory is usable before switching to it
+    let probe = new_dir.join(".write-test");
+    fs::create_dir_all(&new_dir)
+        .and_then(|_| fs::write(&probe, b""))
+        .and_then(|_| fs::remove_file(&probe))
+        .map_err(|e| {
+            StoreError::PermissionDenied(format!(
+                "Cache directory {} isn't writable: {}",
+                new_dir.display(),
+                e
+            ))
+        })?;
+
+    let mut config = config::load(&app)?;
+    config.cache_dir_override = path;
+    config::save(&app, &config)?;
+    *app.state::<ImageCacheDir>().0.write().unwrap() = Some(new_dir.clone());
+
+    // The frontend loads cached images through the asset protocol
+    let _ = app.asset_protocol_scope().allow_directory(&new_dir, true);
+
+    if old_dir != new_dir {
+        let moved = move_cached_files(&old_dir, &new_dir);
+        log::info!(
+            "[set_cache_dir] Moved {} cached files from {} to {}",
+            moved,
+            old_dir.display(),
+            new_dir.display()
+        );
+    }
+
+    Ok(new_dir.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn get_cache_image_dir(app: tauri::AppHandle) -> Result<String, StoreError> {
+    let cache_images_dir = image_cache_dir(&app)?;
+    Ok(cache_images_dir.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn clear_old_cache(app: tauri::AppHandle) -> Result<(), StoreError> {
+    let cache_images_dir = image_cache_dir(&app)?;
+    let index_path = cache_images_dir.join("index.json");
+
+    if index_path.exists() {
+        println!("[Cache] Old cache system detected (index.json found). Clearing...");
+        if cache_images_dir.exists() {
+            fs::remove_dir_all(&cache_images_dir)
+                .map_err(|e| format!("Failed to clear old cache directory: {}", e))?;
+
+            // Recreate the directory
+            fs::create_dir_all(&cache_images_dir)
+                .map_err(|e| format!("Failed to recreate cache directory: {}", e))?;
+        }
+    }
+
+    // Files cached before the type was sniffed may carry the URL's extension; fix them once
+    let marker = cache_images_dir.join(IMAGE_NAMES_MARKER);
+    if cache_images_dir.exists() && !marker.exists() {
+        let renamed = rename_mismatched_images(&cache_images_dir);
+        log::info!("[Cache] Renamed {} images to their real extension", renamed);
+        let _ = fs::write(&marker, b"");
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct CacheStats {
+    file_count: u64,
+    total_bytes: u64,
+    // Unix timestamps (seconds)
+    oldest_mtime: Option<u64>,
+    newest_mtime: Option<u64>,
+}
+
+#[tauri::command]
+fn get_cache_stats(app: tauri::AppHandle) -> Result<CacheStats, StoreError> {
+    let cache_images_dir = image_cache_dir(&app)?;
+
+    let mut stats = CacheStats {
+        file_count: 0,
+        total_bytes: 0,
+        oldest_mtime: None,
+        newest_mtime: None,
+    };
+
+    // Fresh install, nothing cached yet
+    if !cache_images_dir.exists() {
+        return Ok(stats);
+    }
+
+    let entries = fs::read_dir(&cache_images_dir)
+        .map_err(|e| format!("Failed to read cache directory: {}", e))?;
+
+    // Only images count; .meta files and anything else in the directory are skipped
+    for entry in entries.flatten() {
+        if !is_cached_image_name(&entry.file_name().to_string_lossy()) {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+
+        stats.file_count += 1;
+        stats.total_bytes += metadata.len();
+
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+        if let Some(mtime) = mtime {
+            stats.oldest_mtime = Some(stats.oldest_mtime.map_or(mtime, |m| m.min(mtime)));
+            stats.newest_mtime = Some(stats.newest_mtime.map_or(mtime, |m| m.max(mtime)));
+        }
+    }
+
+    Ok(stats)
+}
+
+#[tauri::command]
+fn prune_image_cache(app: tauri::AppHandle, max_bytes: u64) -> Result<u64, StoreError> {
+    let cache_images_dir = image_cache_dir(&app)?;
+    if !cache_images_dir.exists() {
+        return Ok(0);
+    }
+
+    let entries = fs::read_dir(&cache_images_dir)
+        .map_err(|e| format!("Failed to read cache directory: {}", e))?;
+
+    // (path, size, last access) for every cached image; other files are left alone
+    let mut files: Vec<(std::path::PathBuf, u64, std::time::SystemTime)> = Vec::new();
+    let mut total_bytes: u64 = 0;
+    for entry in entries.flatten() {
+        if !is_cached_image_name(&entry.file_name().to_string_lossy()) {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        // atime may be disabled (noatime), mtime is the fallback
+        let last_used = metadata
+            .accessed()
+            .or_else(|_| metadata.modified())
+            .unwrap_or(std::time::UNIX_EPOCH);
+        total_bytes += metadata.len();
+        files.push((entry.path(), metadata.len(), last_used));
+    }
+
+    // Least recently used first
+    files.sort_by_key(|(_, _, last_used)| *last_used);
+
+    let mut freed_bytes: u64 = 0;
+    for (path, size, _) in files {
+        if total_bytes <= max_bytes {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            // Its validators are useless without the image
+            let _ = fs::remove_file(path.with_extension("meta"));
+            total_bytes -= size;
+            freed_bytes += size;
+        }
+    }
+
+    Ok(freed_bytes)
+}
+
+#[derive(Serialize)]
+struct VerifyReport {
+    checked: u64,
+    removed: u64,
+}
+
+// Helper function to tell whether a cached image was written completely
+// The header must match the extension, and formats with a trailer or a length must be whole
+fn cached_image_is_intact(extension: &str, bytes: &[u8]) -> bool {
+    if sniff_image_extension(bytes) != Some(extension) {
+        return false;
+    }
+    match extension {
+        "png" => bytes.ends_with(b"IEND\xAE\x42\x60\x82"),
+        "jpg" => bytes.ends_with(b"\xFF\xD9"),
+        // RIFF size counts everything after the first 8 bytes
+        "webp" => {
+            let declared = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+            bytes.len() as u64 >= declared as u64 + 8
+        }
+        "svg" => bytes.windows(6).any(|w| w == b"</svg>"),
+        _ => true,
+    }
+}
+
+// Delete cached images left empty or cut short by interrupted writes, so they're downloaded again
+#[tauri::command]
+fn verify_image_cache(app: tauri::AppHandle) -> Result<VerifyReport, StoreError> {
+    let cache_images_dir = image_cache_dir(&app)?;
+    let mut report = VerifyReport {
+        checked: 0,
+        removed: 0,
+    };
+    if !cache_images_dir.exists() {
+        return Ok(report);
+    }
+
+    let entries = fs::read_dir(&cache_images_dir)
+        .map_err(|e| format!("Failed to read cache directory: {}", e))?;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // Only images; the .meta files and the legacy index.json aren't checked
+        let Some(extension) = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .filter(|e| CACHED_IMAGE_EXTENSIONS.contains(e))
+        else {
+            continue;
+        };
+        let Ok(bytes) = fs::read(&path) else {
+            continue;
+        };
+
+        report.checked += 1;
+        if cached_image_is_intact(extension, &bytes) {
+            continue;
+        }
+        if fs::remove_file(&path).is_ok() {
+            // Its validators belong to the removed file, drop them so the image is fetched in full
+            let _ = fs::remove_file(path.with_extension("meta"));
+            report.removed += 1;
+        }
+    }
+
+    log::info!(
+        "[verify_image_cache] Checked {} images, removed {}",
+        report.checked,
+        report.removed
+    );
+    Ok(report)
+}
+
+// Extensiones que puede tener una imagen en caché
+const CACHED_IMAGE_EXTENSIONS: &[&str] = &["svg", "webp", "jpg", "png"];
+
+// Hash xxHash3 de la clave de caché: cacheKey si existe y difiere de la URL, si no la URL
+fn image_cache_hash(cache_key: &str, image_url: &str) -> u64 {
+    use xxhash_rust::xxh3::xxh3_64;
+    let key_to_hash = if !cache_key.is_empty() && cache_key != image_url {
+        cache_key
+    } else {
+        image_url
+    };
+    xxh3_64(key_to_hash.as_bytes())
+}
+
+// Extensión adivinada desde la URL (solo como respaldo cuando no hay Content-Type)
+fn image_extension_from_url(image_url: &str) -> &'static str {
+    if image_url.ends_with(".svg") || image_url.contains(".svg?") {
+        "svg"
+    } else if image_url.ends_with(".webp") || image_url.contains(".webp?") {
+        "webp"
+    } else if image_url.ends_with(".jpg")
+        || image_url.ends_with(".jpeg")
+        || image_url.contains(".jpg?")
+        || image_url.contains(".jpeg?")
+    {
+        "jpg"
+    } else {
+        "png" // default
+    }
+}
+
+// Extensión según el Content-Type de la respuesta (ignora parámetros como "; charset=utf-8")
+fn image_extension_from_content_type(content_type: &str) -> Option<&'static str> {
+    let mime = content_type.split(';').next()?.trim().to_ascii_lowercase();
+    match mime.as_str() {
+        "image/svg+xml" => Some("svg"),
+        "image/webp" => Some("webp"),
+        "image/jpeg" | "image/jpg" => Some("jpg"),
+        "image/png" => Some("png"),
+        _ => None,
+    }
+}
+
+// Extensión esperada de una respuesta: el Content-Type manda y, si falta, la URL final
+// (tras las redirecciones), no la pedida: un icono .png puede acabar en un CDN que sirve .webp
+fn response_image_extension(content_type: Option<&str>, final_url: &str) -> &'static str {
+    content_type
+        .and_then(image_extension_from_content_type)
+        .unwrap_or_else(|| image_extension_from_url(final_url))
+}
+
+// Detecta el tipo real de imagen por sus bytes mágicos
+// Una página de error HTML servida con 200 no coincide con ninguno
+fn sniff_image_extension(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG") {
+        return Some("png");
+    }
+    if bytes.starts_with(b"\xFF\xD8") {
+        return Some("jpg");
+    }
+    if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        return Some("webp");
+    }
+
+    // SVG es texto: saltar BOM y espacios iniciales
+    let text = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    let start = text.iter().position(|b| !b.is_ascii_whitespace())?;
+    let text = &text[start..];
+    if text.starts_with(b"<?xml") || text.starts_with(b"<svg") {
+        return Some("svg");
+    }
+
+    None
+}
+
+// Busca el archivo en caché para un hash sin importar con qué extensión se guardó
+fn find_cached_image(cache_images_dir: &std::path::Path, hash: u64) -> Option<String> {
+    CACHED_IMAGE_EXTENSIONS
+        .iter()
+        .map(|ext| format!("{:x}.{}", hash, ext))
+        .find(|filename| cache_images_dir.join(filename).exists())
+}
+
+// Marca que indica que los nombres de la caché ya se corrigieron con rename_mismatched_images
+const IMAGE_NAMES_MARKER: &str = ".names-sniffed";
+
+// Renombra las imágenes guardadas con la extensión adivinada por la URL (antes de detectar
+// el tipo por sus bytes) a la extensión real; las que no son imágenes se dejan a verify_image_cache
+// Devuelve cuántas se renombraron
+fn rename_mismatched_images(cache_images_dir: &std::path::Path) -> u64 {
+    let Ok(entries) = fs::read_dir(cache_images_dir) else {
+        return 0;
+    };
+
+    let mut renamed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(extension) = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .filter(|e| CACHED_IMAGE_EXTENSIONS.contains(e))
+        else {
+            continue;
+        };
+        let Some(sniffed) = fs::read(&path)
+            .ok()
+            .and_then(|bytes| sniff_image_extension(&bytes))
+        else {
+            continue;
+        };
+        if sniffed != extension && fs::rename(&path, path.with_extension(sniffed)).is_ok() {
+            renamed += 1;
+        }
+    }
+    renamed
+}
+
+// Intentos por defecto para descargar una imagen
+const IMAGE_DOWNLOAD_ATTEMPTS: u32 = 3;
+
+// Tope de intentos aunque el frontend pida más
+const IMAGE_DOWNLOAD_MAX_ATTEMPTS: u32 = 5;
+
+// Espera máxima entre dos intentos
+const IMAGE_RETRY_MAX_BACKOFF_MS: u64 = 2000;
+
+// Espera antes del intento siguiente a `attempt` (empieza en 1): 250ms, 500ms, 1000ms... hasta 2s
+fn image_retry_backoff(attempt: u32) -> Duration {
+    let millis = 2u64
+        .checked_pow(attempt.saturating_sub(1))
+        .map_or(IMAGE_RETRY_MAX_BACKOFF_MS, |factor| {
+            factor.saturating_mul(250)
+        });
+    Duration::from_millis(millis.min(IMAGE_RETRY_MAX_BACKOFF_MS))
+}
+
+// GET con reintentos y backoff exponencial (ver image_retry_backoff)
+// Solo se reintentan errores de conexión, timeouts y 5xx; un 404 u otro 4xx falla enseguida
+async fn fetch_image_with_retry(
+    client: &reqwest::Client,
+    image_url: &str,
+    max_attempts: Option<u32>,
+) -> Result<reqwest::Response, StoreError> {
+    let max_attempts = max_attempts
+        .unwrap_or(IMAGE_DOWNLOAD_ATTEMPTS)
+        .clamp(1, IMAGE_DOWNLOAD_MAX_ATTEMPTS);
+    let mut last_error = String::new();
+    let mut timed_out = false;
+
+    for attempt in 1..=max_attempts {
+        match client.get(image_url).send().await {
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) if !response.status().is_server_error() => {
+                return Err(StoreError::HttpError(response.status().as_u16()));
+            }
+            Ok(response) => {
+                last_error = format!("HTTP Error: {}", response.status());
+                timed_out = false;
+            }
+            Err(e) => {
+                last_error = format!("Error downloading image: {}", e);
+                timed_out = e.is_timeout();
+            }
+        }
+
+        if attempt < max_attempts {
+            tokio::time::sleep(image_retry_backoff(attempt)).await;
+        }
+    }
+
+    let message = format!("{} (after {} attempts)", last_error, max_attempts);
+    // Un timeout en el último intento se devuelve aparte para que la UI lo distinga
+    if timed_out {
+        Err(StoreError::Timeout(message))
+    } else {
+        Err(StoreError::NetworkError(message))
+    }
+}
+
+// Un candado por hash de imagen: descargas simultáneas de la misma imagen esperan a la primera
+type ImageDownloadLocks = Arc<Mutex<HashMap<u64, Arc<tokio::sync::Mutex<()>>>>>;
+
+// Máximo de descargas simultáneas en prefetch_images
+const IMAGE_PREFETCH_CONCURRENCY: usize = 6;
+
+// Validadores HTTP guardados junto a cada imagen ({hash}.meta) para revalidarla después
+#[derive(Default, Serialize, Deserialize)]
+struct ImageCacheMeta {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+fn image_meta_path(cache_images_dir: &std::path::Path, hash: u64) -> std::path::PathBuf {
+    cache_images_dir.join(format!("{:x}.meta", hash))
+}
+
+fn read_image_meta(cache_images_dir: &std::path::Path, hash: u64) -> ImageCacheMeta {
+    fs::read_to_string(image_meta_path(cache_images_dir, hash))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+// Guarda el cuerpo de una respuesta 200 como imagen en caché (más su .meta) y devuelve el nombre
+async fn store_image_response(
+    cache_images_dir: &std::path::Path,
+    hash: u64,
+    image_url: &str,
+    response: reqwest::Response,
+) -> Result<String, String> {
+    let final_url = response.url().as_str();
+    if final_url != image_url {
+        log::debug!("[image_cache] {} redirected to {}", image_url, final_url);
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok());
+    let extension = response_image_extension(content_type, final_url);
+
+    let header_value = |name: reqwest::header::HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from)
+    };
+    let meta = ImageCacheMeta {
+        etag: header_value(reqwest::header::ETAG),
+        last_modified: header_value(reqwest::header::LAST_MODIFIED),
+    };
+
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Error reading image bytes: {}", e))?;
+
+    // No guardar nada que no sea realmente una imagen; si el tipo real difiere del esperado, manda el real
+    let sniffed = sniff_image_extension(&bytes).ok_or_else(|| {
+        format!(
+            "Downloaded content is not a valid image (expected {})",
+            extension
+        )
+    })?;
+
+    let filename = format!("{:x}.{}", hash, sniffed);
+    let file_path = cache_images_dir.join(&filename);
+
+    // Si la imagen cambió de formato, borrar la versión anterior
+    if let Some(previous) = find_cached_image(cache_images_dir, hash) {
+        if previous != filename {
+            let _ = fs::remove_file(cache_images_dir.join(previous));
+        }
+    }
+
+    write_atomic(&file_path, &bytes).map_err(|e| format!("Error saving image: {}", e))?;
+
+    // Sin validadores nuevos, un .meta anterior describiría otra versión de la imagen
+    let meta_path = image_meta_path(cache_images_dir, hash);
+    if meta.etag.is_some() || meta.last_modified.is_some() {
+        if let Ok(json) = serde_json::to_string(&meta) {
+            let _ = write_atomic(&meta_path, json.as_bytes());
+        }
+    } else {
+        let _ = fs::remove_file(&meta_path);
+    }
+
+    Ok(filename)
+}
+
+// GET condicional para una imagen ya cacheada: 304 la deja como está, 200 la reescribe
+// Cualquier fallo de red mantiene la copia existente
+async fn revalidate_cached_image(
+    cache_images_dir: &std::path::Path,
+    client: &reqwest::Client,
+    hash: u64,
+    image_url: &str,
+    existing: String,
+) -> String {
+    let meta = read_image_meta(cache_images_dir, hash);
+    if meta.etag.is_none() && meta.last_modified.is_none() {
+        return existing;
+    }
+
+    let mut request = client.get(image_url);
+    if let Some(etag) = &meta.etag {
+        request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = &meta.last_modified {
+        request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+    }
+
+    match request.send().await {
+        Ok(response) if response.status() == reqwest::StatusCode::OK => {
+            store_image_response(cache_images_dir, hash, image_url, response)
+                .await
+                .unwrap_or(existing)
+        }
+        _ => existing,
+    }
+}
+
+// Descarga una imagen al directorio de caché (si no está ya) y devuelve el nombre del archivo
+async fn cache_image(
+    cache_images_dir: &std::path::Path,
+    locks: &ImageDownloadLocks,
+    client: &reqwest::Client,
+    cache_key: &str,
+    image_url: &str,
+    max_attempts: Option<u32>,
+    revalidate: bool,
+) -> Result<String, StoreError> {
+    // Generar nombre de archivo único usando xxHash3
+    // Si cache_key no está vacío y es diferente de image_url, usarlo como key
+    // Si no, usar image_url (caso normal)
+    let hash = image_cache_hash(cache_key, image_url);
+
+    // Si otra llamada ya está descargando esta imagen, esperar a que termine
+    let lock = locks.lock().unwrap().entry(hash).or_default().clone();
+    let guard = lock.lock().await;
+    let result = cache_image_locked(
+        cache_images_dir,
+        client,
+        hash,
+        image_url,
+        max_attempts,
+        revalidate,
+    )
+    .await;
+    drop(guard);
+
+    // Quitar el candado del mapa si nadie más lo espera, para que no crezca sin límite
+    let mut map = locks.lock().unwrap();
+    drop(lock);
+    if map.get(&hash).is_some_and(|l| Arc::strong_count(l) == 1) {
+        map.remove(&hash);
+    }
+    result
+}
+
+// Parte de cache_image que corre con el candado de la imagen tomado
+async fn cache_image_locked(
+    cache_images_dir: &std::path::Path,
+    client: &reqwest::Client,
+    hash: u64,
+    image_url: &str,
+    max_attempts: Option<u32>,
+    revalidate: bool,
+) -> Result<String, StoreError> {
+    // Si el archivo ya existe (con cualquier extensión), no descargar de nuevo salvo que se pida revalidar
+    if let Some(filename) = find_cached_image(cache_images_dir, hash) {
+        if revalidate {
+            return Ok(
+                revalidate_cached_image(cache_images_dir, client, hash, image_url, filename).await,
+            );
+        }
+        return Ok(filename);
+    }
+
+    // Descargar la imagen
+    let response = fetch_image_with_retry(client, image_url, max_attempts).await?;
+    Ok(store_image_response(cache_images_dir, hash, image_url, response).await?)
+}
+
+#[tauri::command]
+async fn download_and_cache_image(
+    app: tauri::AppHandle,
+    http: State<'_, http::HttpClient>,
+    locks: State<'_, ImageDownloadLocks>,
+    app_id: String,
+    image_url: String,
+    max_attempts: Option<u32>,
+    revalidate: Option<bool>,
+) -> Result<String, StoreError> {
+    let cache_images_dir = image_cache_dir(&app)?;
+    fs::create_dir_all(&cache_images_dir)
+        .map_err(|e| format!("Failed to create cacheImages directory: {}", e))?;
+
+    // app_id hace de cacheKey cuando viene informado
+    let client = http.client();
+    cache_image(
+        &cache_images_dir,
+        locks.inner(),
+        &client,
+        &app_id,
+        &image_url,
+        max_attempts,
+        revalidate.unwrap_or(false),
+    )
+    .await
+}
+
+#[derive(Deserialize)]
+struct ImageRequest {
+    cache_key: String,
+    image_url: String,
+}
+
+#[derive(Serialize)]
+struct PrefetchResult {
+    cache_key: String,
+    filename: Option<String>,
+    error: Option<String>,
+}
+
+#[tauri::command]
+async fn prefetch_images(
+    app: tauri::AppHandle,
+    http: State<'_, http::HttpClient>,
+    locks: State<'_, ImageDownloadLocks>,
+    items: Vec<ImageRequest>,
+) -> Result<Vec<PrefetchResult>, StoreError> {
+    let cache_images_dir = image_cache_dir(&app)?;
+    fs::create_dir_all(&cache_images_dir)
+        .map_err(|e| format!("Failed to create cacheImages directory: {}", e))?;
+
+    let client = http.client();
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(IMAGE_PREFETCH_CONCURRENCY));
+
+    let mut handles = Vec::new();
+    for item in items {
+        let semaphore = Arc::clone(&semaphore);
+        let locks = locks.inner().clone();
+        let client = client.clone();
+        let cache_images_dir = cache_images_dir.clone();
+
+        handles.push(tauri::async_runtime::spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let result = cache_image(
+                &cache_images_dir,
+                &locks,
+                &client,
+                &item.cache_key,
+                &item.image_url,
+                None,
+                false,
+            )
+            .await;
+
+            match result {
+                Ok(filename) => PrefetchResult {
+                    cache_key: item.cache_key,
+                    filename: Some(filename),
+                    error: None,
+                },
+                Err(e) => PrefetchResult {
+                    cache_key: item.cache_key,
+                    filename: None,
+                    error: Some(e.to_string()),
+                },
+            }
+        }));
+    }
+
+    // Results keep the order of the request
+    let mut results = Vec::new();
+    for handle in handles {
+        results.push(handle.await.map_err(|e| format!("Prefetch task failed: {}", e))?);
+    }
+
+    Ok(results)
+}
+
+#[tauri::command]
+fn get_cached_image_path(app: tauri::AppHandle, filename: String) -> Result<String, StoreError> {
+    let file_path = image_cache_dir(&app)?.join(filename);
+
+    // Usar canonicalize para obtener la ruta absoluta normalizada
+    let canonical_path = file_path
+        .canonicalize()
+        .unwrap_or_else(|_| file_path.clone());
+
+    Ok(canonical_path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn check_file_exists(path: String) -> bool {
+    std::path::Path::new(&path).exists()
+}
+
+#[tauri::command]
+fn get_cached_image_filename(
+    app: tauri::AppHandle,
+    cache_key: String,
+    image_url: String,
+) -> String {
+    // El nombre real es el del archivo guardado, con la extensión de sus bytes;
+    // la URL solo sirve para adivinarla mientras la imagen no está en caché
+    let hash = image_cache_hash(&cache_key, &image_url);
+    image_cache_dir(&app)
+        .ok()
+        .and_then(|dir| find_cached_image(&dir, hash))
+        .unwrap_or_else(|| format!("{:x}.{}", hash, image_extension_from_url(&image_url)))
+}
+
+#[tauri::command]
+fn check_cached_image_exists(
+    app: tauri::AppHandle,
+    cache_key: String,
+    image_url: String,
+) -> Result<String, StoreError> {
+    let cache_images_dir = image_cache_dir(&app)?;
+
+    // La extensión depende del Content-Type con que se descargó, así que se prueban todas
+    let hash = image_cache_hash(&cache_key, &image_url);
+    find_cached_image(&cache_images_dir, hash)
+        .ok_or_else(|| StoreError::Internal("Image not found in cache".to_string()))
+}
+
+
+#[tauri::command]
+fn get_cached_image_info(
+    app: tauri::AppHandle,
+    cache_key: String,
+    image_url: String,
+) -> Result<String, StoreError> {
+    // Combina check_cached_image_exists + get_cached_image_path en una sola llamada
+    let cache_images_dir = image_cache_dir(&app)?;
+
+    let hash = image_cache_hash(&cache_key, &image_url);
+    let filename = find_cached_image(&cache_images_dir, hash);
+
+    // Verificar que existe y retornar la ruta absoluta
+    if let Some(filename) = filename {
+        let file_path = cache_images_dir.join(&filename);
+        // Usar canonicalize para obtener la ruta absoluta normalizada
+        let canonical_path = file_path
+            .canonicalize()
+            .unwrap_or_else(|_| file_path.clone());
+        Ok(canonical_path.to_string_lossy().to_string())
+    } else {
+        Err(StoreError::Internal("Image not found in cache".to_string()))
+    }
+}
+
+/// Installed apps, runtimes and extensions. Served from the cache until an
+/// install, uninstall or update marks it dirty, or `force_refresh` is set.
+/// `include_eol` fills each app's `eol` field, at the cost of one
+/// `flatpak info` per app, so it is left off the cached listing.
+#[tauri::command]
+async fn get_installed_flatpaks(
+    app: tauri::AppHandle,
+    force_refresh: Option<bool>,
+    include_eol: Option<bool>,
+) -> Result<InstalledPackagesResponse, StoreError> {
+    let cache = app.state::<InstalledCache>();
+    let cached = if force_refresh.unwrap_or(false) {
+        None
+    } else {
+        cache.get()
+    };
+    let mut packages = match cached {
+        Some(packages) => packages,
+        None => {
+            let generation = cache.generation();
+            let packages = list_installed_flatpaks(&app).await?;
+            cache.store(generation, packages.clone());
+            packages
+        }
+    };
+
+    if include_eol.unwrap_or(false) {
+        // Flag apps that are no longer maintained
+        let eol_targets: Vec<String> = packages.apps.iter().map(|a| a.app_id.clone()).collect();
+        let eol_by_id: HashMap<String, String> = find_eol_packages(&app, eol_targets)
+            .await
+            .into_iter()
+            .map(|package| (package.id, package.reason))
+            .collect();
+        for installed in &mut packages.apps {
+            installed.eol = eol_by_id.get(&installed.app_id).cloned();
+        }
+    }
+
+    Ok(packages)
+}
+
+async fn list_installed_flatpaks(
+    app: &tauri::AppHandle,
+) -> Result<InstalledPackagesResponse, StoreError> {
+    let shell = app.shell();
+
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    // Get everything (apps + runtimes) with options column to distinguish
+    // Note: flatpak list without --system or --user gets both
+    // The 'options' column contains 'runtime' for runtimes/extensions and 'current' for apps
+    // The 'origin' column is the remote each ref was installed from
+    // The 'size' column contains the installed size in bytes
+    let output = if is_flatpak {
+        // Inside flatpak, use flatpak-spawn to execute on the host
+        shell
+            .command("flatpak-spawn")
+            .args([
+                "--host",
+                "flatpak",
+                "list",
+                "--columns=application,name,version,description,options,ref,origin,size",
+            ])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
+    } else {
+        // Outside flatpak, use flatpak directly
+        shell
+            .command("flatpak")
+            .args([
+                "list",
+                "--columns=application,name,version,description,options,ref,origin,size",
+            ])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak", e))?
+    };
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut apps: Vec<InstalledApp> = Vec::new();
+    let mut runtimes: Vec<String> = Vec::new();
+    let mut potential_extensions: Vec<(String, String, String, String)> = Vec::new(); // (app_id, name, version, ref)
+
+    // First pass: collect apps and potential extensions
+    for line in stdout.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let parts: Vec<&str> = line.split('\t').collect();
+        // The trailing size column may be missing entirely for some refs
+        if parts.len() >= 6 {
+            let app_id = parts[0].trim();
+            let options = parts[4].trim();
+            let ref_full = parts[5].trim();
+            let origin = parts.get(6).map_or("", |s| s.trim());
+            let size_str = parts.get(7).map_or("", |s| s.trim());
+
+            // Distinguish apps from runtimes using the official 'options' column
+            // Apps have 'current' in options (e.g., "user,current" or "system,current")
+            // Runtimes/extensions have 'runtime' in options (e.g., "user,runtime" or "system,runtime")
+            let is_runtime = options.contains("runtime");
+
+            if is_runtime {
+                // Check if it might be an app extension using blacklist approach
+                // Exclude system/platform extensions, consider everything else as potential app extension
+                let is_system_extension = app_id.contains("org.freedesktop.Platform.")
+                    || app_id.contains("org.freedesktop.Sdk.")
+                    || app_id.contains(".Platform.GL32")
+                    || app_id.contains(".Platform.VAAPI")
+                    || app_id.contains(".Platform.Compat.i386")
+                    || app_id.contains(".Platform.codecs");
+
+                let is_potential_app_extension = !is_system_extension;
+
+                if is_potential_app_extension {
+                    potential_extensions.push((
+                        app_id.to_string(),
+                        parts[1].trim().to_string(),
+                        parts[2].trim().to_string(),
+                        ref_full.to_string(),
+                    ));
+                } else {
+                    // It's a platform/runtime/driver - store the ref
+                    runtimes.push(ref_full.to_string());
+                }
+            } else {
+                // It's an application
+                // Parse size from the size column (format: "715,3 MB" or "1,2 GB")
+                let installed_size = parse_size_string(size_str);
+
+                apps.push(InstalledApp {
+                    app_id: app_id.to_string(),
+                    name: parts[1].trim().to_string(),
+                    version: parts[2].trim().to_string(),
+                    summary: if !parts[3].trim().is_empty() {
+                        Some(parts[3].trim().to_string())
+                    } else {
+                        None
+                    },
+                    developer: extract_developer(app_id),
+                    permissions: None, // Don't get permissions here, too slow
+                    installed_size,
+                    eol: None,
+                    origin: origin.to_string(),
+                });
+            }
+        }
+    }
+
+    // Second pass: match extensions to their parent apps
+    let mut app_ids: Vec<&str> = apps.iter().map(|a| a.app_id.as_str()).collect();
+    app_ids.sort_unstable();
+
+    let mut extensions: Vec<InstalledExtension> = Vec::new();
+    for (ext_id, ext_name, ext_version, ext_ref) in potential_extensions {
+        match find_parent_app_id(&app_ids, &ext_id) {
+            Some(parent_app_id) => extensions.push(InstalledExtension {
+                extension_id: ext_id,
+                name: ext_name,
+                version: ext_version,
+                parent_app_id: parent_app_id.to_string(),
+            }),
+            // If no match found, it's probably a platform extension, add to runtimes
+            None => runtimes.push(ext_ref),
+        }
+    }
+
+    Ok(InstalledPackagesResponse {
+        apps,
+        runtimes,
+        extensions,
+    })
+}
+
+// Installed apps that came from `remote`, to audit what a third-party remote provides
+#[tauri::command]
+async fn get_apps_by_remote(
+    app: tauri::AppHandle,
+    remote: String,
+) -> Result<Vec<InstalledApp>, StoreError> {
+    let installed = get_installed_flatpaks(app, None, None).await?;
+    Ok(installed
+        .apps
+        .into_iter()
+        .filter(|installed| installed.origin == remote)
+        .collect())
+}
+
+// Detailed info for one installed app, for the detail page
+#[tauri::command]
+async fn get_installed_app_info(
+    app: tauri::AppHandle,
+    app_id: String,
+) -> Result<InstalledAppDetail, StoreError> {
+    if !is_valid_flatpak_id(&app_id) {
+        return Err(StoreError::InvalidInput("invalid app id".to_string()));
+    }
+
+    let stdout = flatpak_info_output(&app, &app_id).await?;
+    Ok(parse_flatpak_info(&app_id, &stdout))
+}
+
+// Most `flatpak info` processes find_eol_packages runs at once
+const EOL_CHECK_CONCURRENCY: usize = 4;
+
+// Helper function to run `flatpak info <target>` with English labels
+// `target` is an app id or a full ref such as runtime/org.gnome.Platform/x86_64/45
+async fn flatpak_info_output(app: &tauri::AppHandle, target: &str) -> Result<String, StoreError> {
+    let shell = app.shell();
+
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    // LANG=C keeps the field labels in English
+    let output = if is_flatpak {
+        shell
+            .command("flatpak-spawn")
+            .args(["--host", "--env=LANG=C", "flatpak", "info", target])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
+    } else {
+        shell
+            .command("flatpak")
+            .args(["info", target])
+            .env("LANG", "C")
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak", e))?
+    };
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// Helper function to find which of `targets` are end-of-life
+// One `flatpak info` per target, at most EOL_CHECK_CONCURRENCY at a time; targets that fail
+// to load are skipped
+async fn find_eol_packages(app: &tauri::AppHandle, targets: Vec<String>) -> Vec<EolPackage> {
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(EOL_CHECK_CONCURRENCY));
+    let handles: Vec<_> = targets
+        .into_iter()
+        .map(|target| {
+            let app = app.clone();
+            let semaphore = Arc::clone(&semaphore);
+            tauri::async_runtime::spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let output = flatpak_info_output(&app, &target).await.ok()?;
+                let info = parse_flatpak_info(&target, &output);
+                Some(EolPackage {
+                    id: target,
+                    reason: info.eol?,
+                    rebase: info.eol_rebase,
+                })
+            })
+        })
+        .collect();
+
+    let mut eol_packages = Vec::new();
+    for handle in handles {
+        if let Ok(Some(package)) = handle.await {
+            eol_packages.push(package);
+        }
+    }
+    eol_packages
+}
+
+// Installed apps and runtimes that upstream marked end-of-life
+#[tauri::command]
+async fn get_eol_packages(app: tauri::AppHandle) -> Result<Vec<EolPackage>, StoreError> {
+    let shell = app.shell();
+
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    let output = if is_flatpak {
+        // Inside flatpak, use flatpak-spawn to execute on the host
+        shell
+            .command("flatpak-spawn")
+            .args(["--host", "flatpak", "list", "--columns=ref"])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
+    } else {
+        // Outside flatpak, use flatpak directly
+        shell
+            .command("flatpak")
+            .args(["list", "--columns=ref"])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak", e))?
+    };
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
+    }
+
+    let refs: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect();
+
+    Ok(find_eol_packages(&app, refs).await)
+}
+
+// Register a running probe under `key`, the dependency_cache_key of its check
+// A second check for the same key is refused and its probe killed, so the first one is
+// never replaced while it is still being waited on
+fn register_dependency_probe(
+    checks: &DependencyCheckMap,
+    key: &str,
+    mut child: Child,
+) -> Result<(), StoreError> {
+    match checks.lock().unwrap().entry(key.to_string()) {
+        Entry::Vacant(entry) => {
+            entry.insert(child);
+            Ok(())
+        }
+        Entry::Occupied(_) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            Err(StoreError::InvalidInput(format!(
+                "A dependency check for {} is already running",
+                key
+            )))
+        }
+    }
+}
+
+// Wait until a probe registered in `checks` under `key` exits, `done` reports that its
+// output is complete, or `timeout` passes; then kill and unregister it.
+// Returns false if cancel_dependency_check took the probe first
+fn wait_for_dependency_probe(
+    checks: &DependencyCheckMap,
+    key: &str,
+    timeout: Duration,
+    done: impl Fn() -> bool,
+) -> bool {
+    let start = Instant::now();
+    let mut cancelled = false;
+    while start.elapsed() < timeout {
+        if done() {
+            // Give it a short time to finish printing the whole list
+            std::thread::sleep(Duration::from_millis(500));
+            break;
+        }
+        // Check if process exited on its own, or was taken by cancel_dependency_check
+        match checks.lock().unwrap().get_mut(key) {
+            Some(child) => {
+                if let Ok(Some(_)) = child.try_wait() {
+                    break;
+                }
+            }
+            None => {
+                cancelled = true;
+                break;
+            }
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    // Kill the process if still running - we have what we need or timed out
+    let child = checks.lock().unwrap().remove(key);
+    if let Some(mut child) = child {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    !cancelled
+}
+
+fn dependency_check_cancelled(app_id: &str) -> StoreError {
+    log::info!("[get_install_dependencies] Cancelled check for {}", app_id);
+    StoreError::Cancelled(format!("Dependency check for {}", app_id))
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn get_install_dependencies(
+    _app: tauri::AppHandle,
+    cache: State<'_, DependencyCache>,
+    checks: State<'_, DependencyCheckMap>,
+    app_id: String,
+    remote: Option<String>,
+    scope: Option<InstallScope>,
+    force_refresh: Option<bool>,
+    timeout_secs: Option<u64>,
+) -> Result<InstallDependencies, StoreError> {
+    if !is_valid_flatpak_id(&app_id) {
+        return Err(StoreError::InvalidInput("invalid app id".to_string()));
+    }
+    let remote = resolve_remote(remote)?;
+    let scope = scope.unwrap_or_default();
+
+    // Serve a recent result without spawning flatpak again
+    let cache_key = dependency_cache_key(&app_id, &remote, scope);
+    if !force_refresh.unwrap_or(false) {
+        if let Some((fetched_at, cached)) = cache.lock().unwrap().get(&cache_key) {
+            if fetched_at.elapsed() < DEPENDENCY_CACHE_TTL {
+                return Ok(cached.clone());
+            }
+        }
+    }
+
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    // First phase: Quick check answering "n" on stdin (flatpak doesn't wait for input, just aborts)
+    // The app_id is passed as its own argument, no shell involved
+    let program = if is_flatpak {
+        "flatpak-spawn"
+    } else {
+        "flatpak"
+    };
+    let mut first_child =
+        flatpak_std_command(is_flatpak, &["install", scope.flag(), &remote, &app_id])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| StoreError::spawn(program, e))?;
+
+    if let Some(mut first_stdin) = first_child.stdin.take() {
+        let _ = first_stdin.write_all(b"n\n");
+    }
+    let first_stdout = first_child.stdout.take().ok_or("Failed to get stdout")?;
+    let first_stderr = first_child.stderr.take().ok_or("Failed to get stderr")?;
+    let read_all = |mut pipe: Box<dyn std::io::Read + Send>| {
+        std::thread::spawn(move || {
+            let mut bytes = Vec::new();
+            let _ = pipe.read_to_end(&mut bytes);
+            String::from_utf8_lossy(&bytes).into_owned()
+        })
+    };
+    let first_stdout_thread = read_all(Box::new(first_stdout));
+    let first_stderr_thread = read_all(Box::new(first_stderr));
+
+    // Registered so cancel_dependency_check can kill it; flatpak may also hang on the network
+    let timeout = Duration::from_secs(timeout_secs.unwrap_or(DEPENDENCY_CHECK_TIMEOUT_SECS));
+    register_dependency_probe(&checks, &cache_key, first_child)?;
+    let finished = wait_for_dependency_probe(&checks, &cache_key, timeout, || false);
+    let stdout = first_stdout_thread.join().unwrap_or_default();
+    let stderr = first_stderr_thread.join().unwrap_or_default();
+    if !finished {
+        return Err(dependency_check_cancelled(&app_id));
+    }
+    let combined_first = format!("{}\n{}", stdout, stderr);
+
+    // Check if we got "Required runtime" message
+    let needs_runtime = combined_first.contains("Required runtime for");
+
+    // Second phase: If runtime is required, use controlled process with script
+    let (stdout, stderr) = if needs_runtime {
+        let cmd_str = build_flatpak_dependency_check_cmd(is_flatpak, &app_id, &remote, scope)?;
+        let (cmd, args) = ("sh", vec!["-c", &cmd_str]);
+
+        let mut child = Command::new(cmd)
+            .args(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to spawn process: {}", e))?;
+        let stderr_handle = child.stderr.take().ok_or("Failed to get stderr")?;
+        let stdout_handle = child.stdout.take().ok_or("Failed to get stdout")?;
+
+        // Read stdout and stderr looking for the dependency list
+        use std::sync::{Arc, Mutex};
+        let output_lines = Arc::new(Mutex::new(Vec::<String>::new()));
+        let error_lines = Arc::new(Mutex::new(Vec::<String>::new()));
+        let found_list = Arc::new(Mutex::new(false));
+
+        // Clone for threads
+        let error_clone_err = Arc::clone(&error_lines);
+        let output_clone_out = Arc::clone(&output_lines);
+        let found_clone_out = Arc::clone(&found_list);
+
+        // Read stderr in thread
+        let stderr_thread = std::thread::spawn(move || {
+            let reader = BufReader::new(stderr_handle);
+            for line in reader.lines() {
+                if let Ok(line) = line {
+                    error_clone_err.lock().unwrap().push(line);
+                }
+            }
+        });
+
+        // Read stdout in thread to avoid blocking on prompts without newlines
+        let stdout_thread = std::thread::spawn(move || {
+            let reader = BufReader::new(stdout_handle);
+            let mut line_count_in_list = 0;
+
+            for line in reader.lines() {
+                if let Ok(line) = line {
+                    output_clone_out.lock().unwrap().push(line.clone());
+
+                    // Detect numbered dependency list entries
+                    let trimmed = line.trim();
+                    if trimmed.starts_with(|c: char| c.is_ascii_digit()) && trimmed.contains('.') {
+                        line_count_in_list += 1;
+                    }
+
+                    // Once we've seen at least 1 list entry, we have the data we need
+                    if line_count_in_list >= 1 {
+                        *found_clone_out.lock().unwrap() = true;
+                    }
+                }
+            }
+        });
+
+        // Registered so cancel_dependency_check can kill it
+        register_dependency_probe(&checks, &cache_key, child)?;
+
+        // Wait with timeout for the list to be found or process to exit
+        let finished =
+            wait_for_dependency_probe(&checks, &cache_key, timeout, || *found_list.lock().unwrap());
+        let _ = stdout_thread.join();
+        let _ = stderr_thread.join();
+
+        if !finished {
+            return Err(dependency_check_cancelled(&app_id));
+        }
+
+        let out = output_lines.lock().unwrap().join("\n");
+        let err = error_lines.lock().unwrap().join("\n");
+
+        (out, err)
+    } else {
+        // No runtime needed, use the first phase output
+        (stdout, stderr)
+    };
+
+    // Flatpak outputs dependency info to both stdout and stderr
+    let combined_output = format!("{}\n{}", stdout, stderr);
+
+    // "org.gimp.GIMP/x86_64/stable is already installed", printed instead of a list
+    let already_installed = combined_output.contains("is already installed");
+
+    let mut dependencies = Vec::new();
+    let mut app_main: Option<Dependency> = None;
+    let mut required_runtime: Option<String> = None;
+    let mut is_partial = false;
+
+    for line in combined_output.lines() {
+        let trimmed = line.trim();
+
+        // Skip empty lines
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        // Look for "Required runtime" format (when app isn't already downloaded)
+        // Format: "Required runtime for moe.clover.mm3d/x86_64/stable (runtime/org.kde.Platform/x86_64/5.15-23.08) found in remote flathub"
+        if line.contains("Required runtime for") {
+            if let Some(runtime_part) = line.split("(runtime/").nth(1) {
+                if let Some(runtime_name) = runtime_part.split(')').next() {
+                    required_runtime = Some(runtime_name.to_string());
+                }
+            }
+        }
+
+        if let Some(dep) = parse_dependency_line(line) {
+            if dep.approximate || dep.partial {
+                is_partial = true;
+            }
+
+            if dep.name == app_id {
+                app_main = Some(dep);
+            } else {
+                dependencies.push(dep);
+            }
+        }
+    }
+
+    // If we found a required runtime but no detailed list, create a fallback entry
+    if app_main.is_none() && required_runtime.is_some() {
+        // Sizes are unknown, so the totals can't be trusted
+        is_partial = true;
+
+        // Add the app itself with unknown size
+        app_main = Some(Dependency {
+            name: app_id.clone(),
+            download_size: "Unknown".to_string(),
+            installed_size: "Unknown".to_string(),
+            download_bytes: 0,
+            installed_bytes: 0,
+            approximate: false,
+            partial: false,
+        });
+
+        // Add the runtime as a dependency
+        if let Some(runtime) = required_runtime {
+            dependencies.push(Dependency {
+                name: runtime,
+                download_size: "Unknown".to_string(),
+                installed_size: "Unknown".to_string(),
+                download_bytes: 0,
+                installed_bytes: 0,
+                approximate: false,
+                partial: false,
+            });
+        }
+    }
+
+    // Put app main as first element, then dependencies
+    let mut items = Vec::new();
+    if let Some(main_app) = app_main {
+        items.push(main_app);
+    }
+    items.extend(dependencies);
+
+    let total_download_bytes = items.iter().map(|d| d.download_bytes).sum();
+    let total_installed_bytes = items.iter().map(|d| d.installed_bytes).sum();
+
+    let result = InstallDependencies {
+        items,
+        total_download_bytes,
+        total_installed_bytes,
+        is_partial,
+        already_installed,
+    };
+
+    cache
+        .lock()
+        .unwrap()
+        .insert(cache_key, (Instant::now(), result.clone()));
+
+    Ok(result)
+}
+
+// Stop a running get_install_dependencies probe; the check returns an error instead of a list
+// Without `remote` and `scope` every check running for `app_id` is stopped
+#[tauri::command]
+async fn cancel_dependency_check(
+    checks: State<'_, DependencyCheckMap>,
+    app_id: String,
+    remote: Option<String>,
+    scope: Option<InstallScope>,
+) -> Result<(), StoreError> {
+    let children: Vec<Child> = {
+        let mut checks = checks.lock().unwrap();
+        let keys: Vec<String> = if remote.is_none() && scope.is_none() {
+            // dependency_cache_key starts with "{app_id}|"
+            let prefix = format!("{}|", app_id);
+            checks
+                .keys()
+                .filter(|key| key.starts_with(&prefix))
+                .cloned()
+                .collect()
+        } else {
+            let remote = resolve_remote(remote)?;
+            vec![dependency_cache_key(
+                &app_id,
+                &remote,
+                scope.unwrap_or_default(),
+            )]
+        };
+        keys.iter().filter_map(|key| checks.remove(key)).collect()
+    };
+    if children.is_empty() {
+        return Err(StoreError::InvalidInput(format!(
+            "No dependency check running for {}",
+            app_id
+        )));
+    }
+
+    for mut child in children {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    log::info!("[cancel_dependency_check] Cancelled check for {}", app_id);
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_available_updates(app: tauri::AppHandle) -> Result<Vec<UpdateAvailable>, StoreError> {
+    let shell = app.shell();
+
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    let output = if is_flatpak {
+        // Inside flatpak, use flatpak-spawn to execute on the host
+        shell
+            .command("flatpak-spawn")
+            .args([
+                "--host",
+                "flatpak",
+                "remote-ls",
+                "--updates",
+                "--columns=application,version,branch",
+            ])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
+    } else {
+        // Outside flatpak, use flatpak directly
+        shell
+            .command("flatpak")
+            .args([
+                "remote-ls",
+                "--updates",
+                "--columns=application,version,branch",
+            ])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak", e))?
+    };
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(parse_available_updates(&stdout))
+}
+
+// Helper function to parse `flatpak remote-ls --updates --columns=application,version,branch`
+// Columns are positional: an app without a version still prints the tab ("app\t\tstable")
+fn parse_available_updates(output: &str) -> Vec<UpdateAvailable> {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split('\t').map(str::trim).collect();
+            let app_id = parts.first().filter(|id| !id.is_empty())?;
+            let (new_version, branch) = match parts.len() {
+                1 => return None,
+                // Malformed line without the version column, the last value is the branch
+                2 => ("", parts[1]),
+                _ => (parts[1], parts[2]),
+            };
+            Some(UpdateAvailable {
+                app_id: app_id.to_string(),
+                new_version: new_version.to_string(),
+                branch: if branch.is_empty() { "stable" } else { branch }.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[tauri::command]
+async fn get_flatpak_remotes(app: tauri::AppHandle) -> Result<Vec<FlatpakRemote>, StoreError> {
+    let shell = app.shell();
+
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    let output = if is_flatpak {
+        // Inside flatpak, use flatpak-spawn to execute on the host
+        shell
+            .command("flatpak-spawn")
+            .args([
+                "--host",
+                "flatpak",
+                "remotes",
+                "--columns=name,title,url,options",
+            ])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
+    } else {
+        // Outside flatpak, use flatpak directly
+        shell
+            .command("flatpak")
+            .args(["remotes", "--columns=name,title,url,options"])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak", e))?
+    };
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let remotes: Vec<FlatpakRemote> = stdout
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split('\t').collect();
+            let name = parts.first()?.trim();
+            if name.is_empty() {
+                return None;
+            }
+            // The options column lists the installation ("user" or "system") plus flags like "disabled"
+            let options = parts.get(3).unwrap_or(&"");
+            Some(FlatpakRemote {
+                name: name.to_string(),
+                title: parts.get(1).unwrap_or(&"").trim().to_string(),
+                url: parts.get(2).unwrap_or(&"").trim().to_string(),
+                is_user: options.split(',').any(|o| o.trim() == "user"),
+            })
+        })
+        .collect();
+
+    Ok(remotes)
+}
+
+// Helper function to parse `flatpak search --columns=application,name,description,version,branch,remotes`
+// An app available from several remotes is listed once with a comma-separated remotes column
+fn parse_search_results(output: &str) -> Vec<SearchResult> {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split('\t').collect();
+            let app_id = parts.first()?.trim();
+            // "No matches found" and similar messages have no tab-separated columns
+            if app_id.is_empty() || parts.len() < 2 {
+                return None;
+            }
+            Some(SearchResult {
+                app_id: app_id.to_string(),
+                name: parts.get(1).unwrap_or(&"").trim().to_string(),
+                description: parts.get(2).unwrap_or(&"").trim().to_string(),
+                version: parts.get(3).unwrap_or(&"").trim().to_string(),
+                branch: parts.get(4).unwrap_or(&"").trim().to_string(),
+                remotes: parts
+                    .get(5)
+                    .unwrap_or(&"")
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|remote| !remote.is_empty())
+                    .map(String::from)
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+#[tauri::command]
+async fn search_flatpak(
+    app: tauri::AppHandle,
+    query: String,
+    limit: usize,
+    offset: usize,
+    sort: Option<SearchSort>,
+    remotes: Option<Vec<String>>,
+) -> Result<Vec<SearchResult>, StoreError> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let shell = app.shell();
+
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    // The query is passed as its own argument, no shell involved
+    let output = if is_flatpak {
+        // Inside flatpak, use flatpak-spawn to execute on the host
+        shell
+            .command("flatpak-spawn")
+            .args([
+                "--host",
+                "flatpak",
+                "search",
+                "--columns=application,name,description,version,branch,remotes",
+                query,
+            ])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
+    } else {
+        // Outside flatpak, use flatpak directly
+        shell
+            .command("flatpak")
+            .args([
+                "search",
+                "--columns=application,name,description,version,branch,remotes",
+                query,
+            ])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak", e))?
+    };
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
+    }
+
+    let mut results = parse_search_results(&String::from_utf8_lossy(&output.stdout));
+
+    // flatpak searches every configured remote; narrow down to the requested ones
+    let remotes = remotes.unwrap_or_default();
+    if !remotes.is_empty() {
+        results.retain(|r| r.remotes.iter().any(|remote| remotes.contains(remote)));
+    }
+
+    if sort.unwrap_or_default() == SearchSort::Name {
+        results.sort_by_key(|r| r.name.to_lowercase());
+    }
+
+    Ok(results.into_iter().skip(offset).take(limit).collect())
+}
+
+// First-run check: a missing flatpak (or flatpak-spawn) is reported as unavailable, not as an error
+#[tauri::command]
+async fn check_flatpak_available(app: tauri::AppHandle) -> Result<FlatpakStatus, StoreError> {
+    let shell = app.shell();
+
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    let output = if is_flatpak {
+        // Inside flatpak, use flatpak-spawn to execute on the host
+        shell
+            .command("flatpak-spawn")
+            .args(["--host", "flatpak", "--version"])
+            .output()
+            .await
+    } else {
+        // Outside flatpak, use flatpak directly
+        shell.command("flatpak").args(["--version"]).output().await
+    };
+
+    // Output looks like "Flatpak 1.14.4"
+    let version = match output {
+        Ok(o) if o.status.success() => {
+            let stdout = String::from_utf8_lossy(&o.stdout);
+            stdout.split_whitespace().last().map(String::from)
+        }
+        _ => {
+            return Ok(FlatpakStatus {
+                available: false,
+                version: None,
+                flathub_configured: false,
+            });
+        }
+    };
+
+    // A remote listing failure shouldn't hide that flatpak itself works
+    let flathub_configured = get_flatpak_remotes(app.clone())
+        .await
+        .map(|remotes| remotes.iter().any(|r| r.name == DEFAULT_REMOTE))
+        .unwrap_or(false);
+
+    Ok(FlatpakStatus {
+        available: true,
+        version,
+        flathub_configured,
+    })
+}
+
+// Inside the sandbox every flatpak command goes through `flatpak-spawn --host`, which
+// needs the org.freedesktop.Flatpak talk permission; probe it with a no-op
+#[tauri::command]
+async fn check_host_access(app: tauri::AppHandle) -> Result<HostAccess, StoreError> {
+    if std::env::var("FLATPAK_ID").is_err() {
+        return Ok(HostAccess {
+            can_spawn_host: true,
+            detail: "Not running in a sandbox".to_string(),
+        });
+    }
+
+    let output = app
+        .shell()
+        .command("flatpak-spawn")
+        .args(["--host", "true"])
+        .output()
+        .await;
+
+    let access = match output {
+        Ok(o) if o.status.success() => HostAccess {
+            can_spawn_host: true,
+            detail: "Host commands are available".to_string(),
+        },
+        Ok(o) => {
+            let stderr = String::from_utf8_lossy(&o.stderr).trim().to_string();
+            HostAccess {
+                can_spawn_host: false,
+                detail: if stderr.is_empty() {
+                    format!("flatpak-spawn exited with {:?}", o.status.code())
+                } else {
+                    stderr
+                },
+            }
+        }
+        Err(e) => HostAccess {
+            can_spawn_host: false,
+            detail: format!("Failed to run flatpak-spawn: {}", e),
+        },
+    };
+    if !access.can_spawn_host {
+        log::warn!("[check_host_access] {}", access.detail);
+    }
+    Ok(access)
+}
+
+// One-click "enable flathub" for fresh systems; system scope goes through pkexec
+#[tauri::command]
+async fn add_flathub_remote(app: tauri::AppHandle, scope: InstallScope) -> Result<(), StoreError> {
+    let shell = app.shell();
+
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    let (program, args) = flatpak_command_parts(
+        is_flatpak,
+        scope,
+        &[
+            "remote-add",
+            "--if-not-exists",
+            scope.flag(),
+            DEFAULT_REMOTE,
+            FLATHUB_REPO_URL,
+        ],
+    );
+    let output = shell
+        .command(program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| StoreError::spawn(program, e))?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr).to_string();
+        let _ = app.emit("install-error", error.clone());
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
+    }
+
+    Ok(())
+}
+
+// Helper function to list what `flatpak list --columns=application,origin` has
+// installed from `remote`, apps and runtimes alike
+fn refs_from_remote(output: &str, remote: &str) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::new();
+    for line in output.lines() {
+        let mut parts = line.split('\t').map(str::trim);
+        let (Some(id), Some(origin)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        if origin == remote && !id.is_empty() && !ids.iter().any(|known| known == id) {
+            ids.push(id.to_string());
+        }
+    }
+    ids
+}
+
+/// Delete a configured remote. Fails with REMOTE_IN_USE, listing what is still
+/// installed from it, unless `force` is set; forced removal leaves those refs
+/// installed but without updates. System scope goes through pkexec.
+#[tauri::command]
+async fn remove_flatpak_remote(
+    app: tauri::AppHandle,
+    name: String,
+    scope: InstallScope,
+    force: Option<bool>,
+) -> Result<(), StoreError> {
+    // Remote names follow the same character rules as app ids
+    if !is_valid_flatpak_id(&name) {
+        return Err(StoreError::InvalidInput("invalid remote name".to_string()));
+    }
+    let force = force.unwrap_or(false);
+    let shell = app.shell();
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
 
-    let cache_images_dir = app_data_dir.join("cacheImages");
-    let index_path = cache_images_dir.join("index.json");
+    if !force {
+        // Listing doesn't need root, even for the system installation
+        let (program, args) = flatpak_command_parts(
+            is_flatpak,
+            InstallScope::User,
+            &["list", scope.flag(), "--columns=application,origin"],
+        );
+        let output = shell
+            .command(program)
+            .args(args)
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn(program, e))?;
+        if !output.status.success() {
+            let error = String::from_utf8_lossy(&output.stderr);
+            return Err(StoreError::flatpak_failed(output.status.code(), &error));
+        }
+        let in_use = refs_from_remote(&String::from_utf8_lossy(&output.stdout), &name);
+        if !in_use.is_empty() {
+            return Err(StoreError::RemoteInUse(in_use));
+        }
+    }
 
-    if index_path.exists() {
-        println!("[Cache] Old cache system detected (index.json found). Clearing...");
-        if cache_images_dir.exists() {
-            fs::remove_dir_all(&cache_images_dir)
-                .map_err(|e| format!("Failed to clear old cache directory: {}", e))?;
+    let mut args = vec!["remote-delete", scope.flag()];
+    if force {
+        args.push("--force");
+    }
+    args.push(&name);
+    let (program, args) = flatpak_command_parts(is_flatpak, scope, &args);
+    let output = shell
+        .command(program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| StoreError::spawn(program, e))?;
 
-            // Recreate the directory
-            fs::create_dir_all(&cache_images_dir)
-                .map_err(|e| format!("Failed to recreate cache directory: {}", e))?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
+    }
+
+    log::info!("[remove_flatpak_remote] Removed {}", name);
+    Ok(())
+}
+
+// Install without a PTY, auto-accepting every prompt. start_flatpak_interactive stays
+// the path for installs where the user has to pick between options
+#[tauri::command]
+async fn install_flatpak_noninteractive(
+    app: tauri::AppHandle,
+    app_id: String,
+    remote: Option<String>,
+    scope: Option<InstallScope>,
+    max_parallel_downloads: Option<u32>,
+    arch: Option<String>,
+) -> Result<(), StoreError> {
+    if !is_valid_flatpak_id(&app_id) {
+        return Err(StoreError::InvalidInput("invalid app id".to_string()));
+    }
+    let remote = resolve_remote(remote)?;
+    let scope = scope.unwrap_or_default();
+    let arch_flag = resolve_arch_flag(arch)?;
+
+    status::emit_status(&app, status::INSTALL_START, Some(&app_id))?;
+    let _slot = acquire_download_slot(&app, max_parallel_downloads).await;
+
+    let shell = app.shell();
+
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    let mut install_args = vec!["install", "-y", scope.flag()];
+    install_args.extend(arch_flag.as_deref());
+    install_args.extend([remote.as_str(), app_id.as_str()]);
+
+    // Inside flatpak this goes through flatpak-spawn, system scope adds pkexec
+    let (program, args) = flatpak_command_parts(is_flatpak, scope, &install_args);
+    let (mut rx, child) = shell
+        .command(program)
+        .args(args)
+        .spawn()
+        .map_err(|e| StoreError::spawn(program, e))?;
+    track_shell_child(&app, &app_id, child);
+
+    // Read output in real-time
+    while let Some(event) = rx.recv().await {
+        match event {
+            tauri_plugin_shell::process::CommandEvent::Stdout(line)
+            | tauri_plugin_shell::process::CommandEvent::Stderr(line) => {
+                // Flatpak sends progress output to stderr
+                let output = String::from_utf8_lossy(&line);
+                app.emit("install-output", output.to_string())
+                    .map_err(|e| format!("Failed to emit event: {}", e))?;
+                emit_install_progress(&app, Some(&app_id), &output);
+            }
+            tauri_plugin_shell::process::CommandEvent::Error(err) => {
+                app.emit("install-error", err)
+                    .map_err(|e| format!("Failed to emit error: {}", e))?;
+            }
+            tauri_plugin_shell::process::CommandEvent::Terminated(payload) => {
+                let success = payload.code == Some(0);
+                history::record(&app, &app_id, "install", None, success).await;
+                if untrack_shell_child(&app, &app_id) {
+                    let code = payload.code.unwrap_or(-1);
+                    app.emit("install-completed", code)
+                        .map_err(|e| format!("Failed to emit completion: {}", e))?;
+                    emit_operation_completed(&app, &app_id, "install", code);
+                    notifications::notify_completion(&app, &app_id, "install", success);
+                }
+                break;
+            }
+            _ => {}
         }
     }
+
     Ok(())
 }
 
 #[tauri::command]
-async fn download_and_cache_image(
+async fn update_flatpak(
     app: tauri::AppHandle,
     app_id: String,
-    image_url: String,
-) -> Result<String, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    max_parallel_downloads: Option<u32>,
+) -> Result<(), StoreError> {
+    if !is_valid_flatpak_id(&app_id) {
+        return Err(StoreError::InvalidInput("invalid app id".to_string()));
+    }
+    status::emit_status(&app, status::UPDATE_START, Some(&app_id))?;
+    let _slot = acquire_download_slot(&app, max_parallel_downloads).await;
 
-    let cache_images_dir = app_data_dir.join("cacheImages");
-    fs::create_dir_all(&cache_images_dir)
-        .map_err(|e| format!("Failed to create cacheImages directory: {}", e))?;
+    let shell = app.shell();
 
-    // Determinar extensión desde la URL (consistente con check_cached_image_exists)
-    let extension = if image_url.ends_with(".svg") || image_url.contains(".svg?") {
-        "svg"
-    } else if image_url.ends_with(".webp") || image_url.contains(".webp?") {
-        "webp"
-    } else if image_url.ends_with(".jpg")
-        || image_url.ends_with(".jpeg")
-        || image_url.contains(".jpg?")
-        || image_url.contains(".jpeg?")
-    {
-        "jpg"
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    let (mut rx, child) = if is_flatpak {
+        // Inside flatpak, use flatpak-spawn to execute on the host
+        shell
+            .command("flatpak-spawn")
+            .args(["--host", "flatpak", "update", "-y", &app_id])
+            .spawn()
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
     } else {
-        "png" // default
+        // Outside flatpak, use flatpak directly
+        shell
+            .command("flatpak")
+            .args(["update", "-y", &app_id])
+            .spawn()
+            .map_err(|e| StoreError::spawn("flatpak", e))?
     };
+    track_shell_child(&app, &app_id, child);
 
-    // Generar nombre de archivo único usando xxHash3
-    // Si app_id no está vacío y es diferente de image_url, usarlo como key (caso de cacheKey)
-    // Si no, usar image_url (caso normal)
-    use xxhash_rust::xxh3::xxh3_64;
-    let key_to_hash = if !app_id.is_empty() && app_id != image_url {
-        app_id
+    // Read output in real-time
+    while let Some(event) = rx.recv().await {
+        match event {
+            tauri_plugin_shell::process::CommandEvent::Stdout(line) => {
+                let output = String::from_utf8_lossy(&line);
+                app.emit("install-output", output.to_string())
+                    .map_err(|e| format!("Failed to emit event: {}", e))?;
+                emit_install_progress(&app, Some(&app_id), &output);
+            }
+            tauri_plugin_shell::process::CommandEvent::Stderr(line) => {
+                // Flatpak sends progress output to stderr
+                let output = String::from_utf8_lossy(&line);
+                app.emit("install-output", output.to_string())
+                    .map_err(|e| format!("Failed to emit event: {}", e))?;
+                emit_install_progress(&app, Some(&app_id), &output);
+            }
+            tauri_plugin_shell::process::CommandEvent::Error(err) => {
+                app.emit("install-error", err)
+                    .map_err(|e| format!("Failed to emit error: {}", e))?;
+            }
+            tauri_plugin_shell::process::CommandEvent::Terminated(payload) => {
+                history::record(&app, &app_id, "update", None, payload.code == Some(0)).await;
+                if untrack_shell_child(&app, &app_id) {
+                    let code = payload.code.unwrap_or(-1);
+                    app.emit("install-completed", code)
+                        .map_err(|e| format!("Failed to emit completion: {}", e))?;
+                    emit_operation_completed(&app, &app_id, "update", code);
+                    notifications::notify_completion(&app, &app_id, "update", code == 0);
+                }
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn update_system_flatpaks(
+    app: tauri::AppHandle,
+    max_parallel_downloads: Option<u32>,
+) -> Result<(), StoreError> {
+    status::emit_status(&app, status::UPDATE_SYSTEM_START, None)?;
+    let _slot = acquire_download_slot(&app, max_parallel_downloads).await;
+
+    let shell = app.shell();
+
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    let (mut rx, child) = if is_flatpak {
+        // Inside flatpak, use flatpak-spawn to execute on the host
+        shell
+            .command("flatpak-spawn")
+            .args(["--host", "flatpak", "update", "-y"])
+            .spawn()
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
     } else {
-        image_url.clone()
+        // Outside flatpak, use flatpak directly
+        shell
+            .command("flatpak")
+            .args(["update", "-y"])
+            .spawn()
+            .map_err(|e| StoreError::spawn("flatpak", e))?
     };
+    track_shell_child(&app, SYSTEM_UPDATE_KEY, child);
 
-    let hash = xxh3_64(key_to_hash.as_bytes());
-    let filename = format!("{:x}.{}", hash, extension);
-    let file_path = cache_images_dir.join(&filename);
+    // Read output in real-time
+    while let Some(event) = rx.recv().await {
+        match event {
+            tauri_plugin_shell::process::CommandEvent::Stdout(line) => {
+                let output = String::from_utf8_lossy(&line);
+                app.emit("install-output", output.to_string())
+                    .map_err(|e| format!("Failed to emit event: {}", e))?;
+                emit_install_progress(&app, None, &output);
+            }
+            tauri_plugin_shell::process::CommandEvent::Stderr(line) => {
+                // Flatpak sends progress output to stderr
+                let output = String::from_utf8_lossy(&line);
+                app.emit("install-output", output.to_string())
+                    .map_err(|e| format!("Failed to emit event: {}", e))?;
+                emit_install_progress(&app, None, &output);
+            }
+            tauri_plugin_shell::process::CommandEvent::Error(err) => {
+                app.emit("install-error", err)
+                    .map_err(|e| format!("Failed to emit error: {}", e))?;
+            }
+            tauri_plugin_shell::process::CommandEvent::Terminated(payload) => {
+                if untrack_shell_child(&app, SYSTEM_UPDATE_KEY) {
+                    let code = payload.code.unwrap_or(-1);
+                    app.emit("install-completed", code)
+                        .map_err(|e| format!("Failed to emit completion: {}", e))?;
+                    emit_operation_completed(&app, SYSTEM_UPDATE_KEY, "update", code);
+                }
+                break;
+            }
+            _ => {}
+        }
+    }
 
-    // Si el archivo ya existe, no descargar de nuevo
-    if file_path.exists() {
-        return Ok(filename);
+    Ok(())
+}
+
+// Forward a spawned flatpak command's output as install-output/install-progress/install-completed events
+// Returns the exit code, None if the command ended without reporting one. A child tracked
+// under `tracked_key` is untracked at the end; CANCELLED_EXIT_CODE means it was cancelled
+async fn forward_command_events<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    mut rx: tauri::async_runtime::Receiver<tauri_plugin_shell::process::CommandEvent>,
+    progress_app_id: Option<&str>,
+    tracked_key: Option<&str>,
+) -> Result<Option<i32>, String> {
+    while let Some(event) = rx.recv().await {
+        match event {
+            tauri_plugin_shell::process::CommandEvent::Stdout(line)
+            | tauri_plugin_shell::process::CommandEvent::Stderr(line) => {
+                // Flatpak sends progress output to stderr
+                let output = String::from_utf8_lossy(&line);
+                app.emit("install-output", output.to_string())
+                    .map_err(|e| format!("Failed to emit event: {}", e))?;
+                emit_install_progress(app, progress_app_id, &output);
+            }
+            tauri_plugin_shell::process::CommandEvent::Error(err) => {
+                app.emit("install-error", err)
+                    .map_err(|e| format!("Failed to emit error: {}", e))?;
+            }
+            tauri_plugin_shell::process::CommandEvent::Terminated(payload) => {
+                // Whatever the command did, the installation may have changed
+                mark_installed_changed(app);
+                // A cancelled command has had its install-completed sent already
+                if !tracked_key.is_none_or(|key| untrack_shell_child(app, key)) {
+                    return Ok(Some(CANCELLED_EXIT_CODE));
+                }
+                app.emit("install-completed", payload.code.unwrap_or(-1))
+                    .map_err(|e| format!("Failed to emit completion: {}", e))?;
+                return Ok(payload.code);
+            }
+            _ => {}
+        }
     }
 
-    // Descargar la imagen
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&image_url)
-        .send()
-        .await
-        .map_err(|e| format!("Error downloading image: {}", e))?;
+    if let Some(key) = tracked_key {
+        untrack_shell_child(app, key);
+    }
+    Ok(None)
+}
+
+#[derive(Debug, Serialize)]
+struct RemovedRef {
+    id: String,
+    branch: String,
+    // From `flatpak list`, None when flatpak doesn't report a size
+    installed_bytes: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct UninstallImpact {
+    app_id: String,
+    refs: Vec<RemovedRef>,
+    freed_bytes: u64,
+}
+
+// Helper function to read the (id, branch) of every ref in a flatpak transaction table
+// Format: " 1.     org.gimp.GIMP.Locale    stable    r"
+fn parse_transaction_refs(output: &str) -> Vec<(String, String)> {
+    let mut refs = Vec::new();
+    for line in output.lines() {
+        let normalized = line.replace(['\u{a0}', '\t'], " ");
+        let parts: Vec<&str> = normalized.split_whitespace().collect();
+        let is_list_entry = parts
+            .first()
+            .and_then(|p| p.strip_suffix('.'))
+            .map(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+            .unwrap_or(false);
+        if !is_list_entry {
+            continue;
+        }
+
+        // Skip markers such as "[✓]" or "[ ]" between the index and the ID
+        let mut columns = parts[1..]
+            .iter()
+            .skip_while(|p| p.starts_with('[') || p.ends_with(']'));
+        if let (Some(id), Some(branch)) = (columns.next(), columns.next()) {
+            refs.push((id.to_string(), branch.to_string()));
+        }
+    }
+    refs
+}
+
+// Helper function to read `flatpak list --columns=application,branch,size` into
+// installed sizes keyed by (id, branch)
+fn parse_installed_ref_sizes(output: &str) -> HashMap<(String, String), u64> {
+    let mut sizes = HashMap::new();
+    for line in output.lines() {
+        let mut parts = line.split('\t').map(str::trim);
+        let (Some(id), Some(branch), Some(size)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        if let Some(bytes) = parse_size_string(size) {
+            sizes
+                .entry((id.to_string(), branch.to_string()))
+                .or_insert(bytes);
+        }
+    }
+    sizes
+}
+
+// flatpak-spawn does not forward the caller's environment to the host, so LANG=C
+// goes through --env there to keep flatpak's output in English
+fn flatpak_std_command(is_flatpak: bool, args: &[&str]) -> Command {
+    let mut command = if is_flatpak {
+        let mut command = Command::new("flatpak-spawn");
+        command.args(["--host", "--env=LANG=C", "flatpak"]);
+        command
+    } else {
+        let mut command = Command::new("flatpak");
+        command.env("LANG", "C");
+        command
+    };
+    command.args(args);
+    command
+}
+
+// Run `flatpak <args>` answering "n" to its confirmation prompt, so nothing is changed
+// The wait runs on a blocking thread instead of stalling the async runtime
+async fn flatpak_answer_no(args: Vec<String>) -> Result<std::process::Output, StoreError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+        let program = if is_flatpak {
+            "flatpak-spawn"
+        } else {
+            "flatpak"
+        };
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+        let mut child = flatpak_std_command(is_flatpak, &args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| StoreError::spawn(program, e))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(b"n\n");
+        }
+
+        child
+            .wait_with_output()
+            .map_err(|e| StoreError::Internal(format!("Failed to read flatpak output: {}", e)))
+    })
+    .await
+    .map_err(|e| StoreError::Internal(format!("Flatpak task failed: {}", e)))?
+}
+
+/// What uninstalling `app_id` would remove (the app plus its locale, debug and
+/// other related refs) and how much space that frees. Nothing is removed: the
+/// confirmation prompt is answered "n", like get_update_sizes does.
+#[tauri::command]
+async fn get_uninstall_impact(
+    _app: tauri::AppHandle,
+    app_id: String,
+) -> Result<UninstallImpact, StoreError> {
+    if !is_valid_flatpak_id(&app_id) {
+        return Err(StoreError::InvalidInput(format!(
+            "Invalid app id: {}",
+            app_id
+        )));
+    }
+    // Closing stdin right after the "n" also aborts the "which installation?"
+    // question asked when the app is installed both per-user and system-wide
+    let output = flatpak_answer_no(vec!["uninstall".to_string(), app_id.clone()]).await?;
+    let combined_output = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let refs = parse_transaction_refs(&combined_output);
+    if refs.is_empty() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
+    }
+
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+    let program = if is_flatpak {
+        "flatpak-spawn"
+    } else {
+        "flatpak"
+    };
+    let list = tauri::async_runtime::spawn_blocking(move || {
+        flatpak_std_command(is_flatpak, &["list", "--columns=application,branch,size"]).output()
+    })
+    .await
+    .map_err(|e| StoreError::Internal(format!("Flatpak task failed: {}", e)))?
+    .map_err(|e| StoreError::spawn(program, e))?;
+    let sizes = parse_installed_ref_sizes(&String::from_utf8_lossy(&list.stdout));
+
+    let refs: Vec<RemovedRef> = refs
+        .into_iter()
+        .map(|(id, branch)| RemovedRef {
+            installed_bytes: sizes.get(&(id.clone(), branch.clone())).copied(),
+            id,
+            branch,
+        })
+        .collect();
+    let freed_bytes = refs.iter().filter_map(|r| r.installed_bytes).sum();
 
-    if !response.status().is_success() {
-        return Err(format!("HTTP Error: {}", response.status()));
-    }
+    Ok(UninstallImpact {
+        app_id,
+        refs,
+        freed_bytes,
+    })
+}
 
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| format!("Error reading image bytes: {}", e))?;
+// Dry run of `flatpak update`: answer "n" to the confirmation and report what each ref would download
+#[tauri::command]
+async fn get_update_sizes(_app: tauri::AppHandle) -> Result<Vec<UpdateSize>, StoreError> {
+    let output = flatpak_answer_no(vec!["update".to_string()]).await?;
 
-    fs::write(&file_path, &bytes).map_err(|e| format!("Error saving image: {}", e))?;
+    let combined_output = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
 
-    Ok(filename)
+    Ok(parse_transaction_download_sizes(&combined_output))
 }
 
+// List the runtimes `flatpak uninstall --unused` would remove, answering "n" so nothing is removed
 #[tauri::command]
-fn get_cached_image_path(app: tauri::AppHandle, filename: String) -> Result<String, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+async fn get_unused_runtimes(_app: tauri::AppHandle) -> Result<Vec<String>, StoreError> {
+    let output = flatpak_answer_no(vec!["uninstall".to_string(), "--unused".to_string()]).await?;
 
-    let file_path = app_data_dir.join("cacheImages").join(filename);
+    let combined_output = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
 
-    // Usar canonicalize para obtener la ruta absoluta normalizada
-    let canonical_path = file_path
-        .canonicalize()
-        .unwrap_or_else(|_| file_path.clone());
+    // Proposed removals are numbered like the install list:
+    // " 1.     org.gnome.Platform    42    r"
+    let mut unused = Vec::new();
+    for line in combined_output.lines() {
+        let normalized = line.replace(['\u{a0}', '\t'], " ");
+        let parts: Vec<&str> = normalized.split_whitespace().collect();
+        let is_list_entry = parts
+            .first()
+            .and_then(|p| p.strip_suffix('.'))
+            .map(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+            .unwrap_or(false);
+        if !is_list_entry {
+            continue;
+        }
 
-    Ok(canonical_path.to_string_lossy().to_string())
-}
+        // Skip markers such as "[-]" between the index and the ID
+        let mut fields = parts[1..].iter().filter(|p| !p.starts_with('['));
+        if let Some(id) = fields.next() {
+            match fields.next() {
+                Some(branch) => unused.push(format!("{}//{}", id, branch)),
+                None => unused.push(id.to_string()),
+            }
+        }
+    }
 
-#[tauri::command]
-fn check_file_exists(path: String) -> bool {
-    std::path::Path::new(&path).exists()
+    Ok(unused)
 }
 
 #[tauri::command]
-fn get_cached_image_filename(cache_key: String, image_url: String) -> String {
-    use xxhash_rust::xxh3::xxh3_64;
-    // Si hay cacheKey, usar eso; si no, usar imageUrl
-    let key_to_hash = if !cache_key.is_empty() && cache_key != image_url {
-        cache_key
-    } else {
-        image_url.clone()
-    };
+async fn remove_unused_runtimes(app: tauri::AppHandle) -> Result<(), StoreError> {
+    status::emit_status(&app, status::CLEANUP_START, None)?;
 
-    let hash = xxh3_64(key_to_hash.as_bytes());
+    let shell = app.shell();
 
-    // Determinar extensión basándose en la URL
-    let extension = if image_url.ends_with(".svg") {
-        "svg"
-    } else if image_url.ends_with(".webp") {
-        "webp"
-    } else if image_url.ends_with(".jpg") || image_url.ends_with(".jpeg") {
-        "jpg"
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    let (rx, _child) = if is_flatpak {
+        // Inside flatpak, use flatpak-spawn to execute on the host
+        shell
+            .command("flatpak-spawn")
+            .args(["--host", "flatpak", "uninstall", "--unused", "-y"])
+            .spawn()
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
     } else {
-        "png"
+        // Outside flatpak, use flatpak directly
+        shell
+            .command("flatpak")
+            .args(["uninstall", "--unused", "-y"])
+            .spawn()
+            .map_err(|e| StoreError::spawn("flatpak", e))?
     };
 
-    format!("{:x}.{}", hash, extension)
+    forward_command_events(&app, rx, None, None).await?;
+    Ok(())
 }
 
+// Verify and fix a corrupted installation (interrupted downloads, power loss)
 #[tauri::command]
-fn check_cached_image_exists(
-    app: tauri::AppHandle,
-    cache_key: String,
-    image_url: String,
-) -> Result<String, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+async fn repair_flatpak(app: tauri::AppHandle, scope: InstallScope) -> Result<(), StoreError> {
+    status::emit_status(&app, status::REPAIR_START, None)?;
+
+    // Inside flatpak this goes through flatpak-spawn, system scope adds pkexec
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+    let (program, args) = flatpak_command_parts(is_flatpak, scope, &["repair", scope.flag()]);
+    let (rx, _child) = app
+        .shell()
+        .command(program)
+        .args(args)
+        .spawn()
+        .map_err(|e| StoreError::spawn(program, e))?;
 
-    let cache_images_dir = app_data_dir.join("cacheImages");
+    forward_command_events(&app, rx, None, None).await?;
+    Ok(())
+}
 
-    use xxhash_rust::xxh3::xxh3_64;
+/// Download fresh appstream metadata (names, descriptions, versions) for a
+/// remote, for when search or the update list look out of date. Output streams
+/// through `install-output` like any other operation.
+#[tauri::command]
+async fn update_appstream(app: tauri::AppHandle, remote: Option<String>) -> Result<(), StoreError> {
+    let remote = resolve_remote(remote)?;
+    status::emit_status(&app, status::APPSTREAM_START, Some(&remote))?;
 
-    // Si hay cacheKey, usar eso; si no, usar imageUrl
-    let key_to_hash = if !cache_key.is_empty() && cache_key != image_url {
-        cache_key
-    } else {
-        image_url.clone()
-    };
+    // Refreshing a system remote's appstream doesn't need authentication, so no pkexec
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+    let (program, args) = flatpak_command_parts(
+        is_flatpak,
+        InstallScope::User,
+        &["update", "--appstream", &remote],
+    );
+    let (rx, child) = app
+        .shell()
+        .command(program)
+        .args(args)
+        .spawn()
+        .map_err(|e| StoreError::spawn(program, e))?;
+    track_shell_child(&app, &remote, child);
+
+    let code = forward_command_events(&app, rx, None, Some(&remote)).await?;
+    if code == Some(CANCELLED_EXIT_CODE) {
+        return Ok(());
+    }
+    emit_operation_completed(&app, &remote, "appstream", code.unwrap_or(-1));
+    if code != Some(0) {
+        return Err(StoreError::CommandFailed(
+            code.unwrap_or(-1),
+            format!("Failed to refresh appstream data of {}", remote),
+        ));
+    }
+    log::info!("[update_appstream] Refreshed {}", remote);
+    Ok(())
+}
 
-    let hash = xxh3_64(key_to_hash.as_bytes());
+// ~/.var/app/<app_id>, where flatpak keeps an app's settings, cache and data.
+// The id must be a single path component so the result can't leave ~/.var/app.
+fn app_user_data_dir(app_id: &str) -> Result<std::path::PathBuf, StoreError> {
+    if !is_valid_flatpak_id(app_id) || app_id.starts_with('.') || app_id.contains("..") {
+        return Err(StoreError::InvalidInput(format!(
+            "Invalid app id: {}",
+            app_id
+        )));
+    }
 
-    // Determinar extensión desde la URL (que siempre tiene la URL real de la imagen)
-    let extension = if image_url.ends_with(".svg") || image_url.contains(".svg?") {
-        "svg"
-    } else if image_url.ends_with(".webp") || image_url.contains(".webp?") {
-        "webp"
-    } else if image_url.ends_with(".jpg")
-        || image_url.ends_with(".jpeg")
-        || image_url.contains(".jpg?")
-        || image_url.contains(".jpeg?")
-    {
-        "jpg"
+    // HOME is the real host home both inside and outside the sandbox
+    let home_dir = std::env::var("HOME").map_err(|_| "HOME is not set".to_string())?;
+    let base = std::path::Path::new(&home_dir).join(".var").join("app");
+    let dir = base.join(app_id);
+    let file_name = std::ffi::OsStr::new(app_id);
+    if dir.parent() != Some(base.as_path()) || dir.file_name() != Some(file_name) {
+        return Err(StoreError::InvalidInput(format!(
+            "Invalid app id: {}",
+            app_id
+        )));
+    }
+    Ok(dir)
+}
+
+// Delete everything inside `dir` without removing `dir` itself. Symlinks are removed,
+// never followed.
+fn clear_directory(dir: &std::path::Path) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if fs::symlink_metadata(&path)?.is_dir() {
+            fs::remove_dir_all(&path)?;
+        } else {
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reset an app to its defaults by emptying ~/.var/app/<app_id>. The app stays
+/// installed; unlike `uninstall --delete-data` nothing else is touched.
+#[tauri::command]
+async fn reset_app_data(app: tauri::AppHandle, app_id: String) -> Result<(), StoreError> {
+    let dir = app_user_data_dir(&app_id)?;
+    status::emit_status(&app, status::RESET_DATA_START, Some(&app_id))?;
+
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+    let result = if is_flatpak {
+        // Other apps' data isn't visible inside the sandbox; empty it on the host.
+        // find -delete doesn't follow symlinks.
+        let dir = dir.to_string_lossy().to_string();
+        let output = app
+            .shell()
+            .command("flatpak-spawn")
+            .args(["--host", "find", &dir, "-mindepth", "1", "-delete"])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if output.status.success() || stderr.contains("No such file or directory") {
+            Ok(())
+        } else {
+            Err(StoreError::Internal(format!(
+                "Failed to clear {}: {}",
+                dir,
+                stderr.trim()
+            )))
+        }
     } else {
-        "png" // default
+        match clear_directory(&dir) {
+            Ok(()) => Ok(()),
+            // The app never wrote any data
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(StoreError::Internal(format!(
+                "Failed to clear {}: {}",
+                dir.display(),
+                e
+            ))),
+        }
     };
 
-    let filename = format!("{:x}.{}", hash, extension);
-    let file_path = cache_images_dir.join(&filename);
+    match &result {
+        Ok(()) => log::info!("[reset_app_data] Cleared data of {}", app_id),
+        Err(e) => {
+            log::error!("[reset_app_data] {}", e);
+            let _ = app.emit("install-error", e.to_string());
+        }
+    }
+    let code = if result.is_ok() { 0 } else { 1 };
+    let _ = app.emit("install-completed", code);
+    emit_operation_completed(&app, &app_id, "reset", code);
+    result
+}
 
-    if file_path.exists() {
-        Ok(filename)
-    } else {
-        Err("Image not found in cache".to_string())
+#[derive(Debug, Serialize)]
+struct StorageBreakdown {
+    // The installed app itself, from `flatpak info`
+    app_bytes: u64,
+    // Settings, cache and data under ~/.var/app/<app_id>
+    data_bytes: u64,
+}
+
+// Total size of the files under `path`, without following symlinks.
+// Entries that vanish or can't be read while walking are skipped.
+fn directory_size(path: &std::path::Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| directory_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+async fn app_data_size(app: &tauri::AppHandle, app_id: &str) -> Result<u64, StoreError> {
+    let dir = app_user_data_dir(app_id)?;
+    if std::env::var("FLATPAK_ID").is_err() {
+        return Ok(directory_size(&dir));
     }
+
+    // Other apps' data isn't visible inside the sandbox; measure it on the host
+    let dir = dir.to_string_lossy().to_string();
+    let output = app
+        .shell()
+        .command("flatpak-spawn")
+        .args(["--host", "du", "-sb", &dir])
+        .output()
+        .await
+        .map_err(|e| StoreError::spawn("flatpak-spawn", e))?;
+    // du fails when the app never wrote any data
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .next()
+        .and_then(|size| size.parse().ok())
+        .unwrap_or(0))
 }
 
+/// Disk used by an installed app, split into the app and its saved data.
+#[tauri::command]
+async fn get_app_storage_breakdown(
+    app: tauri::AppHandle,
+    app_id: String,
+) -> Result<StorageBreakdown, StoreError> {
+    // Validates app_id before it is used in a path
+    let data_bytes = app_data_size(&app, &app_id).await?;
+    let info = flatpak_info_output(&app, &app_id).await?;
+    let app_bytes = parse_flatpak_info(&app_id, &info)
+        .installed_size
+        .unwrap_or(0);
+
+    Ok(StorageBreakdown {
+        app_bytes,
+        data_bytes,
+    })
+}
 
 #[tauri::command]
-fn get_cached_image_info(
+async fn launch_flatpak(
     app: tauri::AppHandle,
-    cache_key: String,
-    image_url: String,
-) -> Result<String, String> {
-    // Combina check_cached_image_exists + get_cached_image_path en una sola llamada
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    app_id: String,
+    args: Option<Vec<String>>,
+) -> Result<(), StoreError> {
+    if !is_valid_flatpak_id(&app_id) {
+        return Err(StoreError::InvalidInput("invalid app id".to_string()));
+    }
 
-    let cache_images_dir = app_data_dir.join("cacheImages");
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
 
-    use xxhash_rust::xxh3::xxh3_64;
+    // Arguments after the app id are handed to the app itself
+    let mut run_args: Vec<String> = vec!["run".to_string(), app_id.clone()];
+    run_args.extend(args.unwrap_or_default());
 
-    // Si hay cacheKey, usar eso; si no, usar imageUrl
-    let key_to_hash = if !cache_key.is_empty() && cache_key != image_url {
-        cache_key
+    let mut child = if is_flatpak {
+        // Inside flatpak, use flatpak-spawn to execute on the host
+        Command::new("flatpak-spawn")
+            .args(["--host", "flatpak"])
+            .args(&run_args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
     } else {
-        image_url.clone()
+        // Outside flatpak, use flatpak directly
+        Command::new("flatpak")
+            .args(&run_args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| StoreError::spawn("flatpak", e))?
     };
 
-    let hash = xxh3_64(key_to_hash.as_bytes());
+    let stdout = child.stdout.take().ok_or("Failed to get stdout")?;
+    let stderr = child.stderr.take().ok_or("Failed to get stderr")?;
 
-    // Determinar extension desde la URL
-    let extension = if image_url.ends_with(".svg") || image_url.contains(".svg?") {
-        "svg"
-    } else if image_url.ends_with(".webp") || image_url.contains(".webp?") {
-        "webp"
-    } else if image_url.ends_with(".jpg")
-        || image_url.ends_with(".jpeg")
-        || image_url.contains(".jpg?")
-        || image_url.contains(".jpeg?")
-    {
-        "jpg"
-    } else {
-        "png" // default
-    };
+    // Stream the launched app's output, keyed by app_id like pty-output
+    let app_clone = app.clone();
+    let app_id_clone = app_id.clone();
+    std::thread::spawn(move || {
+        let reader = BufReader::new(stdout);
+        for line in reader.lines().map_while(Result::ok) {
+            let _ = app_clone.emit("launch-output", (app_id_clone.clone(), line));
+        }
+    });
 
-    let filename = format!("{:x}.{}", hash, extension);
-    let file_path = cache_images_dir.join(&filename);
+    // Keep stderr around as well, it is the error message if the launch fails
+    let stderr_lines = Arc::new(Mutex::new(Vec::<String>::new()));
+    let stderr_lines_clone = Arc::clone(&stderr_lines);
+    let app_clone2 = app.clone();
+    let app_id_clone2 = app_id.clone();
+    let stderr_thread = std::thread::spawn(move || {
+        let reader = BufReader::new(stderr);
+        for line in reader.lines().map_while(Result::ok) {
+            stderr_lines_clone.lock().unwrap().push(line.clone());
+            let _ = app_clone2.emit("launch-output", (app_id_clone2.clone(), line));
+        }
+    });
 
-    // Verificar que existe y retornar la ruta absoluta
-    if file_path.exists() {
-        // Usar canonicalize para obtener la ruta absoluta normalizada
-        let canonical_path = file_path
-            .canonicalize()
-            .unwrap_or_else(|_| file_path.clone());
-        Ok(canonical_path.to_string_lossy().to_string())
-    } else {
-        Err("Image not found in cache".to_string())
+    // A bad or missing app id makes flatpak exit almost immediately, give it a moment
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(1) {
+        match child.try_wait() {
+            Ok(Some(status)) if !status.success() => {
+                let _ = stderr_thread.join();
+                let stderr = stderr_lines.lock().unwrap().join("\n");
+                return Err(StoreError::flatpak_failed(status.code(), &stderr));
+            }
+            Ok(Some(_)) => return Ok(()),
+            Ok(None) => std::thread::sleep(Duration::from_millis(100)),
+            Err(e) => return Err(StoreError::spawn("flatpak", e)),
+        }
     }
+
+    // Still running: reap it in the background so it doesn't linger as a zombie
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+
+    Ok(())
 }
 
 #[tauri::command]
-async fn get_installed_flatpaks(
+async fn uninstall_flatpak(
     app: tauri::AppHandle,
-) -> Result<InstalledPackagesResponse, String> {
+    app_id: String,
+    scope: Option<InstallScope>,
+) -> Result<(), StoreError> {
+    status::emit_status(&app, status::UNINSTALL_START, Some(&app_id))?;
+
     let shell = app.shell();
 
     // Detect if we're running inside a flatpak
     let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
 
-    // Get everything (apps + runtimes) with options column to distinguish
-    // Note: flatpak list without --system or --user gets both
-    // The 'options' column contains 'runtime' for runtimes/extensions and 'current' for apps
-    // The 'size' column contains the installed size in bytes
-    let output = if is_flatpak {
-        // Inside flatpak, use flatpak-spawn to execute on the host
-        shell
-            .command("flatpak-spawn")
-            .args([
-                "--host",
-                "flatpak",
-                "list",
-                "--columns=application,name,version,description,options,ref,size",
-            ])
-            .output()
-            .await
-            .map_err(|e| format!("Failed to execute flatpak-spawn: {}", e))?
-    } else {
-        // Outside flatpak, use flatpak directly
-        shell
-            .command("flatpak")
-            .args([
-                "list",
-                "--columns=application,name,version,description,options,ref,size",
-            ])
-            .output()
-            .await
-            .map_err(|e| format!("Failed to execute flatpak: {}", e))?
-    };
+    // Without an explicit scope flatpak picks whichever installation has the app
+    let mut uninstall_args = vec!["uninstall", "-y"];
+    if let Some(scope) = scope {
+        uninstall_args.push(scope.flag());
+    }
+    uninstall_args.push(&app_id);
+
+    // Inside flatpak this goes through flatpak-spawn, system scope adds pkexec
+    let (program, args) =
+        flatpak_command_parts(is_flatpak, scope.unwrap_or_default(), &uninstall_args);
+    let (mut rx, child) = shell
+        .command(program)
+        .args(args)
+        .spawn()
+        .map_err(|e| StoreError::spawn(program, e))?;
+    track_shell_child(&app, &app_id, child);
 
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Flatpak command failed: {}", error));
+    // Read output in real-time
+    while let Some(event) = rx.recv().await {
+        match event {
+            tauri_plugin_shell::process::CommandEvent::Stdout(line) => {
+                let output = String::from_utf8_lossy(&line);
+                app.emit("install-output", output.to_string())
+                    .map_err(|e| format!("Failed to emit event: {}", e))?;
+            }
+            tauri_plugin_shell::process::CommandEvent::Stderr(line) => {
+                // Flatpak sends progress output to stderr
+                let output = String::from_utf8_lossy(&line);
+                app.emit("install-output", output.to_string())
+                    .map_err(|e| format!("Failed to emit event: {}", e))?;
+            }
+            tauri_plugin_shell::process::CommandEvent::Error(err) => {
+                app.emit("install-error", err)
+                    .map_err(|e| format!("Failed to emit error: {}", e))?;
+            }
+            tauri_plugin_shell::process::CommandEvent::Terminated(payload) => {
+                history::record(&app, &app_id, "uninstall", None, payload.code == Some(0)).await;
+                if untrack_shell_child(&app, &app_id) {
+                    let code = payload.code.unwrap_or(-1);
+                    app.emit("install-completed", code)
+                        .map_err(|e| format!("Failed to emit completion: {}", e))?;
+                    emit_operation_completed(&app, &app_id, "uninstall", code);
+                }
+                break;
+            }
+            _ => {}
+        }
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut apps: Vec<InstalledApp> = Vec::new();
-    let mut runtimes: Vec<String> = Vec::new();
-    let mut potential_extensions: Vec<(String, String, String, String)> = Vec::new(); // (app_id, name, version, ref)
+    Ok(())
+}
 
-    // First pass: collect apps and potential extensions
-    for line in stdout.lines() {
-        if line.trim().is_empty() {
-            continue;
-        }
+// Sent after a batch command with whether each app ended up in the requested state
+const BATCH_COMPLETED_EVENT: &str = "batch-completed";
 
-        let parts: Vec<&str> = line.split('\t').collect();
-        if parts.len() >= 7 {
-            let app_id = parts[0].trim();
-            let options = parts[4].trim();
-            let ref_full = parts[5].trim();
-            let size_str = parts[6].trim();
+#[derive(Clone, Serialize)]
+struct BatchCompleted {
+    action: &'static str,
+    // None when the outcome for that app couldn't be checked
+    results: BTreeMap<String, Option<bool>>,
+}
 
-            // Distinguish apps from runtimes using the official 'options' column
-            // Apps have 'current' in options (e.g., "user,current" or "system,current")
-            // Runtimes/extensions have 'runtime' in options (e.g., "user,runtime" or "system,runtime")
-            let is_runtime = options.contains("runtime");
+fn check_batch_ids(app_ids: &[String]) -> Result<(), StoreError> {
+    if app_ids.is_empty() {
+        return Err(StoreError::InvalidInput("No apps given".to_string()));
+    }
+    match app_ids.iter().find(|id| !is_valid_flatpak_id(id)) {
+        Some(id) => Err(StoreError::InvalidInput(format!("Invalid app id: {}", id))),
+        None => Ok(()),
+    }
+}
 
-            if is_runtime {
-                // Check if it might be an app extension using blacklist approach
-                // Exclude system/platform extensions, consider everything else as potential app extension
-                let is_system_extension = app_id.contains("org.freedesktop.Platform.")
-                    || app_id.contains("org.freedesktop.Sdk.")
-                    || app_id.contains(".Platform.GL32")
-                    || app_id.contains(".Platform.VAAPI")
-                    || app_id.contains(".Platform.Compat.i386")
-                    || app_id.contains(".Platform.codecs");
+/// Uninstall several apps with a single `flatpak uninstall`, so they share one
+/// transaction instead of contending for the installation lock. Output streams
+/// like `uninstall_flatpak`; `batch-completed` then reports each app.
+#[tauri::command]
+async fn uninstall_flatpaks(app: tauri::AppHandle, app_ids: Vec<String>) -> Result<(), StoreError> {
+    check_batch_ids(&app_ids)?;
+    for app_id in &app_ids {
+        status::emit_status(&app, status::UNINSTALL_START, Some(app_id))?;
+    }
 
-                let is_potential_app_extension = !is_system_extension;
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+    let mut uninstall_args = vec!["uninstall", "-y"];
+    uninstall_args.extend(app_ids.iter().map(String::as_str));
+    let (program, args) = flatpak_command_parts(is_flatpak, InstallScope::User, &uninstall_args);
+    let (rx, child) = app
+        .shell()
+        .command(program)
+        .args(args)
+        .spawn()
+        .map_err(|e| StoreError::spawn(program, e))?;
+    track_shell_child(&app, BATCH_UNINSTALL_KEY, child);
+
+    // Even when cancelled, each app is reported with the state it was left in
+    forward_command_events(&app, rx, None, Some(BATCH_UNINSTALL_KEY)).await?;
+
+    let mut results = BTreeMap::new();
+    for app_id in app_ids {
+        let success = !is_app_installed(&app, &app_id).await;
+        history::record(&app, &app_id, "uninstall", None, success).await;
+        emit_operation_completed(&app, &app_id, "uninstall", if success { 0 } else { 1 });
+        results.insert(app_id, Some(success));
+    }
+    let _ = app.emit(
+        BATCH_COMPLETED_EVENT,
+        BatchCompleted {
+            action: "uninstall",
+            results,
+        },
+    );
+    Ok(())
+}
 
-                if is_potential_app_extension {
-                    potential_extensions.push((
-                        app_id.to_string(),
-                        parts[1].trim().to_string(),
-                        parts[2].trim().to_string(),
-                        ref_full.to_string(),
-                    ));
-                } else {
-                    // It's a platform/runtime/driver - store the ref
-                    runtimes.push(ref_full.to_string());
-                }
-            } else {
-                // It's an application
-                // Parse size from the size column (format: "715,3 MB" or "1,2 GB")
-                let installed_size = parse_size_string(size_str);
+/// Update the given apps with a single `flatpak update`. Apps still listed by
+/// `get_available_updates` afterwards count as failed and get an `install-error`;
+/// the others are updated regardless. If that list can't be read, every app's
+/// result is unknown (null). `batch-completed` reports each app.
+#[tauri::command]
+async fn update_flatpaks(
+    app: tauri::AppHandle,
+    app_ids: Vec<String>,
+    max_parallel_downloads: Option<u32>,
+) -> Result<(), StoreError> {
+    check_batch_ids(&app_ids)?;
+    for app_id in &app_ids {
+        status::emit_status(&app, status::UPDATE_START, Some(app_id))?;
+    }
+    let _slot = acquire_download_slot(&app, max_parallel_downloads).await;
 
-                apps.push(InstalledApp {
-                    app_id: app_id.to_string(),
-                    name: parts[1].trim().to_string(),
-                    version: parts[2].trim().to_string(),
-                    summary: if !parts[3].trim().is_empty() {
-                        Some(parts[3].trim().to_string())
-                    } else {
-                        None
-                    },
-                    developer: extract_developer(app_id),
-                    permissions: None, // Don't get permissions here, too slow
-                    installed_size,
-                });
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+    let mut update_args = vec!["update", "-y"];
+    update_args.extend(app_ids.iter().map(String::as_str));
+    let (program, args) = flatpak_command_parts(is_flatpak, InstallScope::User, &update_args);
+    let (rx, child) = app
+        .shell()
+        .command(program)
+        .args(args)
+        .spawn()
+        .map_err(|e| StoreError::spawn(program, e))?;
+    track_shell_child(&app, BATCH_UPDATE_KEY, child);
+
+    forward_command_events(&app, rx, None, Some(BATCH_UPDATE_KEY)).await?;
+
+    // The apps are updated whether or not their result can be checked afterwards
+    let pending: Option<HashSet<String>> = match get_available_updates(app.clone()).await {
+        Ok(updates) => Some(updates.into_iter().map(|update| update.app_id).collect()),
+        Err(e) => {
+            log::warn!("[update_flatpaks] Could not check the updated apps: {}", e);
+            None
+        }
+    };
+
+    let mut results = BTreeMap::new();
+    for app_id in app_ids {
+        let success = pending.as_ref().map(|pending| !pending.contains(&app_id));
+        let code = match success {
+            Some(true) => 0,
+            Some(false) => {
+                let _ = app.emit("install-error", format!("Failed to update {}", app_id));
+                1
+            }
+            None => {
+                let _ = app.emit(
+                    "install-error",
+                    format!("Could not check whether {} was updated", app_id),
+                );
+                -1
             }
+        };
+        if let Some(success) = success {
+            history::record(&app, &app_id, "update", None, success).await;
         }
+        emit_operation_completed(&app, &app_id, "update", code);
+        results.insert(app_id, success);
     }
+    let _ = app.emit(
+        BATCH_COMPLETED_EVENT,
+        BatchCompleted {
+            action: "update",
+            results,
+        },
+    );
+    Ok(())
+}
 
-    // Second pass: match extensions to their parent apps
-    let mut extensions: Vec<InstalledExtension> = Vec::new();
-    for (ext_id, ext_name, ext_version, ext_ref) in potential_extensions {
-        // Try to find parent app by checking if any installed app's ID is a prefix of this extension
-        let mut matched = false;
-        for app in &apps {
-            if ext_id.starts_with(&app.app_id) && ext_id != app.app_id {
-                extensions.push(InstalledExtension {
-                    extension_id: ext_id.clone(),
-                    name: ext_name.clone(),
-                    version: ext_version.clone(),
-                    parent_app_id: app.app_id.clone(),
-                });
-                matched = true;
-                break;
+// Helper function to parse the keyfile printed by `flatpak remote-info --show-metadata`
+// List values are ';'-separated with a trailing ';'
+fn parse_app_metadata(metadata: &str) -> AppMetadata {
+    let mut parsed = AppMetadata::default();
+    let mut group = "";
+
+    for line in metadata.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            group = name.trim();
+            if let Some(point) = group.strip_prefix("Extension ") {
+                parsed.extension_points.push(point.trim().to_string());
             }
+            continue;
         }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        let list = || {
+            value
+                .split(';')
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
 
-        // If no match found, it's probably a platform extension, add to runtimes
-        if !matched {
-            runtimes.push(ext_ref);
+        let permissions = &mut parsed.permissions;
+        match (group, key) {
+            ("Application", "runtime") => parsed.runtime = value.to_string(),
+            ("Application", "sdk") => parsed.sdk = value.to_string(),
+            ("Application", "command") => parsed.command = value.to_string(),
+            ("Context", "shared") => permissions.shared = list(),
+            ("Context", "sockets") => permissions.sockets = list(),
+            ("Context", "devices") => permissions.devices = list(),
+            ("Context", "filesystems") => permissions.filesystems = list(),
+            ("Context", "features") => permissions.features = list(),
+            ("Context", "persistent") => permissions.persistent = list(),
+            ("Session Bus Policy", name) => {
+                permissions
+                    .session_bus
+                    .insert(name.to_string(), value.to_string());
+            }
+            ("System Bus Policy", name) => {
+                permissions
+                    .system_bus
+                    .insert(name.to_string(), value.to_string());
+            }
+            _ => {}
         }
     }
 
-    Ok(InstalledPackagesResponse {
-        apps,
-        runtimes,
-        extensions,
-    })
+    parsed
 }
 
 #[tauri::command]
-async fn get_install_dependencies(
-    app: tauri::AppHandle,
-    app_id: String,
-) -> Result<Vec<Dependency>, String> {
+async fn get_app_remote_metadata(app: tauri::AppHandle, app_id: String) -> Result<String, StoreError> {
     let shell = app.shell();
 
     // Detect if we're running inside a flatpak
     let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
 
-    // First phase: Quick check with echo n (flatpak doesn't wait for input, just aborts)
     let output = if is_flatpak {
+        // Inside flatpak, use flatpak-spawn to execute on the host
         shell
-            .command("sh")
+            .command("flatpak-spawn")
             .args([
-                "-c",
-                &format!(
-                    "LANG=C echo n | flatpak-spawn --host flatpak install --user flathub {}",
-                    app_id
-                ),
+                "--host",
+                "flatpak",
+                "remote-info",
+                "--user",
+                "--show-metadata",
+                "flathub",
+                &app_id,
             ])
             .output()
             .await
-            .map_err(|e| format!("Failed to execute flatpak-spawn: {}", e))?
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
     } else {
+        // Outside flatpak, use flatpak directly
         shell
-            .command("sh")
+            .command("flatpak")
             .args([
-                "-c",
-                &format!("LANG=C echo n | flatpak install --user flathub {}", app_id),
+                "remote-info",
+                "--user",
+                "--show-metadata",
+                "flathub",
+                &app_id,
             ])
             .output()
             .await
-            .map_err(|e| format!("Failed to execute flatpak: {}", e))?
-    };
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    let combined_first = format!("{}\n{}", stdout, stderr);
-
-    // Check if we got "Required runtime" message
-    let needs_runtime = combined_first.contains("Required runtime for");
-
-    // Second phase: If runtime is required, use controlled process with script
-    let (stdout, stderr) = if needs_runtime {
-        let cmd_str = build_flatpak_dependency_check_cmd(is_flatpak, &app_id);
-        let (cmd, args) = ("sh", vec!["-c", &cmd_str]);
-
-        let mut child = Command::new(cmd)
-            .args(&args)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| format!("Failed to spawn process: {}", e))?;
-
-        // Read stdout and stderr looking for the dependency list
-        use std::sync::{Arc, Mutex};
-        let output_lines = Arc::new(Mutex::new(Vec::<String>::new()));
-        let error_lines = Arc::new(Mutex::new(Vec::<String>::new()));
-        let found_list = Arc::new(Mutex::new(false));
-
-        // Clone for threads
-        let error_clone_err = Arc::clone(&error_lines);
-        let output_clone_out = Arc::clone(&output_lines);
-        let found_clone_out = Arc::clone(&found_list);
-
-        // Read stderr in thread
-        let stderr_handle = child.stderr.take().ok_or("Failed to get stderr")?;
-        let stderr_thread = std::thread::spawn(move || {
-            let reader = BufReader::new(stderr_handle);
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    error_clone_err.lock().unwrap().push(line);
-                }
-            }
-        });
-
-        // Read stdout in thread to avoid blocking on prompts without newlines
-        let stdout_handle = child.stdout.take().ok_or("Failed to get stdout")?;
-        let stdout_thread = std::thread::spawn(move || {
-            let reader = BufReader::new(stdout_handle);
-            let mut line_count_in_list = 0;
-
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    output_clone_out.lock().unwrap().push(line.clone());
-
-                    // Detect numbered dependency list entries
-                    let trimmed = line.trim();
-                    if trimmed.starts_with(|c: char| c.is_ascii_digit()) && trimmed.contains('.') {
-                        line_count_in_list += 1;
-                    }
-
-                    // Once we've seen at least 1 list entry, we have the data we need
-                    if line_count_in_list >= 1 {
-                        *found_clone_out.lock().unwrap() = true;
-                    }
-                }
-            }
-        });
-
-        // Wait with timeout for the list to be found or process to exit
-        let start = std::time::Instant::now();
-        let timeout = std::time::Duration::from_secs(7);
-        while start.elapsed() < timeout {
-            if *found_list.lock().unwrap() {
-                // Give it a short time to finish printing the whole list
-                std::thread::sleep(std::time::Duration::from_millis(500));
-                break;
-            }
-            // Check if process exited on its own
-            if let Ok(Some(_)) = child.try_wait() {
-                break;
-            }
-            std::thread::sleep(std::time::Duration::from_millis(100));
-        }
-
-        // Kill the process if still running - we have what we need or timed out
-        let _ = child.kill();
-        let _ = stdout_thread.join();
-        let _ = stderr_thread.join();
-
-        let out = output_lines.lock().unwrap().join("\n");
-        let err = error_lines.lock().unwrap().join("\n");
-
-        (out, err)
-    } else {
-        // No runtime needed, use the first phase output
-        (stdout.to_string(), stderr.to_string())
+            .map_err(|e| StoreError::spawn("flatpak", e))?
     };
 
-    // Flatpak outputs dependency info to both stdout and stderr
-    let combined_output = format!("{}\n{}", stdout, stderr);
-
-    let mut dependencies = Vec::new();
-    let mut app_main: Option<Dependency> = None;
-    let mut required_runtime: Option<String> = None;
-
-    for line in combined_output.lines() {
-        let trimmed = line.trim();
-
-        // Skip empty lines
-        if trimmed.is_empty() {
-            continue;
-        }
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
+    }
 
-        // Look for "Required runtime" format (when app isn't already downloaded)
-        // Format: "Required runtime for moe.clover.mm3d/x86_64/stable (runtime/org.kde.Platform/x86_64/5.15-23.08) found in remote flathub"
-        if line.contains("Required runtime for") {
-            if let Some(runtime_part) = line.split("(runtime/").nth(1) {
-                if let Some(runtime_name) = runtime_part.split(')').next() {
-                    required_runtime = Some(runtime_name.to_string());
-                }
-            }
-        }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.to_string())
+}
 
-        // Look for lines that start with a number followed by a dot
-        // Format can be tabs or multiple spaces (especially when using 'script')
-        if let Some(rest) = trimmed.strip_prefix(|c: char| c.is_ascii_digit()) {
-            if rest.starts_with('.') {
-                // Normalize line: replace non-breaking spaces and tabs with regular spaces
-                let normalized = line.replace('\u{a0}', " ").replace('\t', " ");
-
-                // Split by multiple spaces and filter out empty parts
-                let parts: Vec<&str> = normalized.split(' ').filter(|s| !s.is_empty()).collect();
-
-                // A valid dependency line usually has: index, ID, branch, op, remote, size
-                // Example: ["1.", "org.kde.Platform", "5.15-23.08", "i", "flathub", "<", "346,1", "MB"]
-                if parts.len() >= 5 {
-                    let name = parts[1].trim().to_string();
-
-                    // The size is usually at the end. We join the last few parts if they look like a size.
-                    // e.g., ["<", "346,1", "MB"] or ["2,2", "MB"]
-                    let mut size_parts = Vec::new();
-                    let mut found_size_start = false;
-
-                    for i in 4..parts.len() {
-                        let p = parts[i];
-                        if p == "<"
-                            || p.chars().next().unwrap_or(' ').is_ascii_digit()
-                            || p == "MB"
-                            || p == "GB"
-                            || p == "kB"
-                            || p == "B"
-                        {
-                            found_size_start = true;
-                            size_parts.push(p);
-                        } else if found_size_start {
-                            // If we already started finding size parts and find something else, stop
-                            break;
-                        }
-                    }
+// Same metadata as get_app_remote_metadata, parsed into runtime, permissions and extension points
+#[tauri::command]
+async fn get_app_remote_metadata_parsed(
+    app: tauri::AppHandle,
+    app_id: String,
+) -> Result<AppMetadata, StoreError> {
+    let metadata = get_app_remote_metadata(app, app_id).await?;
+    Ok(parse_app_metadata(&metadata))
+}
 
-                    let size_raw = size_parts.join(" ");
-                    let size_clean = size_raw
-                        .replace("<", "")
-                        .replace("(parcial)", "")
-                        .replace("(partial)", "")
-                        .trim()
-                        .to_string();
-
-                    if !name.is_empty() && !size_clean.is_empty() {
-                        let dep = Dependency {
-                            name: name.clone(),
-                            download_size: size_clean.clone(),
-                            installed_size: size_clean,
-                        };
-
-                        if name == app_id {
-                            app_main = Some(dep);
-                        } else {
-                            dependencies.push(dep);
-                        }
-                    }
-                }
-            }
-        }
+// Commits of an app still available on the remote, for rolling back a bad update.
+// NotFound when the remote keeps no history for it
+#[tauri::command]
+async fn get_app_commit_history(
+    app: tauri::AppHandle,
+    app_id: String,
+    remote: Option<String>,
+) -> Result<Vec<CommitEntry>, StoreError> {
+    if !is_valid_flatpak_id(&app_id) {
+        return Err(StoreError::InvalidInput("invalid app id".to_string()));
     }
+    let remote = resolve_remote(remote)?;
+    capabilities::require(&app, capabilities::Feature::Downgrade).await?;
 
-    // If we found a required runtime but no detailed list, create a fallback entry
-    if app_main.is_none() && required_runtime.is_some() {
-        // Add the app itself with unknown size
-        app_main = Some(Dependency {
-            name: app_id.clone(),
-            download_size: "Unknown".to_string(),
-            installed_size: "Unknown".to_string(),
-        });
+    let shell = app.shell();
 
-        // Add the runtime as a dependency
-        if let Some(runtime) = required_runtime {
-            dependencies.push(Dependency {
-                name: runtime,
-                download_size: "Unknown".to_string(),
-                installed_size: "Unknown".to_string(),
-            });
-        }
-    }
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
 
-    // Put app main as first element, then dependencies
-    let mut result = Vec::new();
-    if let Some(main_app) = app_main {
-        result.push(main_app);
+    // LANG=C keeps the field labels in English
+    let output = if is_flatpak {
+        shell
+            .command("flatpak-spawn")
+            .args([
+                "--host",
+                "--env=LANG=C",
+                "flatpak",
+                "remote-info",
+                "--log",
+                &remote,
+                &app_id,
+            ])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
+    } else {
+        shell
+            .command("flatpak")
+            .args(["remote-info", "--log", &remote, &app_id])
+            .env("LANG", "C")
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak", e))?
+    };
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
     }
-    result.extend(dependencies);
 
-    Ok(result)
+    let entries = parse_commit_log(&String::from_utf8_lossy(&output.stdout));
+    if entries.is_empty() {
+        log::info!(
+            "[get_app_commit_history] No commits of {} on remote {}",
+            app_id,
+            remote
+        );
+        return Err(StoreError::NotFound(app_id));
+    }
+    Ok(entries)
 }
 
+// Runtime, sdk and sizes of an app from the remote's metadata, without the
+// dependency probe; enough to warn before a large runtime gets pulled in
 #[tauri::command]
-async fn get_available_updates(app: tauri::AppHandle) -> Result<Vec<UpdateAvailable>, String> {
+async fn get_app_requirements(
+    app: tauri::AppHandle,
+    app_id: String,
+    remote: Option<String>,
+) -> Result<Requirements, StoreError> {
+    if !is_valid_flatpak_id(&app_id) {
+        return Err(StoreError::InvalidInput("invalid app id".to_string()));
+    }
+    let remote = resolve_remote(remote)?;
+
     let shell = app.shell();
 
     // Detect if we're running inside a flatpak
     let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
 
+    // LANG=C keeps the field labels in English
     let output = if is_flatpak {
-        // Inside flatpak, use flatpak-spawn to execute on the host
         shell
             .command("flatpak-spawn")
             .args([
                 "--host",
+                "--env=LANG=C",
                 "flatpak",
-                "remote-ls",
-                "--updates",
-                "--columns=application,version,branch",
+                "remote-info",
+                &remote,
+                &app_id,
             ])
             .output()
             .await
-            .map_err(|e| format!("Failed to execute flatpak-spawn: {}", e))?
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
     } else {
-        // Outside flatpak, use flatpak directly
         shell
             .command("flatpak")
-            .args([
-                "remote-ls",
-                "--updates",
-                "--columns=application,version,branch",
-            ])
+            .args(["remote-info", &remote, &app_id])
+            .env("LANG", "C")
             .output()
             .await
-            .map_err(|e| format!("Failed to execute flatpak: {}", e))?
+            .map_err(|e| StoreError::spawn("flatpak", e))?
     };
 
     if !output.status.success() {
         let error = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Flatpak command failed: {}", error));
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let updates: Vec<UpdateAvailable> = stdout
-        .lines()
-        .filter(|line| !line.trim().is_empty())
-        .filter_map(|line| {
-            let parts: Vec<&str> = line.split('\t').collect();
-            if parts.len() >= 3 {
-                Some(UpdateAvailable {
-                    app_id: parts[0].trim().to_string(),
-                    new_version: parts[1].trim().to_string(),
-                    branch: parts[2].trim().to_string(),
-                })
-            } else if parts.len() >= 2 {
-                // Sometimes version might be empty, branch in position 2
-                Some(UpdateAvailable {
-                    app_id: parts[0].trim().to_string(),
-                    new_version: String::new(),
-                    branch: parts.get(1).unwrap_or(&"stable").trim().to_string(),
-                })
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    Ok(updates)
+    Ok(parse_requirements(
+        &app_id,
+        &String::from_utf8_lossy(&output.stdout),
+    ))
 }
 
+// Roll an app back (or forward) to a specific commit from get_app_commit_history
 #[tauri::command]
-async fn update_flatpak(app: tauri::AppHandle, app_id: String) -> Result<(), String> {
-    app.emit(
-        "install-output",
-        format!("Iniciando actualización de {}...", app_id),
-    )
-    .map_err(|e| format!("Failed to emit: {}", e))?;
+async fn downgrade_app(
+    app: tauri::AppHandle,
+    app_id: String,
+    commit: String,
+) -> Result<(), StoreError> {
+    if !is_valid_flatpak_id(&app_id) {
+        return Err(StoreError::InvalidInput("invalid app id".to_string()));
+    }
+    if commit.is_empty() || !commit.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(StoreError::InvalidInput(format!(
+            "Invalid commit: {}",
+            commit
+        )));
+    }
+    capabilities::require(&app, capabilities::Feature::Downgrade).await?;
+    let commit_arg = format!("--commit={}", commit);
 
     let shell = app.shell();
 
     // Detect if we're running inside a flatpak
     let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
 
-    let (mut rx, _child) = if is_flatpak {
+    let (rx, child) = if is_flatpak {
         // Inside flatpak, use flatpak-spawn to execute on the host
         shell
             .command("flatpak-spawn")
-            .args(["--host", "flatpak", "update", "-y", &app_id])
+            .args(["--host", "flatpak", "update", "-y", &commit_arg, &app_id])
             .spawn()
-            .map_err(|e| format!("Failed to spawn flatpak-spawn: {}", e))?
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
     } else {
         // Outside flatpak, use flatpak directly
         shell
             .command("flatpak")
-            .args(["update", "-y", &app_id])
+            .args(["update", "-y", &commit_arg, &app_id])
             .spawn()
-            .map_err(|e| format!("Failed to spawn flatpak: {}", e))?
+            .map_err(|e| StoreError::spawn("flatpak", e))?
     };
+    track_shell_child(&app, &app_id, child);
 
-    // Read output in real-time
-    while let Some(event) = rx.recv().await {
-        match event {
-            tauri_plugin_shell::process::CommandEvent::Stdout(line) => {
-                let output = String::from_utf8_lossy(&line);
-                app.emit("install-output", output.to_string())
-                    .map_err(|e| format!("Failed to emit event: {}", e))?;
-            }
-            tauri_plugin_shell::process::CommandEvent::Stderr(line) => {
-                // Flatpak sends progress output to stderr
-                let output = String::from_utf8_lossy(&line);
-                app.emit("install-output", output.to_string())
-                    .map_err(|e| format!("Failed to emit event: {}", e))?;
-            }
-            tauri_plugin_shell::process::CommandEvent::Error(err) => {
-                app.emit("install-error", err)
-                    .map_err(|e| format!("Failed to emit error: {}", e))?;
-            }
-            tauri_plugin_shell::process::CommandEvent::Terminated(payload) => {
-                app.emit("install-completed", payload.code.unwrap_or(-1))
-                    .map_err(|e| format!("Failed to emit completion: {}", e))?;
-                break;
-            }
-            _ => {}
-        }
+    forward_command_events(&app, rx, Some(&app_id), Some(&app_id)).await?;
+    Ok(())
+}
+
+// Helper function to pick the branches of `app_id` out of
+// `flatpak remote-ls --app --columns=application,branch`
+fn parse_remote_branches(output: &str, app_id: &str) -> Vec<String> {
+    let mut branches: Vec<String> = output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split('\t').map(str::trim);
+            let id = parts.next()?;
+            let branch = parts.next().filter(|b| !b.is_empty())?;
+            (id == app_id).then(|| branch.to_string())
+        })
+        .collect();
+    branches.dedup();
+    branches
+}
+
+// Move an app to another branch (e.g. stable -> beta). An installed branch is updated,
+// otherwise it's installed; either way it becomes the one `flatpak run` starts
+#[tauri::command]
+async fn switch_branch(
+    app: tauri::AppHandle,
+    app_id: String,
+    branch: String,
+    remote: Option<String>,
+    scope: Option<InstallScope>,
+    max_parallel_downloads: Option<u32>,
+) -> Result<(), StoreError> {
+    if !is_valid_flatpak_id(&app_id) {
+        return Err(StoreError::InvalidInput("invalid app id".to_string()));
+    }
+    // Branch names use the same characters as app ids
+    if !is_valid_flatpak_id(&branch) {
+        return Err(StoreError::InvalidInput(format!(
+            "Invalid branch: {}",
+            branch
+        )));
+    }
+    let remote = resolve_remote(remote)?;
+    let scope = scope.unwrap_or_default();
+
+    let shell = app.shell();
+
+    // Detect if we're running inside a flatpak
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+
+    // remote-info only describes a single ref, remote-ls lists every branch
+    let list_args = [
+        "remote-ls",
+        "--app",
+        "--columns=application,branch",
+        &remote,
+    ];
+    let output = if is_flatpak {
+        shell
+            .command("flatpak-spawn")
+            .args(["--host", "flatpak"])
+            .args(list_args)
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
+    } else {
+        shell
+            .command("flatpak")
+            .args(list_args)
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak", e))?
+    };
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
+    }
+
+    let branches = parse_remote_branches(&String::from_utf8_lossy(&output.stdout), &app_id);
+    if !branches.contains(&branch) {
+        let available = if branches.is_empty() {
+            "none".to_string()
+        } else {
+            branches.join(", ")
+        };
+        return Err(StoreError::InvalidInput(format!(
+            "{} has no branch {} on {} (available: {})",
+            app_id, branch, remote, available
+        )));
+    }
+
+    let target = format!("{}//{}", app_id, branch);
+    let args = if is_app_installed(&app, &target).await {
+        vec!["update", "-y", scope.flag(), target.as_str()]
+    } else {
+        vec![
+            "install",
+            "-y",
+            scope.flag(),
+            remote.as_str(),
+            target.as_str(),
+        ]
+    };
+
+    let _slot = acquire_download_slot(&app, max_parallel_downloads).await;
+    // Inside flatpak this goes through flatpak-spawn, system scope adds pkexec
+    let (program, args) = flatpak_command_parts(is_flatpak, scope, &args);
+    let (rx, _child) = shell
+        .command(program)
+        .args(args)
+        .spawn()
+        .map_err(|e| StoreError::spawn(program, e))?;
+
+    let code = forward_command_events(&app, rx, Some(&app_id), None).await?;
+    mark_installed_changed(&app);
+    if code != Some(0) {
+        return Err(StoreError::CommandFailed(
+            code.unwrap_or(-1),
+            format!("Failed to switch {} to {}", app_id, branch),
+        ));
+    }
+
+    // Both branches stay installed, point `flatpak run` at the new one
+    let (program, args) = flatpak_command_parts(
+        is_flatpak,
+        scope,
+        &["make-current", scope.flag(), &app_id, &branch],
+    );
+    let output = shell
+        .command(program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| StoreError::spawn(program, e))?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
     }
 
+    log::info!("[switch_branch] {} is now on {}", app_id, branch);
     Ok(())
 }
 
+// Helper function to list the arches of `app_id` in
+// `flatpak remote-ls --arch=* --columns=application,arch`, the host arch first
+fn parse_app_arches(output: &str, app_id: &str, default_arch: &str) -> Vec<String> {
+    let mut arches: Vec<String> = Vec::new();
+    for line in output.lines() {
+        let mut parts = line.split('\t').map(str::trim);
+        let (Some(id), Some(arch)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        if id == app_id && !arch.is_empty() && !arches.iter().any(|a| a == arch) {
+            arches.push(arch.to_string());
+        }
+    }
+    if let Some(index) = arches.iter().position(|a| a == default_arch) {
+        let host = arches.remove(index);
+        arches.insert(0, host);
+    }
+    arches
+}
+
+// Architectures `remote` publishes the app for, so ARM users aren't stuck with x86_64
+// The host's default arch comes first when the app is built for it
 #[tauri::command]
-async fn update_system_flatpaks(app: tauri::AppHandle) -> Result<(), String> {
-    app.emit(
-        "install-output",
-        "Iniciando actualización de paquetes del sistema...",
-    )
-    .map_err(|e| format!("Failed to emit: {}", e))?;
+async fn get_app_arches(
+    app: tauri::AppHandle,
+    app_id: String,
+    remote: Option<String>,
+) -> Result<Vec<String>, StoreError> {
+    if !is_valid_flatpak_id(&app_id) {
+        return Err(StoreError::InvalidInput("invalid app id".to_string()));
+    }
+    let remote = resolve_remote(remote)?;
 
     let shell = app.shell();
 
     // Detect if we're running inside a flatpak
     let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
 
-    let (mut rx, _child) = if is_flatpak {
+    let output = if is_flatpak {
         // Inside flatpak, use flatpak-spawn to execute on the host
         shell
             .command("flatpak-spawn")
-            .args(["--host", "flatpak", "update", "-y"])
-            .spawn()
-            .map_err(|e| format!("Failed to spawn flatpak-spawn: {}", e))?
+            .args(["--host", "flatpak", "--default-arch"])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
     } else {
         // Outside flatpak, use flatpak directly
         shell
             .command("flatpak")
-            .args(["update", "-y"])
-            .spawn()
-            .map_err(|e| format!("Failed to spawn flatpak: {}", e))?
+            .args(["--default-arch"])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak", e))?
     };
 
-    // Read output in real-time
-    while let Some(event) = rx.recv().await {
-        match event {
-            tauri_plugin_shell::process::CommandEvent::Stdout(line) => {
-                let output = String::from_utf8_lossy(&line);
-                app.emit("install-output", output.to_string())
-                    .map_err(|e| format!("Failed to emit event: {}", e))?;
-            }
-            tauri_plugin_shell::process::CommandEvent::Stderr(line) => {
-                // Flatpak sends progress output to stderr
-                let output = String::from_utf8_lossy(&line);
-                app.emit("install-output", output.to_string())
-                    .map_err(|e| format!("Failed to emit event: {}", e))?;
-            }
-            tauri_plugin_shell::process::CommandEvent::Error(err) => {
-                app.emit("install-error", err)
-                    .map_err(|e| format!("Failed to emit error: {}", e))?;
-            }
-            tauri_plugin_shell::process::CommandEvent::Terminated(payload) => {
-                app.emit("install-completed", payload.code.unwrap_or(-1))
-                    .map_err(|e| format!("Failed to emit completion: {}", e))?;
-                break;
-            }
-            _ => {}
-        }
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
+    }
+    let default_arch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    // remote-info only describes one arch at a time, remote-ls can list them all
+    let list_args = [
+        "remote-ls",
+        "--app",
+        "--arch=*",
+        "--columns=application,arch",
+        &remote,
+    ];
+    let output = if is_flatpak {
+        shell
+            .command("flatpak-spawn")
+            .args(["--host", "flatpak"])
+            .args(list_args)
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
+    } else {
+        shell
+            .command("flatpak")
+            .args(list_args)
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak", e))?
+    };
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
     }
 
-    Ok(())
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(parse_app_arches(&stdout, &app_id, &default_arch))
 }
 
 #[tauri::command]
-async fn launch_flatpak(app_id: String) -> Result<(), String> {
+async fn get_app_permissions(
+    app: tauri::AppHandle,
+    app_id: String,
+) -> Result<AppPermissions, StoreError> {
+    let shell = app.shell();
+
     // Detect if we're running inside a flatpak
     let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
 
     let output = if is_flatpak {
         // Inside flatpak, use flatpak-spawn to execute on the host
-        Command::new("flatpak-spawn")
-            .args(["--host", "flatpak", "run", &app_id])
+        shell
+            .command("flatpak-spawn")
+            .args(["--host", "flatpak", "info", "--show-permissions", &app_id])
             .output()
-            .map_err(|e| format!("Failed to launch app: {}", e))?
+            .await
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
     } else {
         // Outside flatpak, use flatpak directly
-        Command::new("flatpak")
-            .args(["run", &app_id])
+        shell
+            .command("flatpak")
+            .args(["info", "--show-permissions", &app_id])
             .output()
-            .map_err(|e| format!("Failed to launch app: {}", e))?
+            .await
+            .map_err(|e| StoreError::spawn("flatpak", e))?
     };
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Failed to launch app: {}", stderr));
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
     }
 
-    Ok(())
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(parse_app_permissions(&stdout))
 }
 
-#[tauri::command]
-async fn uninstall_flatpak(app: tauri::AppHandle, app_id: String) -> Result<(), String> {
-    app.emit(
-        "install-output",
-        format!("Iniciando desinstalación de {}...", app_id),
-    )
-    .map_err(|e| format!("Failed to emit: {}", e))?;
-
+// `flatpak override` options the frontend is allowed to set, without the leading "--"
+const ALLOWED_OVERRIDE_KEYS: &[&str] = &[
+    "filesystem",
+    "nofilesystem",
+    "socket",
+    "nosocket",
+    "device",
+    "nodevice",
+    "share",
+    "unshare",
+    "talk-name",
+    "no-talk-name",
+    "own-name",
+    "system-talk-name",
+    "system-no-talk-name",
+    "persist",
+];
+
+// Helper function to run `flatpak override --user <args> <app_id>` and report failures through install-error
+async fn run_flatpak_override(
+    app: &tauri::AppHandle,
+    app_id: &str,
+    override_arg: &str,
+) -> Result<(), StoreError> {
     let shell = app.shell();
 
     // Detect if we're running inside a flatpak
     let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
 
-    let (mut rx, _child) = if is_flatpak {
+    let output = if is_flatpak {
         // Inside flatpak, use flatpak-spawn to execute on the host
         shell
             .command("flatpak-spawn")
-            .args(["--host", "flatpak", "uninstall", "-y", &app_id])
-            .spawn()
-            .map_err(|e| format!("Failed to spawn flatpak-spawn: {}", e))?
+            .args(["--host", "flatpak", "override", "--user", override_arg, app_id])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
     } else {
         // Outside flatpak, use flatpak directly
         shell
             .command("flatpak")
-            .args(["uninstall", "-y", &app_id])
-            .spawn()
-            .map_err(|e| format!("Failed to spawn flatpak: {}", e))?
+            .args(["override", "--user", override_arg, app_id])
+            .output()
+            .await
+            .map_err(|e| StoreError::spawn("flatpak", e))?
     };
 
-    // Read output in real-time
-    while let Some(event) = rx.recv().await {
-        match event {
-            tauri_plugin_shell::process::CommandEvent::Stdout(line) => {
-                let output = String::from_utf8_lossy(&line);
-                app.emit("install-output", output.to_string())
-                    .map_err(|e| format!("Failed to emit event: {}", e))?;
-            }
-            tauri_plugin_shell::process::CommandEvent::Stderr(line) => {
-                // Flatpak sends progress output to stderr
-                let output = String::from_utf8_lossy(&line);
-                app.emit("install-output", output.to_string())
-                    .map_err(|e| format!("Failed to emit event: {}", e))?;
-            }
-            tauri_plugin_shell::process::CommandEvent::Error(err) => {
-                app.emit("install-error", err)
-                    .map_err(|e| format!("Failed to emit error: {}", e))?;
-            }
-            tauri_plugin_shell::process::CommandEvent::Terminated(payload) => {
-                app.emit("install-completed", payload.code.unwrap_or(-1))
-                    .map_err(|e| format!("Failed to emit completion: {}", e))?;
-                break;
-            }
-            _ => {}
-        }
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr).to_string();
+        let _ = app.emit("install-error", error.clone());
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
     }
 
     Ok(())
 }
 
 #[tauri::command]
-async fn get_app_remote_metadata(app: tauri::AppHandle, app_id: String) -> Result<String, String> {
+async fn set_permission_override(
+    app: tauri::AppHandle,
+    app_id: String,
+    override_key: String,
+    value: String,
+) -> Result<(), StoreError> {
+    if !is_valid_flatpak_id(&app_id) {
+        return Err(StoreError::InvalidInput("invalid app id".to_string()));
+    }
+
+    let key = override_key.trim().trim_start_matches("--");
+    if !ALLOWED_OVERRIDE_KEYS.contains(&key) {
+        return Err(StoreError::InvalidInput(format!(
+            "Unsupported override: {}",
+            override_key
+        )));
+    }
+
+    let value = value.trim();
+    if value.is_empty() || value.contains('\n') {
+        return Err(StoreError::InvalidInput("Invalid override value".to_string()));
+    }
+
+    // e.g. --nofilesystem=home or --filesystem=~/Downloads
+    let override_arg = format!("--{}={}", key, value);
+    run_flatpak_override(&app, &app_id, &override_arg).await
+}
+
+#[tauri::command]
+async fn reset_permission_overrides(app: tauri::AppHandle, app_id: String) -> Result<(), StoreError> {
+    if !is_valid_flatpak_id(&app_id) {
+        return Err(StoreError::InvalidInput("invalid app id".to_string()));
+    }
+
+    run_flatpak_override(&app, &app_id, "--reset").await
+}
+
+// Helper function to run `flatpak mask --user` with extra args, returning stdout
+// Masked apps are skipped by `flatpak update`, so update_system_flatpaks respects them
+async fn run_flatpak_mask(app: &tauri::AppHandle, args: &[&str]) -> Result<String, StoreError> {
+    capabilities::require(app, capabilities::Feature::Mask).await?;
+
     let shell = app.shell();
 
     // Detect if we're running inside a flatpak
@@ -1457,41 +5450,66 @@ async fn get_app_remote_metadata(app: tauri::AppHandle, app_id: String) -> Resul
         // Inside flatpak, use flatpak-spawn to execute on the host
         shell
             .command("flatpak-spawn")
-            .args([
-                "--host",
-                "flatpak",
-                "remote-info",
-                "--user",
-                "--show-metadata",
-                "flathub",
-                &app_id,
-            ])
+            .args(["--host", "flatpak", "mask", "--user"])
+            .args(args)
             .output()
             .await
-            .map_err(|e| format!("Failed to execute flatpak-spawn: {}", e))?
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
     } else {
         // Outside flatpak, use flatpak directly
         shell
             .command("flatpak")
-            .args([
-                "remote-info",
-                "--user",
-                "--show-metadata",
-                "flathub",
-                &app_id,
-            ])
+            .args(["mask", "--user"])
+            .args(args)
             .output()
             .await
-            .map_err(|e| format!("Failed to execute flatpak: {}", e))?
+            .map_err(|e| StoreError::spawn("flatpak", e))?
     };
 
     if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Flatpak command failed: {}", error));
+        let error = String::from_utf8_lossy(&output.stderr).to_string();
+        return Err(StoreError::flatpak_failed(output.status.code(), &error));
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    Ok(stdout.to_string())
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// Helper function to parse the pattern list printed by `flatpak mask`
+// Output: a "Masked patterns:" header followed by one indented pattern per line
+fn parse_masked_patterns(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.ends_with(':') && !line.contains(' '))
+        .map(String::from)
+        .collect()
+}
+
+// Hold an app at its current version
+#[tauri::command]
+async fn mask_app(app: tauri::AppHandle, app_id: String) -> Result<(), StoreError> {
+    if !is_valid_flatpak_id(&app_id) {
+        return Err(StoreError::InvalidInput("invalid app id".to_string()));
+    }
+
+    run_flatpak_mask(&app, &[&app_id]).await?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn unmask_app(app: tauri::AppHandle, app_id: String) -> Result<(), StoreError> {
+    if !is_valid_flatpak_id(&app_id) {
+        return Err(StoreError::InvalidInput("invalid app id".to_string()));
+    }
+
+    run_flatpak_mask(&app, &["--remove", &app_id]).await?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_masked_apps(app: tauri::AppHandle) -> Result<Vec<String>, StoreError> {
+    let output = run_flatpak_mask(&app, &[]).await?;
+    Ok(parse_masked_patterns(&output))
 }
 
 #[derive(serde::Serialize)]
@@ -1505,44 +5523,37 @@ struct InstallableExtension {
 async fn get_installable_extensions(
     app: tauri::AppHandle,
     app_id: String,
-) -> Result<Vec<InstallableExtension>, String> {
+) -> Result<Vec<InstallableExtension>, StoreError> {
     let shell = app.shell();
     let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
 
     // First, get the metadata to find extension points
     let metadata = get_app_remote_metadata(app.clone(), app_id.clone()).await?;
 
-    // Parse extension points from metadata
+    // Keep only the extension points that belong to this app
     let mut extension_points = Vec::new();
-    for line in metadata.lines() {
-        if let Some(captures) = line
-            .strip_prefix("[Extension ")
-            .and_then(|s| s.strip_suffix("]"))
-        {
-            let extension_point = captures.trim().to_string();
-
-            // Use a blacklist approach: exclude system/platform extensions
-            // Only accept extensions that belong to this app's domain
-            let belongs_to_app = extension_point.starts_with(&app_id);
-
-            // Check if it's a system extension by looking at the first segment after app_id
-            // Example: io.github.peazip.PeaZip.Debug -> first segment is "Debug" (exclude)
-            // Example: io.github.peazip.PeaZip.Addon.i386 -> first segment is "Addon" (allow)
-            let is_system_extension = if belongs_to_app && extension_point.len() > app_id.len() {
-                let suffix = &extension_point[app_id.len()..];
-                // Get the first segment after app_id (e.g., ".Debug" or ".Addon")
-                let first_segment = suffix.split('.').nth(1).unwrap_or("");
-
-                first_segment == "Debug" || first_segment == "Locale" || first_segment == "Help"
-            } else {
-                // Platform extensions from freedesktop
-                extension_point.contains("org.freedesktop.Platform.")
-                    || extension_point.contains("org.freedesktop.Sdk.")
-            };
+    for extension_point in parse_app_metadata(&metadata).extension_points {
+        // Use a blacklist approach: exclude system/platform extensions
+        // Only accept extensions that belong to this app's domain
+        let belongs_to_app = extension_point.starts_with(&app_id);
+
+        // Check if it's a system extension by looking at the first segment after app_id
+        // Example: io.github.peazip.PeaZip.Debug -> first segment is "Debug" (exclude)
+        // Example: io.github.peazip.PeaZip.Addon.i386 -> first segment is "Addon" (allow)
+        let is_system_extension = if belongs_to_app && extension_point.len() > app_id.len() {
+            let suffix = &extension_point[app_id.len()..];
+            // Get the first segment after app_id (e.g., ".Debug" or ".Addon")
+            let first_segment = suffix.split('.').nth(1).unwrap_or("");
+
+            first_segment == "Debug" || first_segment == "Locale" || first_segment == "Help"
+        } else {
+            // Platform extensions from freedesktop
+            extension_point.contains("org.freedesktop.Platform.")
+                || extension_point.contains("org.freedesktop.Sdk.")
+        };
 
-            if belongs_to_app && !is_system_extension {
-                extension_points.push(extension_point);
-            }
+        if belongs_to_app && !is_system_extension {
+            extension_points.push(extension_point);
         }
     }
 
@@ -1564,14 +5575,14 @@ async fn get_installable_extensions(
                 ])
                 .output()
                 .await
-                .map_err(|e| format!("Failed to execute flatpak-spawn: {}", e))?
+                .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
         } else {
             shell
                 .command("flatpak")
                 .args(["search", "--columns=application,name", &extension_point])
                 .output()
                 .await
-                .map_err(|e| format!("Failed to execute flatpak: {}", e))?
+                .map_err(|e| StoreError::spawn("flatpak", e))?
         };
 
         if output.status.success() {
@@ -1614,41 +5625,38 @@ async fn get_installable_extensions(
 }
 
 #[tauri::command]
-async fn install_extension(app: tauri::AppHandle, extension_id: String) -> Result<(), String> {
-    app.emit(
-        "install-output",
-        format!("Installing extension {}...", extension_id),
-    )
-    .map_err(|e| format!("Failed to emit: {}", e))?;
+async fn install_extension(
+    app: tauri::AppHandle,
+    extension_id: String,
+    remote: Option<String>,
+    scope: Option<InstallScope>,
+    max_parallel_downloads: Option<u32>,
+) -> Result<(), StoreError> {
+    if !is_valid_flatpak_id(&extension_id) {
+        return Err(StoreError::InvalidInput("invalid extension id".to_string()));
+    }
+    let remote = resolve_remote(remote)?;
+    let scope = scope.unwrap_or_default();
+
+    status::emit_status(&app, status::EXTENSION_INSTALL_START, Some(&extension_id))?;
+    let _slot = acquire_download_slot(&app, max_parallel_downloads).await;
 
     let shell = app.shell();
 
     // Detect if we're running inside a flatpak
     let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
 
-    let (mut rx, _child) = if is_flatpak {
-        // Inside flatpak, use flatpak-spawn to execute on the host
-        shell
-            .command("flatpak-spawn")
-            .args([
-                "--host",
-                "flatpak",
-                "install",
-                "-y",
-                "--user",
-                "flathub",
-                &extension_id,
-            ])
-            .spawn()
-            .map_err(|e| format!("Failed to spawn flatpak-spawn: {}", e))?
-    } else {
-        // Outside flatpak, use flatpak directly
-        shell
-            .command("flatpak")
-            .args(["install", "-y", "--user", "flathub", &extension_id])
-            .spawn()
-            .map_err(|e| format!("Failed to spawn flatpak: {}", e))?
-    };
+    // Inside flatpak this goes through flatpak-spawn, system scope adds pkexec
+    let (program, args) = flatpak_command_parts(
+        is_flatpak,
+        scope,
+        &["install", "-y", scope.flag(), &remote, &extension_id],
+    );
+    let (mut rx, _child) = shell
+        .command(program)
+        .args(args)
+        .spawn()
+        .map_err(|e| StoreError::spawn(program, e))?;
 
     // Read output in real-time
     while let Some(event) = rx.recv().await {
@@ -1657,19 +5665,26 @@ async fn install_extension(app: tauri::AppHandle, extension_id: String) -> Resul
                 let output = String::from_utf8_lossy(&line);
                 app.emit("install-output", output.to_string())
                     .map_err(|e| format!("Failed to emit event: {}", e))?;
+                emit_install_progress(&app, Some(&extension_id), &output);
             }
             tauri_plugin_shell::process::CommandEvent::Stderr(line) => {
                 let output = String::from_utf8_lossy(&line);
                 app.emit("install-output", output.to_string())
                     .map_err(|e| format!("Failed to emit event: {}", e))?;
+                emit_install_progress(&app, Some(&extension_id), &output);
             }
             tauri_plugin_shell::process::CommandEvent::Error(err) => {
                 app.emit("install-error", err)
                     .map_err(|e| format!("Failed to emit error: {}", e))?;
             }
             tauri_plugin_shell::process::CommandEvent::Terminated(payload) => {
-                app.emit("install-completed", payload.code.unwrap_or(-1))
+                let success = payload.code == Some(0);
+                history::record(&app, &extension_id, "install", None, success).await;
+                let code = payload.code.unwrap_or(-1);
+                app.emit("install-completed", code)
                     .map_err(|e| format!("Failed to emit completion: {}", e))?;
+                emit_operation_completed(&app, &extension_id, "install", code);
+                notifications::notify_completion(&app, &extension_id, "install", success);
                 break;
             }
             _ => {}
@@ -1680,12 +5695,11 @@ async fn install_extension(app: tauri::AppHandle, extension_id: String) -> Resul
 }
 
 #[tauri::command]
-async fn uninstall_extension(app: tauri::AppHandle, extension_id: String) -> Result<(), String> {
-    app.emit(
-        "install-output",
-        format!("Uninstalling extension {}...", extension_id),
-    )
-    .map_err(|e| format!("Failed to emit: {}", e))?;
+async fn uninstall_extension(app: tauri::AppHandle, extension_id: String) -> Result<(), StoreError> {
+    if !is_valid_flatpak_id(&extension_id) {
+        return Err(StoreError::InvalidInput("invalid extension id".to_string()));
+    }
+    status::emit_status(&app, status::EXTENSION_UNINSTALL_START, Some(&extension_id))?;
 
     let shell = app.shell();
 
@@ -1698,14 +5712,14 @@ async fn uninstall_extension(app: tauri::AppHandle, extension_id: String) -> Res
             .command("flatpak-spawn")
             .args(["--host", "flatpak", "uninstall", "-y", &extension_id])
             .spawn()
-            .map_err(|e| format!("Failed to spawn flatpak-spawn: {}", e))?
+            .map_err(|e| StoreError::spawn("flatpak-spawn", e))?
     } else {
         // Outside flatpak, use flatpak directly
         shell
             .command("flatpak")
             .args(["uninstall", "-y", &extension_id])
             .spawn()
-            .map_err(|e| format!("Failed to spawn flatpak: {}", e))?
+            .map_err(|e| StoreError::spawn("flatpak", e))?
     };
 
     // Read output in real-time
@@ -1726,8 +5740,10 @@ async fn uninstall_extension(app: tauri::AppHandle, extension_id: String) -> Res
                     .map_err(|e| format!("Failed to emit error: {}", e))?;
             }
             tauri_plugin_shell::process::CommandEvent::Terminated(payload) => {
-                app.emit("install-completed", payload.code.unwrap_or(-1))
+                let code = payload.code.unwrap_or(-1);
+                app.emit("install-completed", code)
                     .map_err(|e| format!("Failed to emit completion: {}", e))?;
+                emit_operation_completed(&app, &extension_id, "uninstall", code);
                 break;
             }
             _ => {}
@@ -1739,18 +5755,38 @@ async fn uninstall_extension(app: tauri::AppHandle, extension_id: String) -> Res
 
 // Start an interactive PTY process for flatpak install (check dependencies + optional install)
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn start_flatpak_interactive(
     app: tauri::AppHandle,
     processes: State<'_, ProcessMap>,
     app_id: String,
-) -> Result<(), String> {
-    eprintln!(
+    remote: Option<String>,
+    scope: Option<InstallScope>,
+    max_parallel_downloads: Option<u32>,
+    idle_timeout_secs: Option<u64>,
+    force: Option<bool>,
+    arch: Option<String>,
+) -> Result<(), StoreError> {
+    log::info!(
         "[start_flatpak_interactive] Starting for app_id: {}",
         app_id
     );
+    let remote = resolve_remote(remote)?;
+    let arch_flag = resolve_arch_flag(arch)?;
+    // Fail before waiting for a download slot; checked again below when storing the process
+    let force = force.unwrap_or(false);
+    clear_pty_slot(&mut processes.lock().unwrap(), &app_id, force)?;
+    // Held by the monitor thread below until the install exits
+    let slot = acquire_download_slot(&app, max_parallel_downloads).await;
     let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
-    let cmd_str = build_flatpak_interactive_cmd(is_flatpak, &app_id);
-    eprintln!("[start_flatpak_interactive] Command: {}", cmd_str);
+    let cmd_str = build_flatpak_interactive_cmd(
+        is_flatpak,
+        &app_id,
+        &remote,
+        scope.unwrap_or_default(),
+        arch_flag.as_deref(),
+    )?;
+    log::debug!("[start_flatpak_interactive] Command: {}", cmd_str);
 
     let mut child = Command::new("sh")
         .args(["-c", &cmd_str])
@@ -1764,40 +5800,72 @@ async fn start_flatpak_interactive(
     let stdout = child.stdout.take().ok_or("Failed to get stdout")?;
     let stderr = child.stderr.take().ok_or("Failed to get stderr")?;
 
-    eprintln!("[start_flatpak_interactive] Process spawned successfully");
+    log::info!("[start_flatpak_interactive] Process spawned successfully");
 
-    // Store the process
+    // Store the process, unless another install of the app started in the meantime
+    let pid = child.id();
     {
         let mut map = processes.lock().unwrap();
+        if let Err(e) = clear_pty_slot(&mut map, &app_id, force) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(e);
+        }
         map.insert(app_id.clone(), PtyProcess { child, stdin });
-        eprintln!("[start_flatpak_interactive] Process stored in map");
+        log::debug!("[start_flatpak_interactive] Process stored in map");
     }
 
     // Read stdout in background thread - read byte by byte to capture \r updates
-    let app_clone = app.clone();
-    let app_id_clone = app_id.clone();
+    // The time of the last output lets the monitor below kill installs stuck on a prompt
+    let last_output = Arc::new(Mutex::new(Instant::now()));
+    let last_output_clone = last_output.clone();
+    let (chunk_tx, chunk_rx) = std::sync::mpsc::channel::<String>();
     std::thread::spawn(move || {
         use std::io::Read;
         let mut buffer = [0u8; 1024];
+        let mut carry = Vec::new();
         let mut stdout_reader = stdout;
 
         loop {
             match stdout_reader.read(&mut buffer) {
                 Ok(0) => break, // EOF
                 Ok(n) => {
-                    let chunk = String::from_utf8_lossy(&buffer[..n]).to_string();
-                    // Split by \n but preserve \r to allow frontend to handle line overwrites
-                    for line in chunk.split('\n') {
-                        if !line.is_empty() {
-                            let _ = app_clone
-                                .emit("pty-output", (app_id_clone.clone(), line.to_string()));
-                        }
+                    *last_output_clone.lock().unwrap() = Instant::now();
+                    let chunk = decode_pty_chunk(&mut carry, &buffer[..n]);
+                    if chunk_tx.send(chunk).is_err() {
+                        break;
                     }
                 }
                 Err(e) => {
-                    eprintln!("[start_flatpak_interactive] Error reading stdout: {}", e);
+                    log::error!("[start_flatpak_interactive] Error reading stdout: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+
+    // Emit stdout as coalesced lines so \r progress updates don't flood the event bridge
+    let app_clone = app.clone();
+    let app_id_clone = app_id.clone();
+    std::thread::spawn(move || {
+        use std::sync::mpsc::RecvTimeoutError;
+        let mut coalescer = PtyLineCoalescer::new(PTY_OUTPUT_THROTTLE);
+        let mut choices = PtyChoiceCollector::default();
+
+        loop {
+            let lines = match chunk_rx.recv_timeout(PTY_OUTPUT_THROTTLE) {
+                Ok(chunk) => coalescer.push(&chunk),
+                // Output paused: show the unfinished line, it may be a prompt
+                Err(RecvTimeoutError::Timeout) => coalescer.flush().into_iter().collect(),
+                Err(RecvTimeoutError::Disconnected) => {
+                    if let Some(line) = coalescer.flush() {
+                        emit_pty_line(&app_clone, &app_id_clone, line, &mut choices);
+                    }
                     break;
                 }
+            };
+            for line in lines {
+                emit_pty_line(&app_clone, &app_id_clone, line, &mut choices);
             }
         }
     });
@@ -1818,35 +5886,73 @@ async fn start_flatpak_interactive(
     let app_clone3 = app.clone();
     let app_id_clone3 = app_id.clone();
     let processes_clone = processes.inner().clone();
+    // No timeout unless asked for: a user may legitimately leave a prompt open
+    let idle_timeout = idle_timeout_secs.map(Duration::from_secs);
     std::thread::spawn(move || {
+        let _slot = slot;
         // Poll the process status every 500ms
         loop {
             std::thread::sleep(std::time::Duration::from_millis(500));
 
             let mut map = processes_clone.lock().unwrap();
-            if let Some(pty_process) = map.get_mut(&app_id_clone3) {
+            // A forced restart replaces the entry; that process has its own monitor
+            let ours = map
+                .get_mut(&app_id_clone3)
+                .filter(|pty_process| pty_process.child.id() == pid);
+            if let Some(pty_process) = ours {
                 match pty_process.child.try_wait() {
                     Ok(Some(status)) => {
-                        eprintln!(
+                        log::info!(
                             "[start_flatpak_interactive] Process terminated with status: {:?}",
                             status
                         );
                         // Process has exited, emit event and remove from map
-                        let _ = app_clone3.emit("pty-terminated", app_id_clone3.clone());
+                        emit_pty_terminated(&app_clone3, &app_id_clone3, Some(status), false);
+                        let code = status.code().unwrap_or(-1);
+                        emit_operation_completed(&app_clone3, &app_id_clone3, "install", code);
+                        notifications::notify_completion(
+                            &app_clone3,
+                            &app_id_clone3,
+                            "install",
+                            status.success(),
+                        );
                         map.remove(&app_id_clone3);
                         break;
                     }
                     Ok(None) => {
-                        // Still running, continue
+                        // Still running, kill it if it has gone quiet for too long
+                        let idle = last_output.lock().unwrap().elapsed();
+                        if idle_timeout.is_some_and(|timeout| idle >= timeout) {
+                            log::warn!(
+                                "[start_flatpak_interactive] No output for {}s, killing {}",
+                                idle.as_secs(),
+                                app_id_clone3
+                            );
+                            let _ = pty_process.child.kill();
+                            let status = pty_process.child.wait().ok();
+                            emit_pty_terminated(&app_clone3, &app_id_clone3, status, true);
+                            emit_operation_completed(&app_clone3, &app_id_clone3, "install", -1);
+                            notifications::notify_completion(
+                                &app_clone3,
+                                &app_id_clone3,
+                                "install",
+                                false,
+                            );
+                            map.remove(&app_id_clone3);
+                            break;
+                        }
                     }
                     Err(e) => {
-                        eprintln!("[start_flatpak_interactive] Error checking process: {}", e);
+                        log::error!("[start_flatpak_interactive] Error checking process: {}", e);
+                        // Still report the end so the UI doesn't wait forever
+                        emit_pty_terminated(&app_clone3, &app_id_clone3, None, false);
+                        emit_operation_completed(&app_clone3, &app_id_clone3, "install", -1);
                         map.remove(&app_id_clone3);
                         break;
                     }
                 }
             } else {
-                // Process was removed externally
+                // Process was removed or replaced externally
                 break;
             }
         }
@@ -1892,14 +5998,15 @@ struct LocalFlatpakDep {
 }
 
 #[tauri::command]
-async fn download_flatpak_release(github_repo: String, app_id: String) -> Result<String, String> {
+async fn download_flatpak_release(
+    http: State<'_, http::HttpClient>,
+    github_repo: String,
+    app_id: String,
+) -> Result<String, StoreError> {
     // Resolve latest release from GitHub API
     let api_url = format!("https://api.github.com/repos/{}/releases/latest", github_repo);
 
-    let client = reqwest::Client::builder()
-        .user_agent("klia-store")
-        .build()
-        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let client = http.client();
 
     let release: serde_json::Value = {
         let text = client
@@ -1936,7 +6043,7 @@ async fn download_flatpak_release(github_repo: String, app_id: String) -> Result
 
     let dest = std::env::temp_dir().join(filename);
 
-    eprintln!(
+    log::info!(
         "[download_flatpak_release] Downloading {} for {}",
         flatpak_url, app_id
     );
@@ -1948,7 +6055,7 @@ async fn download_flatpak_release(github_repo: String, app_id: String) -> Result
         .map_err(|e| format!("Download failed: {}", e))?;
 
     if !response.status().is_success() {
-        return Err(format!("HTTP {}: {}", response.status(), flatpak_url));
+        return Err(StoreError::HttpError(response.status().as_u16()));
     }
 
     let bytes = response
@@ -1958,7 +6065,7 @@ async fn download_flatpak_release(github_repo: String, app_id: String) -> Result
 
     fs::write(&dest, &bytes).map_err(|e| format!("Failed to write file: {}", e))?;
 
-    eprintln!(
+    log::info!(
         "[download_flatpak_release] Saved {} ({} bytes)",
         filename,
         bytes.len()
@@ -1966,7 +6073,7 @@ async fn download_flatpak_release(github_repo: String, app_id: String) -> Result
 
     dest.to_str()
         .map(String::from)
-        .ok_or_else(|| "Invalid path".to_string())
+        .ok_or_else(|| StoreError::Internal("Invalid path".to_string()))
 }
 
 #[derive(Serialize)]
@@ -1985,14 +6092,12 @@ async fn check_github_updates(
     app: tauri::AppHandle,
     // List of (app_id, github_repo) pairs to check, e.g. [["io.github.N3kosempai.klia-kompress", "N3koSempai/klia-kompress"]]
     apps: Vec<(String, String)>,
-) -> Result<Vec<GitHubUpdateInfo>, String> {
+    http: State<'_, http::HttpClient>,
+) -> Result<Vec<GitHubUpdateInfo>, StoreError> {
     let shell = app.shell();
     let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
 
-    let client = reqwest::Client::builder()
-        .user_agent("klia-store")
-        .build()
-        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let client = http.client();
 
     let mut results = Vec::new();
 
@@ -2065,7 +6170,7 @@ async fn check_github_updates(
 async fn inspect_local_flatpak(
     app: tauri::AppHandle,
     file_path: String,
-) -> Result<LocalFlatpakInfo, String> {
+) -> Result<LocalFlatpakInfo, StoreError> {
     let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
 
     // Get file size
@@ -2241,21 +6346,8 @@ async fn inspect_local_flatpak(
         .collect();
 
     // Check if already installed
-    let check_cmd = if is_flatpak {
-        format!(
-            "flatpak-spawn --host flatpak info {} 2>/dev/null",
-            app_id
-        )
-    } else {
-        format!("flatpak info {} 2>/dev/null", app_id)
-    };
-    let already_installed = shell
-        .command("sh")
-        .args(["-c", &check_cmd])
-        .output()
-        .await
-        .map(|o| o.status.success())
-        .unwrap_or(false);
+    // The app_id comes from the bundle itself, so it is validated and passed as a plain argument
+    let already_installed = is_valid_flatpak_id(&app_id) && is_app_installed(&app, &app_id).await;
 
     let name = if app_id.is_empty() {
         std::path::Path::new(&file_path)
@@ -2282,30 +6374,56 @@ async fn inspect_local_flatpak(
     })
 }
 
-#[tauri::command]
-async fn install_local_flatpak(
-    app: tauri::AppHandle,
-    processes: State<'_, ProcessMap>,
-    file_path: String,
-) -> Result<(), String> {
-    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+// Runtime named in flatpak's "requires the runtime <ref> which was not found" error
+fn missing_runtime(output: &str) -> Option<&str> {
+    let (_, rest) = output.split_once("requires the runtime ")?;
+    rest.split_whitespace()
+        .next()
+        .filter(|_| rest.contains("which was not found"))
+}
 
-    let cmd_str = if is_flatpak {
-        format!(
-            "LANG=C script -q /dev/null -c \"flatpak-spawn --host flatpak install -y --user {:?}\"",
-            file_path
-        )
+// Quote a value for a POSIX shell command line: wrapped in single quotes, with any
+// single quote inside written as '\''
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+// Command line `script` runs for a local install; the path is a single shell word
+fn local_install_command(is_flatpak: bool, assume_yes: bool, file_path: &str) -> String {
+    let prefix = if is_flatpak {
+        "flatpak-spawn --host "
     } else {
-        format!(
-            "LANG=C script -q /dev/null -c \"flatpak install -y --user {:?}\"",
-            file_path
-        )
+        ""
     };
+    let yes = if assume_yes { " -y" } else { "" };
+    format!(
+        "{}flatpak install{} --user {}",
+        prefix,
+        yes,
+        shell_quote(file_path)
+    )
+}
 
-    let process_key = format!("local::{}", file_path);
+// Install a local .flatpak/.flatpakref file in a PTY. Output goes out as pty-output
+// keyed "local::<source>"; without `assume_yes` flatpak's confirmation prompt is
+// answered through send_to_pty. `temp_file` is deleted and `slot` released once flatpak exits.
+fn spawn_local_install(
+    app: &tauri::AppHandle,
+    processes: &ProcessMap,
+    file_path: &str,
+    source: &str,
+    assume_yes: bool,
+    temp_file: Option<std::path::PathBuf>,
+    slot: DownloadSlot,
+) -> Result<(), StoreError> {
+    let is_flatpak = std::env::var("FLATPAK_ID").is_ok();
+    let cmd_str = local_install_command(is_flatpak, assume_yes, file_path);
 
-    let mut child = Command::new("sh")
-        .args(["-c", &cmd_str])
+    let process_key = format!("local::{}", source);
+
+    let mut child = Command::new("script")
+        .args(["-q", "/dev/null", "-c", &cmd_str])
+        .env("LANG", "C")
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -2326,15 +6444,29 @@ async fn install_local_flatpak(
     std::thread::spawn(move || {
         use std::io::Read;
         let mut buffer = [0u8; 1024];
+        let mut carry = Vec::new();
+        let mut choices = PtyChoiceCollector::default();
         let mut reader = stdout;
         loop {
             match reader.read(&mut buffer) {
                 Ok(0) => break,
                 Ok(n) => {
-                    let chunk = String::from_utf8_lossy(&buffer[..n]).to_string();
+                    let chunk = strip_ansi(&decode_pty_chunk(&mut carry, &buffer[..n]));
                     for line in chunk.split('\n') {
                         if !line.is_empty() {
-                            let _ = app_clone.emit("pty-output", (key_clone.clone(), line.to_string()));
+                            // flatpak's own message doesn't say what to do about it
+                            if let Some(runtime) = missing_runtime(line) {
+                                let _ = app_clone.emit(
+                                    "install-error",
+                                    format!(
+                                        "The bundle needs the runtime {}, which is not installed \
+                                         and not available from any configured remote. Add the \
+                                         remote that provides it (usually Flathub) and try again.",
+                                        runtime
+                                    ),
+                                );
+                            }
+                            emit_pty_line(&app_clone, &key_clone, line.to_string(), &mut choices);
                         }
                     }
                 }
@@ -2343,78 +6475,224 @@ async fn install_local_flatpak(
         }
     });
 
-    let app_clone2 = app.clone();
-    let key_clone2 = process_key.clone();
-    std::thread::spawn(move || {
-        let reader = BufReader::new(stderr);
-        for line in reader.lines() {
-            if let Ok(line) = line {
-                let _ = app_clone2.emit("pty-error", (key_clone2.clone(), line));
-            }
-        }
-    });
+    let app_clone2 = app.clone();
+    let key_clone2 = process_key.clone();
+    std::thread::spawn(move || {
+        let reader = BufReader::new(stderr);
+        for line in reader.lines() {
+            if let Ok(line) = line {
+                let _ = app_clone2.emit("pty-error", (key_clone2.clone(), line));
+            }
+        }
+    });
+
+    let app_clone3 = app.clone();
+    let key_clone3 = process_key.clone();
+    let processes_clone = processes.clone();
+    std::thread::spawn(move || {
+        let _slot = slot;
+        loop {
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            let mut map = processes_clone.lock().unwrap();
+            if let Some(pty_process) = map.get_mut(&key_clone3) {
+                match pty_process.child.try_wait() {
+                    Ok(Some(status)) => {
+                        emit_pty_terminated(&app_clone3, &key_clone3, Some(status), false);
+                        map.remove(&key_clone3);
+                        break;
+                    }
+                    Ok(None) => {}
+                    Err(_) => {
+                        emit_pty_terminated(&app_clone3, &key_clone3, None, false);
+                        map.remove(&key_clone3);
+                        break;
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+        if let Some(temp_file) = temp_file {
+            let _ = fs::remove_file(temp_file);
+        }
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn install_local_flatpak(
+    app: tauri::AppHandle,
+    processes: State<'_, ProcessMap>,
+    file_path: String,
+    max_parallel_downloads: Option<u32>,
+) -> Result<(), StoreError> {
+    let slot = acquire_download_slot(&app, max_parallel_downloads).await;
+    spawn_local_install(&app, &processes, &file_path, &file_path, true, None, slot)
+}
+
+// Check a file picked for a local install
+fn check_local_install_path(path: &str, extension: &str) -> Result<(), StoreError> {
+    if !path.ends_with(extension) {
+        return Err(StoreError::InvalidInput(format!(
+            "Not a {} file: {}",
+            extension, path
+        )));
+    }
+    if !std::path::Path::new(path).is_file() {
+        return Err(StoreError::InvalidInput(format!(
+            "File not found: {}",
+            path
+        )));
+    }
+    Ok(())
+}
+
+/// Install a .flatpak bundle picked by the user. Unlike `install_local_flatpak`
+/// flatpak asks for confirmation, which the frontend answers with send_to_pty.
+#[tauri::command]
+async fn install_bundle(
+    app: tauri::AppHandle,
+    processes: State<'_, ProcessMap>,
+    path: String,
+    max_parallel_downloads: Option<u32>,
+) -> Result<(), StoreError> {
+    check_local_install_path(&path, ".flatpak")?;
+    log::info!("[install_bundle] Installing {}", path);
+    let slot = acquire_download_slot(&app, max_parallel_downloads).await;
+    spawn_local_install(&app, &processes, &path, &path, false, None, slot)
+}
+
+// Fetch a .flatpakref over https into the temp dir, named after the URL hash
+async fn download_flatpakref(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<std::path::PathBuf, StoreError> {
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| StoreError::request("Failed to download flatpakref", e))?;
+    if !response.status().is_success() {
+        return Err(StoreError::HttpError(response.status().as_u16()));
+    }
+    let text = response
+        .text()
+        .await
+        .map_err(|e| StoreError::request("Failed to read flatpakref", e))?;
+    if !text.contains("[Flatpak Ref]") {
+        return Err(StoreError::InvalidInput(format!(
+            "Not a flatpakref: {}",
+            url
+        )));
+    }
+
+    let hash = xxhash_rust::xxh3::xxh3_64(url.as_bytes());
+    let dest = std::env::temp_dir().join(format!("klia-{:x}.flatpakref", hash));
+    write_atomic(&dest, text.as_bytes())
+        .map_err(|e| format!("Failed to write flatpakref: {}", e))?;
+    Ok(dest)
+}
+
+/// Install from a .flatpakref given as a local path or an https:// URL, as handed
+/// out by flathub's install buttons. pty-output is keyed "local::<ref_source>".
+#[tauri::command]
+async fn install_flatpakref(
+    app: tauri::AppHandle,
+    http: State<'_, http::HttpClient>,
+    processes: State<'_, ProcessMap>,
+    ref_source: String,
+    max_parallel_downloads: Option<u32>,
+) -> Result<(), StoreError> {
+    if ref_source.starts_with("https://") {
+        let file = download_flatpakref(&http.client(), &ref_source).await?;
+        let file_path = file.to_string_lossy().to_string();
+        log::info!(
+            "[install_flatpakref] Downloaded {} to {}",
+            ref_source,
+            file_path
+        );
+        let slot = acquire_download_slot(&app, max_parallel_downloads).await;
+        spawn_local_install(
+            &app,
+            &processes,
+            &file_path,
+            &ref_source,
+            false,
+            Some(file),
+            slot,
+        )
+    } else if ref_source.contains("://") {
+        Err(StoreError::InvalidInput(format!(
+            "Only https:// flatpakref URLs are supported: {}",
+            ref_source
+        )))
+    } else {
+        check_local_install_path(&ref_source, ".flatpakref")?;
+        let slot = acquire_download_slot(&app, max_parallel_downloads).await;
+        spawn_local_install(
+            &app,
+            &processes,
+            &ref_source,
+            &ref_source,
+            false,
+            None,
+            slot,
+        )
+    }
+}
 
-    let app_clone3 = app.clone();
-    let key_clone3 = process_key.clone();
-    let processes_clone = processes.inner().clone();
-    std::thread::spawn(move || {
-        loop {
-            std::thread::sleep(std::time::Duration::from_millis(500));
-            let mut map = processes_clone.lock().unwrap();
-            if let Some(pty_process) = map.get_mut(&key_clone3) {
-                match pty_process.child.try_wait() {
-                    Ok(Some(_status)) => {
-                        let _ = app_clone3.emit("pty-terminated", key_clone3.clone());
-                        map.remove(&key_clone3);
-                        break;
-                    }
-                    Ok(None) => {}
-                    Err(_) => {
-                        map.remove(&key_clone3);
-                        break;
-                    }
-                }
-            } else {
-                break;
-            }
-        }
-    });
+// Helper function to write bytes to a running PTY process's stdin
+fn write_to_pty(processes: &ProcessMap, app_id: &str, bytes: &[u8]) -> Result<(), StoreError> {
+    let mut map = processes.lock().unwrap();
 
+    let Some(pty_process) = map.get_mut(app_id) else {
+        log::error!("[send_to_pty] No process found for app_id: {}", app_id);
+        return Err(StoreError::Internal(format!(
+            "No process found for app_id: {}",
+            app_id
+        )));
+    };
+    log::debug!("[send_to_pty] Process found, writing to stdin");
+    pty_process
+        .stdin
+        .write_all(bytes)
+        .map_err(|e| format!("Failed to write to stdin: {}", e))?;
+    pty_process
+        .stdin
+        .flush()
+        .map_err(|e| format!("Failed to flush stdin: {}", e))?;
+    log::debug!("[send_to_pty] Successfully sent input");
     Ok(())
 }
 
-// Send input to a running PTY process
+// Send a line of input to a running PTY process
 #[tauri::command]
 async fn send_to_pty(
     processes: State<'_, ProcessMap>,
     app_id: String,
     input: String,
-) -> Result<(), String> {
-    eprintln!(
+) -> Result<(), StoreError> {
+    log::debug!(
         "[send_to_pty] Attempting to send '{}' to app_id: {}",
         input, app_id
     );
-    let mut map = processes.lock().unwrap();
+    write_to_pty(&processes, &app_id, format!("{}\n", input).as_bytes())
+}
 
-    if let Some(pty_process) = map.get_mut(&app_id) {
-        eprintln!("[send_to_pty] Process found, writing to stdin");
-        pty_process
-            .stdin
-            .write_all(format!("{}\n", input).as_bytes())
-            .map_err(|e| format!("Failed to write to stdin: {}", e))?;
-        pty_process
-            .stdin
-            .flush()
-            .map_err(|e| format!("Failed to flush stdin: {}", e))?;
-        eprintln!("[send_to_pty] Successfully sent input");
-        Ok(())
-    } else {
-        eprintln!(
-            "[send_to_pty] ERROR: No process found for app_id: {}",
-            app_id
-        );
-        Err(format!("No process found for app_id: {}", app_id))
-    }
+// Send exactly these bytes, no newline, for prompts that read a single keystroke
+#[tauri::command]
+async fn send_to_pty_raw(
+    processes: State<'_, ProcessMap>,
+    app_id: String,
+    bytes: Vec<u8>,
+) -> Result<(), StoreError> {
+    log::debug!(
+        "[send_to_pty_raw] Attempting to send {} byte(s) to app_id: {}",
+        bytes.len(),
+        app_id
+    );
+    write_to_pty(&processes, &app_id, &bytes)
 }
 
 // Kill a PTY process
@@ -2423,16 +6701,129 @@ async fn kill_pty_process(
     app: tauri::AppHandle,
     processes: State<'_, ProcessMap>,
     app_id: String,
-) -> Result<(), String> {
+) -> Result<(), StoreError> {
     let mut map = processes.lock().unwrap();
 
     if let Some(mut pty_process) = map.remove(&app_id) {
         let _ = pty_process.child.kill();
-        let _ = pty_process.child.wait();
-        let _ = app.emit("pty-terminated", app_id);
+        let status = pty_process.child.wait().ok();
+        emit_pty_terminated(&app, &app_id, status, false);
         Ok(())
     } else {
-        Err(format!("No process found for app_id: {}", app_id))
+        Err(StoreError::Internal(format!(
+            "No process found for app_id: {}",
+            app_id
+        )))
+    }
+}
+
+// Abort a running update/uninstall started through the shell plugin.
+// Use "system" as app_id to cancel `update_system_flatpaks`.
+#[tauri::command]
+async fn cancel_operation(
+    app: tauri::AppHandle,
+    children: State<'_, ShellChildMap>,
+    app_id: String,
+) -> Result<(), StoreError> {
+    terminate_shell_child(&children, &app_id)?;
+    log::info!("[cancel_operation] Cancelled operation for {}", app_id);
+
+    app.emit("install-completed", CANCELLED_EXIT_CODE)
+        .map_err(|e| format!("Failed to emit completion: {}", e))?;
+    emit_operation_completed(&app, &app_id, "cancel", CANCELLED_EXIT_CODE);
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct CancelReport {
+    queued: usize,
+    installs: usize,
+    operations: usize,
+}
+
+// Stop everything at once: pending queue entries, PTY installs and the updates/uninstalls
+// running through the shell plugin. Each one gets its usual events so the UI resets
+#[tauri::command]
+async fn cancel_all_operations(
+    app: tauri::AppHandle,
+    processes: State<'_, ProcessMap>,
+    children: State<'_, ShellChildMap>,
+) -> Result<CancelReport, StoreError> {
+    // Pending entries first, or the queue would start the next one as soon as this ends
+    let queued = queue::clear_pending(&app).await;
+
+    let ptys: Vec<(String, PtyProcess)> = processes.lock().unwrap().drain().collect();
+    let installs = ptys.len();
+    for (app_id, mut pty_process) in ptys {
+        let _ = pty_process.child.kill();
+        let status = pty_process.child.wait().ok();
+        emit_pty_terminated(&app, &app_id, status, false);
+        emit_operation_completed(&app, &app_id, "cancel", CANCELLED_EXIT_CODE);
+    }
+
+    // Untracking the children makes the commands skip their own completion events
+    let running: Vec<String> = children.lock().unwrap().keys().cloned().collect();
+    let mut operations = 0;
+    for app_id in running {
+        if let Err(e) = terminate_shell_child(&children, &app_id) {
+            log::warn!("[cancel_all_operations] Failed to cancel {}: {}", app_id, e);
+            continue;
+        }
+        operations += 1;
+        let _ = app.emit("install-completed", CANCELLED_EXIT_CODE);
+        emit_operation_completed(&app, &app_id, "cancel", CANCELLED_EXIT_CODE);
+    }
+
+    log::info!(
+        "[cancel_all_operations] Cancelled {} queued, {} installs, {} operations",
+        queued,
+        installs,
+        operations
+    );
+    Ok(CancelReport {
+        queued,
+        installs,
+        operations,
+    })
+}
+
+// Make room in the map for a new PTY process under `key`. An exited process is dropped;
+// a live one is killed when `force` is set and otherwise reported as an error.
+fn clear_pty_slot(
+    map: &mut HashMap<String, PtyProcess>,
+    key: &str,
+    force: bool,
+) -> Result<(), StoreError> {
+    let Some(pty_process) = map.get_mut(key) else {
+        return Ok(());
+    };
+    if matches!(pty_process.child.try_wait(), Ok(None)) {
+        if !force {
+            return Err(StoreError::InvalidInput(format!(
+                "An install of {} is already in progress",
+                key
+            )));
+        }
+        log::info!("[pty] Killing previous process for {}", key);
+        let _ = pty_process.child.kill();
+        let _ = pty_process.child.wait();
+    }
+    map.remove(key);
+    Ok(())
+}
+
+// Kill and reap every PTY child so no installer outlives the store window
+fn kill_all_pty_processes(processes: &ProcessMap) {
+    let mut map = processes.lock().unwrap();
+    let count = map.len();
+    for (app_id, mut pty_process) in map.drain() {
+        if let Err(e) = pty_process.child.kill() {
+            log::warn!("[exit] Failed to kill PTY process for {}: {}", app_id, e);
+        }
+        let _ = pty_process.child.wait();
+    }
+    if count > 0 {
+        log::info!("[exit] Cleaned up {} PTY process(es)", count);
     }
 }
 
@@ -2441,7 +6832,7 @@ async fn kill_pty_process(
 async fn check_pty_process(
     processes: State<'_, ProcessMap>,
     app_id: String,
-) -> Result<bool, String> {
+) -> Result<bool, StoreError> {
     let mut map = processes.lock().unwrap();
     if let Some(pty_process) = map.get_mut(&app_id) {
         match pty_process.child.try_wait() {
@@ -3222,14 +7613,14 @@ async fn verify_gitlab_release(
 }
 
 #[tauri::command]
-async fn verify_app_hash(app_id: String) -> Result<VerificationResult, String> {
+async fn verify_app_hash(
+    http: State<'_, http::HttpClient>,
+    app_id: String,
+) -> Result<VerificationResult, StoreError> {
     println!("[verify_app_hash] Starting hash verification for: {}", app_id);
 
-    // Creamos un único cliente HTTP para todas las operaciones
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    // Un único cliente HTTP (el compartido) para todas las operaciones
+    let client = http.client();
 
     // Fetch the manifest from flathub (reusando el cliente)
     let manifest_content = match fetch_manifest_from_flathub(&client, &app_id).await {
@@ -3626,14 +8017,54 @@ fn find_main_module(modules: &[serde_yaml::Value], app_id: &str) -> Option<Flatp
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // An appstream:// link for an already open store is handed to that instance
+    if deeplink::forward_to_running_instance() {
+        return;
+    }
+
     tauri::Builder::default()
         .manage(ProcessMap::default())
+        .manage(DependencyCache::default())
+        .manage(InstalledCache::default())
+        .manage(DependencyCheckMap::default())
+        .manage(ShellChildMap::default())
+        .manage(DownloadSlotsState::default())
+        .manage(http::HttpClient::default())
+        .manage(ImageDownloadLocks::default())
+        .manage(ImageCacheDir::default())
+        .manage(history::HistoryDb::default())
+        .manage(queue::OperationQueue::default())
+        .manage(scheduler::UpdateScheduler::default())
+        .manage(flathub::VerificationCache::default())
+        .manage(flathub::CollectionCache::default())
+        .manage(flathub::RateLimiter::default())
+        .manage(flathub::LocalStateCache::default())
+        .manage(capabilities::CapabilitiesCache::default())
         .setup(|app| {
+            if let Err(e) = logging::init(app.handle()) {
+                eprintln!("[setup] Logging disabled: {}", e);
+            }
+
+            http::init(app.handle());
+            flathub::init(app.handle());
+            // A relocated image cache may be outside the asset protocol's static scope
+            if let Ok(cache_images_dir) = image_cache_dir(app.handle()) {
+                let _ = app
+                    .asset_protocol_scope()
+                    .allow_directory(cache_images_dir, true);
+            }
+            tauri::async_runtime::spawn(scheduler::run(app.handle().clone()));
+            if let Err(e) = tray::init(app.handle()) {
+                log::warn!("[setup] Tray icon disabled: {}", e);
+            }
+
+            deeplink::init(app.handle());
+
             // If the app was opened with a .flatpak or .flatpakref file as argument,
             // emit an event so the frontend can show the local install dialog.
             let args: Vec<String> = std::env::args().collect();
             if let Some(file_path) = args.get(1) {
-                let fp = file_path.clone();
+                let fp = deeplink::local_path_arg(file_path);
                 if fp.ends_with(".flatpak") || fp.ends_with(".flatpakref") {
                     let handle = app.handle().clone();
                     // Delay slightly so the frontend has time to mount listeners
@@ -3645,6 +8076,15 @@ pub fn run() {
             }
             Ok(())
         })
+        .on_window_event(|window, event| {
+            // With minimize_to_tray the close button hides the window, the tray brings it back
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                if tray::hides_on_close(window.app_handle()) {
+                    api.prevent_close();
+                    let _ = window.hide();
+                }
+            }
+        })
         .plugin(tauri_plugin_sql::Builder::new().build())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_opener::init())
@@ -3657,21 +8097,54 @@ pub fn run() {
             initialize_app,
             get_app_data_path,
             get_cache_image_dir,
+            set_cache_dir,
             clear_old_cache,
+            prune_image_cache,
+            verify_image_cache,
+            get_cache_stats,
             download_and_cache_image,
+            prefetch_images,
             get_cached_image_path,
             get_cached_image_filename,
             check_cached_image_exists,
             check_file_exists,
             get_installed_flatpaks,
+            get_apps_by_remote,
+            remove_flatpak_remote,
+            get_installed_app_info,
+            get_eol_packages,
             get_install_dependencies,
+            cancel_dependency_check,
             get_app_remote_metadata,
+            get_app_remote_metadata_parsed,
+            get_app_commit_history,
+            get_app_requirements,
+            downgrade_app,
+            switch_branch,
+            get_app_arches,
             get_installable_extensions,
             get_available_updates,
+            get_update_sizes,
+            get_uninstall_impact,
+            get_flatpak_remotes,
+            search_flatpak,
+            check_flatpak_available,
+            check_host_access,
+            capabilities::get_flatpak_capabilities,
+            add_flathub_remote,
+            install_flatpak_noninteractive,
             update_flatpak,
+            update_flatpaks,
             update_system_flatpaks,
+            get_unused_runtimes,
+            remove_unused_runtimes,
+            repair_flatpak,
+            update_appstream,
+            reset_app_data,
+            get_app_storage_breakdown,
             launch_flatpak,
             uninstall_flatpak,
+            uninstall_flatpaks,
             install_extension,
             uninstall_extension,
             start_flatpak_interactive,
@@ -3679,15 +8152,828 @@ pub fn run() {
             check_github_updates,
             inspect_local_flatpak,
             install_local_flatpak,
+            install_bundle,
+            install_flatpakref,
             send_to_pty,
+            send_to_pty_raw,
             kill_pty_process,
+            cancel_operation,
+            cancel_all_operations,
             check_pty_process,
             get_system_analytics,
+            check_disk_space,
+            get_total_flatpak_size,
             get_app_permissions_batch,
+            get_app_permissions,
+            set_permission_override,
+            reset_permission_overrides,
+            mask_app,
+            unmask_app,
+            get_masked_apps,
             verify_app_hash,
+            logging::get_log_path,
+            history::record_install_event,
+            history::get_install_history,
+            favorites::add_favorite,
+            favorites::remove_favorite,
+            favorites::get_favorites,
+            queue::enqueue_operation,
+            queue::get_queue,
+            scheduler::get_update_check_interval,
+            config::get_config,
+            config::update_config,
+            backup::export_installed_apps,
+            backup::import_app_manifest,
+            flathub::get_verification_status,
+            flathub::get_app_screenshots,
+            flathub::get_app_releases,
+            flathub::get_app_details,
+            flathub::get_apps_by_category,
+            flathub::get_popular_apps,
+            flathub::get_trending_apps,
+            flathub::search_apps,
+            http::set_proxy,
+            scheduler::set_update_check_interval,
+            tray::set_tray_badge,
             donations::verify_btc_donation,
             donations::verify_usdt_eth_donation
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                kill_all_pty_processes(&app_handle.state::<ProcessMap>());
+                deeplink::cleanup();
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_ansi_keeps_text_and_carriage_returns() {
+        let line = "\x1b[32mInstalling\x1b[0m 1/3\u{2026} \x1b[1;34m45%\x1b[0m\r\x1b[K";
+        assert_eq!(strip_ansi(line), "Installing 1/3\u{2026} 45%\r");
+        assert_eq!(strip_ansi("\x1b]0;flatpak\x07done"), "done");
+    }
+
+    #[test]
+    fn pty_coalescer_throttles_carriage_return_updates() {
+        let mut coalescer = PtyLineCoalescer::new(Duration::from_secs(60));
+
+        // The first partial line goes out right away, later rewrites wait for the throttle
+        assert_eq!(coalescer.push("Downloading 10%"), vec!["Downloading 10%"]);
+        assert!(coalescer.push("\rDownloading 20%").is_empty());
+        assert!(coalescer.push("\rDownloading 30%").is_empty());
+
+        // Finishing the line sends its final state as an overwrite
+        assert_eq!(
+            coalescer.push("\rDownloading 100%\nDone\n"),
+            vec!["\rDownloading 100%", "Done"]
+        );
+        assert_eq!(coalescer.flush(), None);
+    }
+
+    #[test]
+    fn pty_chunks_keep_split_utf8_characters() {
+        let text = format!("{}\u{2192} Installing\u{2026}", "a".repeat(1023));
+        let bytes = text.as_bytes();
+
+        let mut carry = Vec::new();
+        let mut decoded = decode_pty_chunk(&mut carry, &bytes[..1024]);
+        assert!(!carry.is_empty());
+        decoded.push_str(&decode_pty_chunk(&mut carry, &bytes[1024..]));
+
+        assert!(!decoded.contains('\u{FFFD}'));
+        assert_eq!(decoded, text);
+        assert!(carry.is_empty());
+    }
+
+    #[test]
+    fn developer_from_app_id() {
+        assert_eq!(
+            extract_developer("io.github.N3kosempai.klia-store").as_deref(),
+            Some("N3kosempai")
+        );
+        assert_eq!(
+            extract_developer("org.mozilla.firefox").as_deref(),
+            Some("mozilla")
+        );
+        assert_eq!(
+            extract_developer("com.github.tchx84.Flatseal").as_deref(),
+            Some("tchx84")
+        );
+        assert_eq!(extract_developer("io.Firefox"), None);
+        assert_eq!(
+            extract_developer("de.haeckerfelix.Fragments").as_deref(),
+            Some("haeckerfelix")
+        );
+    }
+
+    #[test]
+    fn app_metadata_groups() {
+        let metadata = "[Application]\n\
+                        name=org.mozilla.firefox\n\
+                        runtime=org.freedesktop.Platform/x86_64/23.08\n\
+                        sdk=org.freedesktop.Sdk/x86_64/23.08\n\
+                        command=firefox\n\
+                        \n\
+                        [Context]\n\
+                        shared=network;ipc;\n\
+                        sockets=x11;wayland;pulseaudio;\n\
+                        filesystems=xdg-download;/run/.heim_org.h5l.kcm-socket;\n\
+                        \n\
+                        [Session Bus Policy]\n\
+                        org.freedesktop.Notifications=talk\n\
+                        \n\
+                        [Extension org.mozilla.firefox.systemconfig]\n\
+                        directory=etc/firefox\n\
+                        \n\
+                        [Extension org.freedesktop.Platform.ffmpeg-full]\n\
+                        directory=lib/ffmpeg\n";
+        let parsed = parse_app_metadata(metadata);
+        assert_eq!(parsed.runtime, "org.freedesktop.Platform/x86_64/23.08");
+        assert_eq!(parsed.sdk, "org.freedesktop.Sdk/x86_64/23.08");
+        assert_eq!(parsed.command, "firefox");
+        assert_eq!(parsed.permissions.shared, ["network", "ipc"]);
+        assert_eq!(parsed.permissions.sockets, ["x11", "wayland", "pulseaudio"]);
+        assert_eq!(parsed.permissions.filesystems.len(), 2);
+        assert_eq!(
+            parsed.permissions.session_bus["org.freedesktop.Notifications"],
+            "talk"
+        );
+        assert!(parsed.permissions.devices.is_empty());
+        assert_eq!(
+            parsed.extension_points,
+            [
+                "org.mozilla.firefox.systemconfig",
+                "org.freedesktop.Platform.ffmpeg-full"
+            ]
+        );
+    }
+
+    #[test]
+    fn truncated_cached_images_are_detected() {
+        let png = b"\x89PNG\r\n\x1a\n....IEND\xAE\x42\x60\x82";
+        assert!(cached_image_is_intact("png", png));
+        assert!(!cached_image_is_intact("png", &png[..12]));
+        assert!(!cached_image_is_intact("png", b""));
+        // An HTML error page saved as an icon
+        assert!(!cached_image_is_intact("jpg", b"<html>Not found</html>"));
+
+        let mut webp = b"RIFF\x08\x00\x00\x00WEBPVP8 ".to_vec();
+        assert!(cached_image_is_intact("webp", &webp));
+        webp.truncate(10);
+        assert!(!cached_image_is_intact("webp", &webp));
+
+        assert!(cached_image_is_intact("svg", b"<svg></svg>\n"));
+        assert!(!cached_image_is_intact("svg", b"<svg><path d="));
+    }
+
+    #[test]
+    fn redirected_image_uses_final_url_extension() {
+        // icon.png -> 302 -> CDN .webp without a usable Content-Type
+        let final_url = "https://cdn.example.org/icons/128/app.webp?v=3";
+        assert_eq!(response_image_extension(None, final_url), "webp");
+        assert_eq!(
+            response_image_extension(Some("application/octet-stream"), final_url),
+            "webp"
+        );
+        // A real image Content-Type still wins over the URL
+        assert_eq!(
+            response_image_extension(Some("image/png; charset=binary"), final_url),
+            "png"
+        );
+    }
+
+    #[test]
+    fn image_retry_backoff_is_capped() {
+        assert_eq!(image_retry_backoff(1), Duration::from_millis(250));
+        assert_eq!(image_retry_backoff(3), Duration::from_millis(1000));
+        assert_eq!(image_retry_backoff(5), Duration::from_millis(2000));
+        assert_eq!(image_retry_backoff(u32::MAX), Duration::from_millis(2000));
+    }
+
+    #[test]
+    fn user_data_dir_stays_in_var_app() {
+        for app_id in ["..", "../.ssh", "org.example/..", ".hidden", "a..b", ""] {
+            assert!(app_user_data_dir(app_id).is_err(), "{}", app_id);
+        }
+        if std::env::var("HOME").is_ok() {
+            let dir = app_user_data_dir("org.example.App").unwrap();
+            assert!(dir.ends_with(".var/app/org.example.App"));
+        }
+    }
+
+    #[test]
+    fn missing_runtime_from_install_error() {
+        let line = "error: The application org.example.App/x86_64/stable requires the runtime \
+                    org.gnome.Platform/x86_64/46 which was not found";
+        assert_eq!(missing_runtime(line), Some("org.gnome.Platform/x86_64/46"));
+        assert_eq!(
+            missing_runtime("Installing org.gnome.Platform/x86_64/46"),
+            None
+        );
+    }
+
+    #[test]
+    fn directory_size_counts_files_not_symlink_targets() {
+        let dir = std::env::temp_dir().join(format!("klia-size-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("cache")).unwrap();
+        fs::write(dir.join("config.json"), [0u8; 100]).unwrap();
+        fs::write(dir.join("cache").join("blob"), [0u8; 1000]).unwrap();
+        std::os::unix::fs::symlink("/usr", dir.join("link")).unwrap();
+
+        let size = directory_size(&dir);
+        let link_size = fs::symlink_metadata(dir.join("link")).unwrap().len();
+        assert_eq!(size, 1100 + link_size);
+        assert_eq!(directory_size(&dir.join("missing")), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn search_results_list_every_remote() {
+        let output = "org.gimp.GIMP\tGIMP\tImage editor\t2.10.38\tstable\tflathub,flathub-beta\n\
+                      org.gimp.GIMP\tGIMP\tImage editor\t3.0.0\tbeta\tflathub-beta\n\
+                      No matches found\n";
+        let results = parse_search_results(output);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].remotes, ["flathub", "flathub-beta"]);
+        assert_eq!(results[1].branch, "beta");
+        assert_eq!(results[1].remotes, ["flathub-beta"]);
+    }
+
+    #[test]
+    fn available_updates_keep_branch_with_empty_version() {
+        let output = "org.example.App\t\tstable\norg.gimp.GIMP\t3.0.2\tbeta\n\n";
+        let updates = parse_available_updates(output);
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[0].app_id, "org.example.App");
+        assert_eq!(updates[0].new_version, "");
+        assert_eq!(updates[0].branch, "stable");
+        assert_eq!(updates[1].new_version, "3.0.2");
+        assert_eq!(updates[1].branch, "beta");
+    }
+
+    #[test]
+    fn dependency_size_markers() {
+        let runtime =
+            parse_dependency_line(" 1.\torg.kde.Platform\t5.15-23.08\ti\tflathub\t< 346,1 MB")
+                .unwrap();
+        assert_eq!(runtime.download_size, "346,1 MB");
+        assert!(runtime.approximate && !runtime.partial);
+
+        let english =
+            parse_dependency_line(" 2. org.gnome.Platform.Locale 46 u flathub < 18,2 kB (partial)")
+                .unwrap();
+        assert_eq!(english.download_size, "18,2 kB");
+        assert!(english.approximate && english.partial);
+
+        let spanish =
+            parse_dependency_line(" 3. org.gimp.GIMP.Locale stable i flathub 2,2 MB (parcial)")
+                .unwrap();
+        assert!(!spanish.approximate && spanish.partial);
+
+        let exact = parse_dependency_line(" 4. org.gimp.GIMP stable i flathub 120,5 MB").unwrap();
+        assert!(!exact.approximate && !exact.partial);
+        assert!(parse_dependency_line("Proceed with these changes? [Y/n]:").is_none());
+    }
+
+    #[test]
+    fn remote_branches_for_one_app() {
+        let output = "org.gimp.GIMP\tstable\norg.gimp.GIMP\tbeta\norg.inkscape.Inkscape\tstable\n";
+        assert_eq!(
+            parse_remote_branches(output, "org.gimp.GIMP"),
+            ["stable", "beta"]
+        );
+        assert!(parse_remote_branches(output, "org.kde.krita").is_empty());
+    }
+
+    #[test]
+    fn app_arches_put_the_host_first() {
+        let output = "org.gimp.GIMP\tx86_64\norg.gimp.GIMP\taarch64\norg.kde.krita\tx86_64\n";
+        assert_eq!(
+            parse_app_arches(output, "org.gimp.GIMP", "aarch64"),
+            ["aarch64", "x86_64"]
+        );
+        assert_eq!(
+            parse_app_arches(output, "org.kde.krita", "aarch64"),
+            ["x86_64"]
+        );
+    }
+
+    #[test]
+    fn flatpak_info_fields() {
+        let output = [
+            "",
+            "Firefox - Fast, Private & Safe Web Browser",
+            "",
+            "          ID: org.mozilla.firefox",
+            "         Ref: app/org.mozilla.firefox/x86_64/stable",
+            "      Branch: stable",
+            "     Version: 131.0",
+            "      Origin: flathub",
+            "Installation: system",
+            "   Installed: 254,1 MB",
+            "     Runtime: org.freedesktop.Platform/x86_64/24.08",
+            "",
+            "      Commit: 3b1f0c2a",
+            "     Subject: Update to 131.0",
+            " End-of-life: Replaced by org.mozilla.Firefox",
+            "End-of-life-rebase: org.mozilla.Firefox",
+        ]
+        .join("\n");
+
+        let info = parse_flatpak_info("org.mozilla.firefox", &output);
+        assert_eq!(info.name.as_deref(), Some("Firefox"));
+        assert_eq!(info.app_ref, "app/org.mozilla.firefox/x86_64/stable");
+        assert_eq!(info.branch.as_deref(), Some("stable"));
+        assert_eq!(info.origin.as_deref(), Some("flathub"));
+        assert_eq!(info.installation.as_deref(), Some("system"));
+        assert_eq!(info.installed_size, Some(254_100_000));
+        assert_eq!(info.commit.as_deref(), Some("3b1f0c2a"));
+        assert_eq!(info.eol.as_deref(), Some("Replaced by org.mozilla.Firefox"));
+        assert_eq!(info.eol_rebase.as_deref(), Some("org.mozilla.Firefox"));
+    }
+
+    #[test]
+    fn masked_patterns_skip_header() {
+        let output = "Masked patterns:\n  org.mozilla.firefox\n  org.gnome.*\n";
+        assert_eq!(
+            parse_masked_patterns(output),
+            vec!["org.mozilla.firefox", "org.gnome.*"]
+        );
+        assert!(parse_masked_patterns("No masked patterns\n").is_empty());
+    }
+
+    #[test]
+    fn commit_log_entries() {
+        let output = [
+            "        ID: org.mozilla.firefox",
+            "    Commit: 7a1c",
+            "   Subject: Update to 131.0",
+            "      Date: 2024-10-01 10:00:00 +0000",
+            "   History:",
+            "",
+            "    Commit: 5e2b",
+            "   Subject: Update to 130.0",
+            "      Date: 2024-09-03 09:30:00 +0000",
+        ]
+        .join("\n");
+
+        let entries = parse_commit_log(&output);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].commit, "7a1c");
+        assert_eq!(entries[1].subject, "Update to 130.0");
+        assert_eq!(entries[1].date, "2024-09-03 09:30:00 +0000");
+    }
+
+    #[test]
+    fn requirements_from_remote_info() {
+        let output = [
+            "GNU Image Manipulation Program - Create images and edit photographs",
+            "",
+            "          ID: org.gimp.GIMP",
+            "         Ref: app/org.gimp.GIMP/x86_64/stable",
+            "    Download: 143,6 MB",
+            "   Installed: 474,2 MB",
+            "     Runtime: org.gnome.Platform/x86_64/48",
+            "         Sdk: org.gnome.Sdk/x86_64/48",
+            "",
+            "      Commit: 7a1c",
+        ]
+        .join("\n");
+
+        let requirements = parse_requirements("org.gimp.GIMP", &output);
+        let runtime = requirements.runtime.unwrap();
+        assert_eq!(runtime.id, "org.gnome.Platform");
+        assert_eq!(runtime.branch, "48");
+        assert_eq!(requirements.sdk.unwrap().id, "org.gnome.Sdk");
+        assert!(requirements.download_bytes.unwrap() > 143_000_000);
+        assert!(requirements.installed_bytes.unwrap() > 474_000_000);
+
+        let bare = parse_requirements("org.example.App", "ID: org.example.App\n");
+        assert!(bare.runtime.is_none() && bare.download_bytes.is_none());
+    }
+
+    #[test]
+    fn extension_matches_its_parent_app() {
+        let mut app_ids = vec!["org.mozilla.firefox", "org.kde.kdenlive", "org.kde.krita"];
+        app_ids.sort_unstable();
+
+        assert_eq!(
+            find_parent_app_id(&app_ids, "org.kde.kdenlive.Codecs"),
+            Some("org.kde.kdenlive")
+        );
+        assert_eq!(find_parent_app_id(&app_ids, "org.kde.kdenlive"), None);
+        assert_eq!(
+            find_parent_app_id(&app_ids, "org.freedesktop.Platform.GL.default"),
+            None
+        );
+    }
+
+    #[test]
+    fn size_strings_with_locale_decimals() {
+        assert_eq!(parse_size_string("715,3 MB"), Some(715_300_000));
+        assert_eq!(parse_size_string("1.2 GB"), Some(1_200_000_000));
+        assert_eq!(parse_size_string("2,5 kB"), Some(2_500));
+        assert_eq!(parse_size_string("512 bytes"), Some(512));
+        assert_eq!(parse_size_string(""), None);
+        assert_eq!(parse_size_string("unknown"), None);
+    }
+
+    #[test]
+    fn transaction_table_download_sizes() {
+        let output = [
+            "Looking for updates…",
+            "        ID                    Branch  Op  Remote   Download",
+            " 1.     org.gnome.Platform    46      u   flathub  < 123,4 MB",
+            " 2. [✓] org.mozilla.firefox   stable  u   flathub  2,5 kB",
+            "Proceed with these changes to the user installation? [Y/n]: n",
+        ]
+        .join("\n");
+
+        let sizes = parse_transaction_download_sizes(&output);
+        assert_eq!(sizes.len(), 2);
+        assert_eq!(sizes[0].app_id, "org.gnome.Platform");
+        assert_eq!(sizes[0].download_bytes, 123_400_000);
+        assert_eq!(sizes[1].app_id, "org.mozilla.firefox");
+        assert_eq!(sizes[1].download_bytes, 2_500);
+    }
+
+    #[test]
+    fn installed_cache_is_dirty_after_install() {
+        let listing = |app_ids: &[&str]| InstalledPackagesResponse {
+            apps: app_ids
+                .iter()
+                .map(|app_id| InstalledApp {
+                    app_id: app_id.to_string(),
+                    name: String::new(),
+                    version: String::new(),
+                    summary: None,
+                    developer: None,
+                    permissions: None,
+                    installed_size: None,
+                    eol: None,
+                    origin: "flathub".to_string(),
+                })
+                .collect(),
+            runtimes: Vec::new(),
+            extensions: Vec::new(),
+        };
+
+        let cache = InstalledCache::default();
+        assert!(cache.get().is_none());
+        cache.store(cache.generation(), listing(&["org.gimp.GIMP"]));
+        assert_eq!(cache.get().unwrap().apps.len(), 1);
+
+        // An install finishing while a listing is running makes that listing stale
+        let generation = cache.generation();
+        cache.mark_dirty();
+        assert!(cache.get().is_none());
+        cache.store(generation, listing(&["org.gimp.GIMP"]));
+        assert!(cache.get().is_none());
+
+        cache.store(
+            cache.generation(),
+            listing(&["org.gimp.GIMP", "org.kde.krita"]),
+        );
+        assert_eq!(cache.get().unwrap().apps.len(), 2);
+    }
+
+    #[test]
+    fn installation_sizes_count_each_ref_once() {
+        let output = [
+            "app/org.gimp.GIMP/x86_64/stable\tuser\tcurrent\t300,0 MB",
+            "app/org.kde.krita/x86_64/stable\tsystem\tcurrent\t200,0 MB",
+            "runtime/org.gnome.Platform/x86_64/46\tsystem\truntime\t1,0 GB",
+            "runtime/org.gnome.Platform/x86_64/46\tsystem\truntime\t1,0 GB",
+            "runtime/org.gnome.Platform/x86_64/46\tuser\truntime\t1,0 GB",
+            "runtime/org.kde.Platform/x86_64/6.7\tsystem\truntime\t",
+        ]
+        .join("\n");
+
+        let summary = parse_installation_sizes(&output);
+        assert_eq!(summary.user_bytes, 1_300_000_000);
+        assert_eq!(summary.system_bytes, 1_200_000_000);
+        assert_eq!(summary.apps_bytes, 500_000_000);
+        assert_eq!(summary.runtime_bytes, 2_000_000_000);
+        assert_eq!(summary.total_bytes, 2_500_000_000);
+    }
+
+    #[test]
+    fn uninstall_impact_refs_and_sizes() {
+        let output = [
+            "        ID                        Branch    Op",
+            " 1.     org.gimp.GIMP             stable    r",
+            " 2. [ ] org.gimp.GIMP.Locale      stable    r",
+            "Proceed with these changes to the user installation? [Y/n]: n",
+        ]
+        .join("\n");
+        let refs = parse_transaction_refs(&output);
+        assert_eq!(
+            refs,
+            [
+                ("org.gimp.GIMP".to_string(), "stable".to_string()),
+                ("org.gimp.GIMP.Locale".to_string(), "stable".to_string()),
+            ]
+        );
+
+        let list = "org.gimp.GIMP\tstable\t300,0 MB\norg.gimp.GIMP.Locale\tstable\t\n";
+        let sizes = parse_installed_ref_sizes(list);
+        let key = ("org.gimp.GIMP".to_string(), "stable".to_string());
+        assert_eq!(sizes.get(&key), Some(&300_000_000));
+        assert_eq!(sizes.len(), 1);
+    }
+
+    #[test]
+    fn pty_prompts_are_classified() {
+        assert_eq!(
+            detect_pty_prompt("Proceed with these changes to the user installation? [Y/n]: "),
+            Some("confirm")
+        );
+        assert_eq!(
+            detect_pty_prompt("\rConfigure this as new remote 'flathub' [Y/n]:"),
+            Some("trust")
+        );
+        assert_eq!(
+            detect_pty_prompt("Which do you want to use (0 to abort)? [0-2]: "),
+            Some("choice")
+        );
+        // Already answered, or not a question at all
+        assert_eq!(
+            detect_pty_prompt("Proceed with these changes to the user installation? [Y/n]: y"),
+            None
+        );
+        assert_eq!(detect_pty_prompt("Installing 1/2… 45%"), None);
+    }
+
+    #[test]
+    fn similar_refs_become_choice_options() {
+        let output = [
+            "Looking for matches…",
+            "Similar refs found for ‘gimp’ in remote ‘flathub’ (system):",
+            "",
+            "   1) app/org.gimp.GIMP/x86_64/stable",
+            "   2) app/org.gimp.GIMP/x86_64/beta",
+            "",
+            "Which do you want to use (0 to abort)? [0-2]: ",
+        ];
+
+        let mut choices = PtyChoiceCollector::default();
+        let found: Vec<_> = output
+            .iter()
+            .filter_map(|line| choices.feed(line))
+            .collect();
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            found[0],
+            [
+                PtyChoiceOption {
+                    index: 1,
+                    flatpak_ref: "app/org.gimp.GIMP/x86_64/stable".to_string(),
+                },
+                PtyChoiceOption {
+                    index: 2,
+                    flatpak_ref: "app/org.gimp.GIMP/x86_64/beta".to_string(),
+                },
+            ]
+        );
+
+        // Numbered lines that aren't followed by a prompt are forgotten
+        assert_eq!(choices.feed(" 1) something"), None);
+        assert_eq!(choices.feed("Done."), None);
+        assert_eq!(choices.feed("Which one? [0-1]: "), None);
+    }
+
+    #[test]
+    fn refs_installed_from_a_remote() {
+        let output = [
+            "org.gimp.GIMP\tflathub",
+            "org.gnome.Platform\tflathub",
+            "org.gnome.Platform\tflathub",
+            "org.example.Nightly\texample-nightly",
+        ]
+        .join("\n");
+
+        assert_eq!(
+            refs_from_remote(&output, "flathub"),
+            ["org.gimp.GIMP", "org.gnome.Platform"]
+        );
+        assert!(refs_from_remote(&output, "fedora").is_empty());
+    }
+
+    #[test]
+    fn only_cache_files_are_moved() {
+        assert!(is_cached_image_name("1f3a9c.png"));
+        assert!(is_cached_image_name("deadbeef.svg"));
+        assert!(!is_cached_image_name("1f3a9c.meta"));
+        assert!(!is_cached_image_name(".names-sniffed"));
+        assert!(!is_cached_image_name("notes.png"));
+        assert!(!is_cached_image_name("1f3a9c.png.bak"));
+        assert!(is_cache_file_name("1f3a9c.meta"));
+        assert!(!is_cache_file_name(".write-test"));
+
+        let base = std::env::temp_dir().join(format!("klia-move-test-{}", std::process::id()));
+        let (from, to) = (base.join("from"), base.join("to"));
+        fs::create_dir_all(&from).unwrap();
+        fs::create_dir_all(&to).unwrap();
+        for name in ["1f3a9c.png", "1f3a9c.meta", "thesis.pdf", ".names-sniffed"] {
+            fs::write(from.join(name), b"x").unwrap();
+        }
+
+        assert_eq!(move_cached_files(&from, &to), 2);
+        assert!(to.join("1f3a9c.png").exists() && to.join("1f3a9c.meta").exists());
+        assert!(from.join("thesis.pdf").exists() && from.join(".names-sniffed").exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn local_install_path_is_one_shell_word() {
+        let path = "/home/me/My Apps (old); rm -rf ~/it's.flatpak";
+        let command = local_install_command(false, false, path);
+        assert!(command.starts_with("flatpak install --user '"));
+
+        // The shell hands the quoted path back unchanged, as a single argument
+        let output = std::process::Command::new("sh")
+            .args(["-c", &format!("printf '%s|' {}", shell_quote(path))])
+            .output()
+            .unwrap();
+        let printed = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(printed, format!("{}|", path));
+    }
+
+    #[test]
+    fn finished_command_marks_installed_dirty() {
+        use tauri_plugin_shell::process::{CommandEvent, TerminatedPayload};
+
+        let app = tauri::test::mock_app();
+        app.manage(InstalledCache::default());
+        app.manage(flathub::LocalStateCache::default());
+        app.manage(DependencyCache::default());
+        let cache = app.state::<InstalledCache>();
+        let listing = InstalledPackagesResponse {
+            apps: Vec::new(),
+            runtimes: Vec::new(),
+            extensions: Vec::new(),
+        };
+        cache.store(cache.generation(), listing);
+        assert!(cache.get().is_some());
+
+        // A mock `flatpak install` that prints one line and exits cleanly
+        let (tx, rx) = tauri::async_runtime::channel(4);
+        tx.try_send(CommandEvent::Stdout(b"Installing org.gimp.GIMP".to_vec()))
+            .unwrap();
+        tx.try_send(CommandEvent::Terminated(TerminatedPayload {
+            code: Some(0),
+            signal: None,
+        }))
+        .unwrap();
+
+        let code = tauri::async_runtime::block_on(forward_command_events(
+            app.handle(),
+            rx,
+            Some("org.gimp.GIMP"),
+            None,
+        ));
+        assert_eq!(code, Ok(Some(0)));
+        assert!(cache.get().is_none());
+    }
+
+    #[test]
+    fn dependency_checks_are_dropped_when_installed_changes() {
+        let app = tauri::test::mock_app();
+        app.manage(InstalledCache::default());
+        app.manage(flathub::LocalStateCache::default());
+        app.manage(DependencyCache::default());
+
+        // GIMP was found already installed; uninstalling it must not keep that answer
+        let checked = InstallDependencies {
+            items: Vec::new(),
+            total_download_bytes: 0,
+            total_installed_bytes: 0,
+            is_partial: false,
+            already_installed: true,
+        };
+        let key = dependency_cache_key("org.gimp.GIMP", "flathub", InstallScope::User);
+        let cache = app.state::<DependencyCache>();
+        cache.lock().unwrap().insert(key, (Instant::now(), checked));
+
+        mark_installed_changed(app.handle());
+        assert!(cache.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn second_dependency_probe_for_a_key_is_refused() {
+        let checks = DependencyCheckMap::default();
+        let key = dependency_cache_key("org.gimp.GIMP", "flathub", InstallScope::User);
+        let sleep = || Command::new("sleep").arg("30").spawn().unwrap();
+
+        register_dependency_probe(&checks, &key, sleep()).unwrap();
+        assert!(register_dependency_probe(&checks, &key, sleep()).is_err());
+
+        // Another remote is a separate check
+        let other = dependency_cache_key("org.gimp.GIMP", "flathub-beta", InstallScope::User);
+        register_dependency_probe(&checks, &other, sleep()).unwrap();
+        assert_eq!(checks.lock().unwrap().len(), 2);
+
+        for (_, mut child) in checks.lock().unwrap().drain() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+
+    #[test]
+    fn flatpak_ids_are_validated() {
+        assert!(is_valid_flatpak_id("org.mozilla.firefox"));
+        assert!(is_valid_flatpak_id("io.github.N3kosempai.klia-store"));
+        assert!(!is_valid_flatpak_id("org.mozilla.firefox;rm -rf ~"));
+        assert!(!is_valid_flatpak_id(""));
+        assert!(!is_valid_flatpak_id("--system"));
+    }
+
+    #[test]
+    fn html_error_page_is_not_cached() {
+        let dir = std::env::temp_dir().join(format!("klia-html-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        // A CDN error page served under the image's URL and content type
+        let page = tauri::http::Response::builder()
+            .header("content-type", "image/png")
+            .body(b"<!DOCTYPE html><html><body>Rate limited</body></html>".to_vec())
+            .unwrap();
+        let url = "https://dl.flathub.org/media/org/gimp/GIMP/icon.png";
+        let stored =
+            tauri::async_runtime::block_on(store_image_response(&dir, 0x1f3a9c, url, page.into()));
+
+        assert!(stored.is_err());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn dependency_sizes_in_bytes() {
+        let runtime =
+            parse_dependency_line("1.\torg.kde.Platform\t5.15-23.08\ti\tflathub\t< 346,1 MB")
+                .unwrap();
+        assert_eq!(runtime.name, "org.kde.Platform");
+        assert_eq!(runtime.download_size, "346,1 MB");
+        assert_eq!(runtime.download_bytes, 346_100_000);
+        // Only the download size is printed here
+        assert_eq!(runtime.installed_size, "Unknown");
+        assert_eq!(runtime.installed_bytes, 0);
+    }
+
+    #[test]
+    fn atomic_writes_leave_no_temp_files() {
+        let dir = std::env::temp_dir().join(format!("klia-atomic-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.json");
+
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn image_without_validators_drops_old_meta() {
+        let dir = std::env::temp_dir().join(format!("klia-meta-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let meta_path = image_meta_path(&dir, 0x1f3a9c);
+        fs::write(&meta_path, br#"{"etag":"\"v1\""}"#).unwrap();
+
+        let fresh = tauri::http::Response::builder()
+            .header("content-type", "image/png")
+            .body(b"\x89PNG\r\n\x1a\n".to_vec())
+            .unwrap();
+        let url = "https://dl.flathub.org/media/org/gimp/GIMP/icon.png";
+        let stored =
+            tauri::async_runtime::block_on(store_image_response(&dir, 0x1f3a9c, url, fresh.into()));
+
+        assert_eq!(stored.unwrap(), "1f3a9c.png");
+        assert!(!meta_path.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn progress_percentages() {
+        assert_eq!(
+            parse_flatpak_progress("Installing 2/3… ████████   45%"),
+            Some(45)
+        );
+        assert_eq!(parse_flatpak_progress("Downloading: 45,5 %"), Some(45));
+        assert_eq!(parse_flatpak_progress("(100%)"), Some(100));
+        // Digits glued to a longer number are not a percentage
+        assert_eq!(parse_flatpak_progress("1045%"), None);
+        assert_eq!(
+            parse_flatpak_progress("Installing org.gimp.GIMP 2.10"),
+            None
+        );
+    }
 }
@@ -0,0 +1,95 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::error::StoreError;
+
+// ─── File logger ─────────────────────────────────────────────────────────────
+
+const LOG_DIR: &str = "logs";
+const LOG_FILE: &str = "klia-store.log";
+
+/// Minimal `log` backend that appends to a file in the app data dir.
+/// Debug builds also echo to stderr, like the old eprintln! lines did.
+struct FileLogger {
+    file: Mutex<File>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Info || cfg!(debug_assertions)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let line = format!("{} {:<5} {}\n", timestamp, record.level(), record.args());
+
+        if cfg!(debug_assertions) {
+            eprint!("{}", line);
+        }
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Path of the log file inside the app data dir.
+pub fn log_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    Ok(app_data_dir.join(LOG_DIR).join(LOG_FILE))
+}
+
+/// Install the file logger. Called once from `run()` setup.
+pub fn init(app: &tauri::AppHandle) -> Result<(), String> {
+    let path = log_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create log directory: {}", e))?;
+    }
+
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open log file: {}", e))?;
+
+    log::set_boxed_logger(Box::new(FileLogger {
+        file: Mutex::new(file),
+    }))
+    .map_err(|e| format!("Failed to install logger: {}", e))?;
+    log::set_max_level(if cfg!(debug_assertions) {
+        LevelFilter::Debug
+    } else {
+        LevelFilter::Info
+    });
+
+    Ok(())
+}
+
+// ─── Commands ────────────────────────────────────────────────────────────────
+
+/// Return the log file path so users can attach it to bug reports.
+#[tauri::command]
+pub fn get_log_path(app: tauri::AppHandle) -> Result<String, StoreError> {
+    Ok(log_path(&app)?.to_string_lossy().to_string())
+}
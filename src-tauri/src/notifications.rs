@@ -0,0 +1,114 @@
+use tauri::Manager;
+use tauri_plugin_shell::ShellExt;
+
+use crate::config;
+
+// ─── Desktop notifications ───────────────────────────────────────────────────
+
+// Notifications go out through `gdbus call` to org.freedesktop.Notifications rather
+// than tauri-plugin-notification. The Flatpak build is offline and gets its crates from
+// cargo-sources.json, so a new crate means regenerating that file; gdbus ships with the
+// GNOME runtime and the manifest already allows --talk-name=org.freedesktop.Notifications.
+
+const APP_NAME: &str = "Klia Store";
+const APP_ICON: &str = "io.github.N3kosempai.klia-store";
+const EXPIRE_TIMEOUT_MS: &str = "5000";
+
+fn completion_message(action: &str, success: bool) -> &'static str {
+    match (action, success) {
+        ("install", true) => "Installed successfully",
+        ("install", false) => "Installation failed",
+        ("update", true) => "Updated successfully",
+        ("update", false) => "Update failed",
+        (_, true) => "Finished successfully",
+        (_, false) => "Failed",
+    }
+}
+
+// gdbus reads each argument as GVariant text, where strings are single-quoted
+fn gvariant_string(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+// Name shown for an installed app or extension, the app id when it isn't installed
+// One `flatpak info` for this id: the installed list was just invalidated by the operation
+async fn display_name(app: &tauri::AppHandle, app_id: &str) -> String {
+    crate::flatpak_info_output(app, app_id)
+        .await
+        .ok()
+        .and_then(|output| crate::parse_flatpak_info(app_id, &output).name)
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| app_id.to_string())
+}
+
+// The session's notification server, reached through gdbus so no extra crate is needed
+async fn send(app: &tauri::AppHandle, summary: &str, body: &str) -> Result<(), String> {
+    let output = app
+        .shell()
+        .command("gdbus")
+        .args([
+            "call",
+            "--session",
+            "--dest=org.freedesktop.Notifications",
+            "--object-path=/org/freedesktop/Notifications",
+            "--method=org.freedesktop.Notifications.Notify",
+            &gvariant_string(APP_NAME),
+            "0",
+            &gvariant_string(APP_ICON),
+            &gvariant_string(summary),
+            &gvariant_string(body),
+            "[]",
+            "{}",
+            EXPIRE_TIMEOUT_MS,
+        ])
+        .output()
+        .await
+        .map_err(|e| format!("Failed to execute gdbus: {}", e))?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
+
+/// Tell the user an install or update finished when the store isn't focused.
+/// Runs in the background and never fails the operation; disabled by
+/// `notificationsEnabled` in the config.
+pub fn notify_completion(
+    app: &tauri::AppHandle,
+    app_id: &str,
+    action: &'static str,
+    success: bool,
+) {
+    let enabled = config::load(app)
+        .map(|config| config.notifications_enabled)
+        .unwrap_or(true);
+    let focused = app
+        .get_webview_window("main")
+        .and_then(|window| window.is_focused().ok())
+        .unwrap_or(false);
+    if !enabled || focused {
+        return;
+    }
+
+    let app = app.clone();
+    let app_id = app_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let name = display_name(&app, &app_id).await;
+        let body = completion_message(action, success);
+        if let Err(e) = send(&app, &name, body).await {
+            log::warn!("[notifications] Failed to notify about {}: {}", app_id, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gvariant_strings_are_quoted() {
+        assert_eq!(gvariant_string("GIMP"), "'GIMP'");
+        assert_eq!(gvariant_string("Tom's \\ App"), "'Tom\\'s \\\\ App'");
+    }
+}
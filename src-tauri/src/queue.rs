@@ -0,0 +1,231 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager, State};
+use tokio::sync::oneshot;
+
+use crate::error::StoreError;
+use crate::{InstallScope, ProcessMap};
+
+// ─── Types ───────────────────────────────────────────────────────────────────
+
+pub const QUEUE_CHANGED_EVENT: &str = "queue-changed";
+
+// How often the worker checks whether the PTY slot is free again
+const PTY_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpKind {
+    Install,
+    Update,
+    Uninstall,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedOp {
+    /// Assigned by `enqueue_operation`; whatever the frontend sends is ignored.
+    #[serde(default)]
+    pub id: u64,
+    pub kind: OpKind,
+    pub app_id: String,
+    #[serde(default)]
+    pub remote: Option<String>,
+    #[serde(default)]
+    pub scope: Option<InstallScope>,
+}
+
+/// Pending flatpak operations. The front entry is the one currently running
+/// and stays in the queue until it finishes.
+#[derive(Default)]
+pub struct OperationQueue {
+    ops: tokio::sync::Mutex<VecDeque<QueuedOp>>,
+    // Set while a worker task is draining the queue
+    running: AtomicBool,
+    next_id: AtomicU64,
+    // Callers waiting for an operation's outcome, keyed by op id
+    watchers: Mutex<HashMap<u64, oneshot::Sender<bool>>>,
+}
+
+// ─── Worker ──────────────────────────────────────────────────────────────────
+
+fn emit_queue_changed(app: &tauri::AppHandle, ops: &VecDeque<QueuedOp>) {
+    let snapshot: Vec<QueuedOp> = ops.iter().cloned().collect();
+    let _ = app.emit(QUEUE_CHANGED_EVENT, snapshot);
+}
+
+// Interactive installs run in a PTY outside the queue; any live one holds the slot
+fn pty_busy(app: &tauri::AppHandle, app_id: Option<&str>) -> bool {
+    let processes = app.state::<ProcessMap>();
+    let map = processes.lock().unwrap();
+    match app_id {
+        Some(app_id) => map.contains_key(app_id),
+        None => !map.is_empty(),
+    }
+}
+
+async fn wait_for_pty(app: &tauri::AppHandle, app_id: Option<&str>) {
+    while pty_busy(app, app_id) {
+        tokio::time::sleep(PTY_POLL_INTERVAL).await;
+    }
+}
+
+async fn run_operation(app: &tauri::AppHandle, op: &QueuedOp) -> Result<(), StoreError> {
+    match op.kind {
+        OpKind::Install => {
+            crate::start_flatpak_interactive(
+                app.clone(),
+                app.state::<ProcessMap>(),
+                op.app_id.clone(),
+                op.remote.clone(),
+                op.scope,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await?;
+            // The PTY install returns as soon as it is spawned; hold the slot until it exits
+            wait_for_pty(app, Some(&op.app_id)).await;
+            Ok(())
+        }
+        OpKind::Update => crate::update_flatpak(app.clone(), op.app_id.clone(), None).await,
+        OpKind::Uninstall => {
+            crate::uninstall_flatpak(app.clone(), op.app_id.clone(), op.scope).await
+        }
+    }
+}
+
+// Whether a finished operation left the app in the expected state. The commands
+// report flatpak's exit code through events only, so check the result directly.
+async fn operation_succeeded(app: &tauri::AppHandle, op: &QueuedOp) -> bool {
+    match op.kind {
+        OpKind::Install => crate::is_app_installed(app, &op.app_id).await,
+        OpKind::Uninstall => !crate::is_app_installed(app, &op.app_id).await,
+        OpKind::Update => true,
+    }
+}
+
+/// Drain the queue one operation at a time.
+async fn run_queue(app: tauri::AppHandle) {
+    let queue = app.state::<OperationQueue>();
+
+    loop {
+        let op = {
+            let ops = queue.ops.lock().await;
+            match ops.front() {
+                Some(op) => op.clone(),
+                None => {
+                    // Cleared under the lock so a concurrent enqueue starts a new worker
+                    queue.running.store(false, Ordering::SeqCst);
+                    break;
+                }
+            }
+        };
+
+        wait_for_pty(&app, None).await;
+
+        log::info!("[queue] Running {:?} for {}", op.kind, op.app_id);
+        let success = match run_operation(&app, &op).await {
+            Ok(()) => operation_succeeded(&app, &op).await,
+            Err(e) => {
+                log::error!("[queue] {:?} for {} failed: {}", op.kind, op.app_id, e);
+                let _ = app.emit("install-error", e.to_string());
+                false
+            }
+        };
+
+        let watcher = queue.watchers.lock().unwrap().remove(&op.id);
+        if let Some(watcher) = watcher {
+            let _ = watcher.send(success);
+        }
+
+        let mut ops = queue.ops.lock().await;
+        ops.pop_front();
+        emit_queue_changed(&app, &ops);
+    }
+}
+
+async fn push(
+    app: &tauri::AppHandle,
+    queue: &OperationQueue,
+    mut op: QueuedOp,
+    watcher: Option<oneshot::Sender<bool>>,
+) -> Result<u64, StoreError> {
+    if !crate::is_valid_flatpak_id(&op.app_id) {
+        return Err(StoreError::InvalidInput(format!(
+            "Invalid app id: {}",
+            op.app_id
+        )));
+    }
+    op.id = queue.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+    let id = op.id;
+
+    // Registered before the op is visible to the worker so the outcome can't be missed
+    if let Some(watcher) = watcher {
+        queue.watchers.lock().unwrap().insert(id, watcher);
+    }
+
+    let mut ops = queue.ops.lock().await;
+    ops.push_back(op);
+    emit_queue_changed(app, &ops);
+
+    if !queue.running.swap(true, Ordering::SeqCst) {
+        tauri::async_runtime::spawn(run_queue(app.clone()));
+    }
+
+    Ok(id)
+}
+
+/// Queue an operation and get its outcome (true on success) once it has run.
+pub async fn enqueue_and_watch(
+    app: &tauri::AppHandle,
+    op: QueuedOp,
+) -> Result<oneshot::Receiver<bool>, StoreError> {
+    let (tx, rx) = oneshot::channel();
+    push(app, &app.state::<OperationQueue>(), op, Some(tx)).await?;
+    Ok(rx)
+}
+
+/// Drop every operation that hasn't started. The running one stays until it ends.
+/// Returns how many were dropped; anyone watching them sees a failure.
+pub async fn clear_pending(app: &tauri::AppHandle) -> usize {
+    let queue = app.state::<OperationQueue>();
+    let mut ops = queue.ops.lock().await;
+    let running = ops.len().min(1);
+    let dropped: Vec<QueuedOp> = ops.drain(running..).collect();
+    if dropped.is_empty() {
+        return 0;
+    }
+
+    let mut watchers = queue.watchers.lock().unwrap();
+    for op in &dropped {
+        watchers.remove(&op.id);
+    }
+    drop(watchers);
+
+    emit_queue_changed(app, &ops);
+    dropped.len()
+}
+
+// ─── Commands ────────────────────────────────────────────────────────────────
+
+/// Add an install/update/uninstall to the queue. Returns the id assigned to it.
+#[tauri::command]
+pub async fn enqueue_operation(
+    app: tauri::AppHandle,
+    queue: State<'_, OperationQueue>,
+    op: QueuedOp,
+) -> Result<u64, StoreError> {
+    push(&app, &queue, op, None).await
+}
+
+/// Current queue, the running operation first.
+#[tauri::command]
+pub async fn get_queue(app: tauri::AppHandle) -> Result<Vec<QueuedOp>, StoreError> {
+    let queue = app.state::<OperationQueue>();
+    let ops = queue.ops.lock().await;
+    Ok(ops.iter().cloned().collect())
+}
@@ -0,0 +1,88 @@
+use std::time::Duration;
+use tauri::{Emitter, Manager, State};
+use tokio::sync::Notify;
+
+use crate::config;
+use crate::error::StoreError;
+
+pub const UPDATES_AVAILABLE_EVENT: &str = "updates-available";
+
+// An unreadable config falls back to the default interval
+fn read_interval(app: &tauri::AppHandle) -> u64 {
+    config::load(app)
+        .unwrap_or_default()
+        .update_check_interval_minutes
+}
+
+// ─── Scheduler ───────────────────────────────────────────────────────────────
+
+/// Wakes the scheduler early when the interval changes.
+#[derive(Default)]
+pub struct UpdateScheduler {
+    wake: Notify,
+}
+
+impl UpdateScheduler {
+    /// Restart the wait with the interval currently in the config.
+    pub fn reschedule(&self) {
+        self.wake.notify_one();
+    }
+}
+
+/// Background loop started from `run()`: checks for updates every interval
+/// and emits the number found.
+pub async fn run(app: tauri::AppHandle) {
+    let scheduler = app.state::<UpdateScheduler>();
+
+    loop {
+        let minutes = read_interval(&app);
+        if minutes == 0 {
+            // Disabled until the user picks an interval
+            scheduler.wake.notified().await;
+            continue;
+        }
+
+        let interval = Duration::from_secs(minutes * 60);
+        if tokio::time::timeout(interval, scheduler.wake.notified())
+            .await
+            .is_ok()
+        {
+            // Interval changed, start over with the new value
+            continue;
+        }
+
+        check_now(&app).await;
+    }
+}
+
+/// Check for updates right away and emit the number found.
+pub async fn check_now(app: &tauri::AppHandle) {
+    match crate::get_available_updates(app.clone()).await {
+        Ok(updates) => {
+            log::info!("[scheduler] {} update(s) available", updates.len());
+            let _ = app.emit(UPDATES_AVAILABLE_EVENT, updates.len());
+        }
+        Err(e) => log::warn!("[scheduler] Update check failed: {}", e),
+    }
+}
+
+// ─── Commands ────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_update_check_interval(app: tauri::AppHandle) -> Result<u64, StoreError> {
+    Ok(read_interval(&app))
+}
+
+/// Persist the interval in minutes (0 disables) and reschedule right away.
+#[tauri::command]
+pub fn set_update_check_interval(
+    app: tauri::AppHandle,
+    scheduler: State<'_, UpdateScheduler>,
+    minutes: u64,
+) -> Result<(), StoreError> {
+    let mut config = config::load(&app)?;
+    config.update_check_interval_minutes = minutes;
+    config::save(&app, &config)?;
+    scheduler.reschedule();
+    Ok(())
+}
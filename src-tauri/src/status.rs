@@ -0,0 +1,65 @@
+use serde::Serialize;
+use tauri::Emitter;
+
+// ─── Status keys ─────────────────────────────────────────────────────────────
+
+// Keys match the `backendStatus` section of the frontend locale files.
+pub const INSTALL_START: &str = "install.start";
+pub const UPDATE_START: &str = "update.start";
+pub const UPDATE_SYSTEM_START: &str = "update.systemStart";
+pub const UNINSTALL_START: &str = "uninstall.start";
+pub const EXTENSION_INSTALL_START: &str = "extension.installStart";
+pub const EXTENSION_UNINSTALL_START: &str = "extension.uninstallStart";
+pub const CLEANUP_START: &str = "cleanup.start";
+pub const REPAIR_START: &str = "repair.start";
+pub const RESET_DATA_START: &str = "resetData.start";
+pub const APPSTREAM_START: &str = "appstream.start";
+
+/// Event carrying a translatable status line. Raw CLI output keeps going
+/// through `install-output`.
+pub const STATUS_EVENT: &str = "install-status";
+
+#[derive(Clone, Serialize)]
+pub struct StatusMessage {
+    pub key: &'static str,
+    pub app_id: Option<String>,
+    /// English default, used when the frontend has no translation for `key`.
+    pub message: String,
+}
+
+/// English default text for a status key.
+fn default_message(key: &str, app_id: Option<&str>) -> String {
+    let app_id = app_id.unwrap_or_default();
+    match key {
+        INSTALL_START => format!("Starting install of {}...", app_id),
+        UPDATE_START => format!("Starting update of {}...", app_id),
+        UPDATE_SYSTEM_START => "Starting system package update...".to_string(),
+        UNINSTALL_START => format!("Starting uninstall of {}...", app_id),
+        EXTENSION_INSTALL_START => format!("Installing extension {}...", app_id),
+        EXTENSION_UNINSTALL_START => format!("Uninstalling extension {}...", app_id),
+        CLEANUP_START => "Removing unused runtimes...".to_string(),
+        REPAIR_START => "Repairing the flatpak installation...".to_string(),
+        RESET_DATA_START => format!("Clearing data of {}...", app_id),
+        APPSTREAM_START => format!("Refreshing app information from {}...", app_id),
+        _ => key.to_string(),
+    }
+}
+
+// ─── Emit ────────────────────────────────────────────────────────────────────
+
+/// Emit a status key plus its interpolation args to the frontend.
+pub fn emit_status(
+    app: &tauri::AppHandle,
+    key: &'static str,
+    app_id: Option<&str>,
+) -> Result<(), String> {
+    app.emit(
+        STATUS_EVENT,
+        StatusMessage {
+            key,
+            app_id: app_id.map(String::from),
+            message: default_message(key, app_id),
+        },
+    )
+    .map_err(|e| format!("Failed to emit: {}", e))
+}
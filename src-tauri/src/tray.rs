@@ -0,0 +1,137 @@
+use std::path::Path;
+use tauri::menu::{Menu, MenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{Listener, Manager};
+
+use crate::config;
+use crate::error::StoreError;
+use crate::scheduler::{self, UPDATES_AVAILABLE_EVENT};
+
+// ─── Tray icon ───────────────────────────────────────────────────────────────
+
+const TRAY_ID: &str = "main";
+const TOOLTIP: &str = "Klia Store";
+
+const APPINDICATOR_LIBRARIES: &[&str] = &["libayatana-appindicator3.so.1", "libappindicator3.so.1"];
+const LIBRARY_DIRS: &[&str] = &[
+    "/app/lib",
+    "/usr/lib",
+    "/usr/lib64",
+    "/usr/lib/x86_64-linux-gnu",
+    "/usr/lib/aarch64-linux-gnu",
+];
+
+// The tray backend loads libappindicator at runtime and aborts when it can't,
+// so look for the library before creating the icon
+fn appindicator_available() -> bool {
+    let env_dirs = std::env::var("LD_LIBRARY_PATH").unwrap_or_default();
+    env_dirs
+        .split(':')
+        .filter(|dir| !dir.is_empty())
+        .chain(LIBRARY_DIRS.iter().copied())
+        .any(|dir| {
+            APPINDICATOR_LIBRARIES
+                .iter()
+                .any(|library| Path::new(dir).join(library).exists())
+        })
+}
+
+/// Bring the main window back, whether it was minimized or hidden to the tray.
+pub fn show_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn tooltip(count: usize) -> String {
+    match count {
+        0 => TOOLTIP.to_string(),
+        1 => format!("{} - 1 update available", TOOLTIP),
+        n => format!("{} - {} updates available", TOOLTIP, n),
+    }
+}
+
+// Tooltip plus a count next to the icon, cleared when nothing is pending
+fn set_badge(app: &tauri::AppHandle, count: usize) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let _ = tray.set_tooltip(Some(tooltip(count)));
+    let _ = tray.set_title((count > 0).then(|| count.to_string()));
+}
+
+/// Whether closing the window should hide it to the tray instead of quitting.
+/// Never true without a tray, or the window couldn't be brought back.
+pub fn hides_on_close(app: &tauri::AppHandle) -> bool {
+    app.tray_by_id(TRAY_ID).is_some()
+        && config::load(app)
+            .map(|config| config.minimize_to_tray)
+            .unwrap_or(false)
+}
+
+/// Create the tray icon and keep its badge in sync with the background update
+/// check. Called once from setup; without libappindicator there is no tray.
+pub fn init(app: &tauri::AppHandle) -> tauri::Result<()> {
+    if !appindicator_available() {
+        log::warn!("[tray] libappindicator not found, tray icon disabled");
+        return Ok(());
+    }
+
+    let check = MenuItem::with_id(
+        app,
+        "check-updates",
+        "Check for updates",
+        true,
+        None::<&str>,
+    )?;
+    let open = MenuItem::with_id(app, "open", "Open", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[&check, &open, &quit])?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(TOOLTIP)
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id().as_ref() {
+            "check-updates" => {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move { scheduler::check_now(&app).await });
+            }
+            "open" => show_main_window(app),
+            "quit" => app.exit(0),
+            _ => {}
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    let handle = app.clone();
+    app.listen(UPDATES_AVAILABLE_EVENT, move |event| {
+        if let Ok(count) = serde_json::from_str::<usize>(event.payload()) {
+            set_badge(&handle, count);
+        }
+    });
+    Ok(())
+}
+
+// ─── Commands ────────────────────────────────────────────────────────────────
+
+/// Show `count` pending updates on the tray icon, 0 clears it.
+#[tauri::command]
+pub fn set_tray_badge(app: tauri::AppHandle, count: usize) -> Result<(), StoreError> {
+    set_badge(&app, count);
+    Ok(())
+}
@@ -1,6 +1,7 @@
 import { BrokenImage } from "@mui/icons-material";
 import { Box, Skeleton } from "@mui/material";
 import { useEffect, useRef, useState } from "react";
+import { getBackendErrorMessage } from "../utils/backendError";
 import { imageCacheManager } from "../utils/imageCache";
 
 interface CachedImageProps {
@@ -131,7 +132,7 @@ export const CachedImage = ({
 
 				console.error("Error loading cached image:", err);
 				if (isMounted) {
-					const errorMsg = String(err).toLowerCase();
+					const errorMsg = getBackendErrorMessage(err).toLowerCase();
 					const isTemporaryError =
 						errorMsg.includes("timeout") ||
 						errorMsg.includes("error sending request") ||
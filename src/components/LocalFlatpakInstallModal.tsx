@@ -22,6 +22,8 @@ import {
 import { invoke } from "@tauri-apps/api/core";
 import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import { useCallback, useEffect, useRef, useState } from "react";
+import type { PtyTerminated } from "../types";
+import { formatBackendError } from "../utils/backendError";
 import { Terminal } from "./Terminal";
 
 interface LocalFlatpakPermissions {
@@ -201,6 +203,7 @@ export function LocalFlatpakInstallModal({ filePath, onClose }: LocalFlatpakInst
 	const [info, setInfo] = useState<LocalFlatpakInfo | null>(null);
 	const [loadError, setLoadError] = useState<string | null>(null);
 	const [terminalLines, setTerminalLines] = useState<string[]>([]);
+	const [installFailed, setInstallFailed] = useState(false);
 	const [showDeps, setShowDeps] = useState(false);
 	const [showTerminal, setShowTerminal] = useState(false);
 	const processKey = useRef<string | null>(null);
@@ -221,7 +224,7 @@ export function LocalFlatpakInstallModal({ filePath, onClose }: LocalFlatpakInst
 				setPhase("review");
 			})
 			.catch((err) => {
-				setLoadError(String(err));
+				setLoadError(formatBackendError(err));
 				setPhase("error");
 			});
 	}, [filePath]);
@@ -230,6 +233,7 @@ export function LocalFlatpakInstallModal({ filePath, onClose }: LocalFlatpakInst
 		if (!filePath) return;
 		setPhase("installing");
 		setTerminalLines([]);
+		setInstallFailed(false);
 		setShowTerminal(true);
 		processKey.current = `local::${filePath}`;
 
@@ -251,18 +255,25 @@ export function LocalFlatpakInstallModal({ filePath, onClose }: LocalFlatpakInst
 		});
 		unlisteners.push(unlistenError);
 
-		const unlistenDone = await listen<string>("pty-terminated", (event) => {
-			if (event.payload === processKey.current) {
-				setPhase("done");
-				for (const fn of unlisteners) fn();
-			}
-		});
+		const unlistenDone = await listen<PtyTerminated>(
+			"pty-terminated",
+			(event) => {
+				if (event.payload.app_id === processKey.current) {
+					setInstallFailed(!event.payload.success);
+					setPhase("done");
+					for (const fn of unlisteners) fn();
+				}
+			},
+		);
 		unlisteners.push(unlistenDone);
 
 		try {
 			await invoke("install_local_flatpak", { filePath });
 		} catch (err) {
-			setTerminalLines((prev) => [...prev, `Error: ${err}`]);
+			setTerminalLines((prev) => [
+				...prev,
+				`Error: ${formatBackendError(err)}`,
+			]);
 			setPhase("error");
 			for (const fn of unlisteners) fn();
 		}
@@ -634,6 +645,8 @@ export function LocalFlatpakInstallModal({ filePath, onClose }: LocalFlatpakInst
 							<Stack direction="row" spacing={1.5} alignItems="center" sx={{ mb: 2 }}>
 								{phase === "installing" ? (
 									<CircularProgress size={18} sx={{ color: "#4A86CF" }} />
+								) : installFailed ? (
+									<ErrorOutlineIcon sx={{ color: "#FF5252", fontSize: "1.2rem" }} />
 								) : (
 									<CheckCircleIcon sx={{ color: "#4CAF50", fontSize: "1.2rem" }} />
 								)}
@@ -642,10 +655,14 @@ export function LocalFlatpakInstallModal({ filePath, onClose }: LocalFlatpakInst
 										fontFamily: "IBM Plex Sans, sans-serif",
 										fontWeight: 600,
 										fontSize: "0.9rem",
-										color: phase === "done" ? "#4CAF50" : "#FFFFFF",
+										color: phase !== "done" ? "#FFFFFF" : installFailed ? "#FF5252" : "#4CAF50",
 									}}
 								>
-									{phase === "installing" ? `Installing ${info?.name ?? ""}…` : "Installation complete"}
+									{phase === "installing"
+										? `Installing ${info?.name ?? ""}…`
+										: installFailed
+											? "Installation failed"
+											: "Installation complete"}
 								</Typography>
 							</Stack>
 
@@ -11,6 +11,7 @@ import {
 import { invoke } from "@tauri-apps/api/core";
 import { useCallback, useEffect, useMemo, useRef, useState } from "react";
 import { useTranslation } from "react-i18next";
+import { formatBackendError } from "../utils/backendError";
 import { Terminal } from "./Terminal";
 
 export interface AppToVerify {
@@ -217,7 +218,7 @@ export const UpdateAllModal = ({
 							phase: "warning",
 							decision: "include",
 							showDetails: false,
-							fullResult: { sources: [], error: String(err) },
+							fullResult: { sources: [], error: formatBackendError(err) },
 						},
 					}));
 				})
@@ -1,5 +1,6 @@
 import { invoke } from "@tauri-apps/api/core";
 import { useCallback, useRef, useState } from "react";
+import { formatBackendError } from "../utils/backendError";
 
 interface InstallableExtensionRust {
 	extension_id: string;
@@ -71,7 +72,7 @@ export function useAppExtensions(): UseAppExtensionsReturn {
 
 				setAvailableExtensions(extensionsWithStatus);
 			} catch (err) {
-				setError(err instanceof Error ? err.message : String(err));
+				setError(formatBackendError(err));
 				setAvailableExtensions([]);
 			} finally {
 				setIsLoading(false);
@@ -1,5 +1,6 @@
 import { invoke } from "@tauri-apps/api/core";
 import { useEffect, useState } from "react";
+import { formatBackendError } from "../utils/backendError";
 
 export function useAppInitialization() {
 	const [isFirstLaunch, setIsFirstLaunch] = useState<boolean | null>(null);
@@ -15,7 +16,7 @@ export function useAppInitialization() {
 				setIsInitializing(false);
 			} catch (err) {
 				console.error("Failed to check first launch:", err);
-				setError(err instanceof Error ? err.message : String(err));
+				setError(formatBackendError(err));
 				setIsInitializing(false);
 			}
 		};
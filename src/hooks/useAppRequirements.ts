@@ -0,0 +1,51 @@
+import { invoke } from "@tauri-apps/api/core";
+import { useEffect, useState } from "react";
+
+export interface RequiredRef {
+	id: string;
+	arch: string;
+	branch: string;
+}
+
+export interface Requirements {
+	app_id: string;
+	runtime: RequiredRef | null;
+	sdk: RequiredRef | null;
+	download_bytes: number | null;
+	installed_bytes: number | null;
+}
+
+/**
+ * Custom hook to read the runtime an app needs from the remote's metadata
+ * Uses get_app_requirements command (lighter than the dependency probe)
+ * @param appId - The Flatpak application ID (e.g., "org.gnome.Builder")
+ * @returns The requirements, or null while loading or when they can't be read
+ */
+export function useAppRequirements(appId: string): Requirements | null {
+	const [requirements, setRequirements] = useState<Requirements | null>(null);
+
+	useEffect(() => {
+		let cancelled = false;
+		setRequirements(null);
+
+		invoke<Requirements>("get_app_requirements", { appId })
+			.then((result) => {
+				if (!cancelled) {
+					setRequirements(result);
+				}
+			})
+			.catch((err) => {
+				// Only a hint on the detail page, the install flow reports real errors
+				console.error(
+					"[useAppRequirements] Error fetching requirements:",
+					err,
+				);
+			});
+
+		return () => {
+			cancelled = true;
+		};
+	}, [appId]);
+
+	return requirements;
+}
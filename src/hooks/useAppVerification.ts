@@ -1,5 +1,6 @@
 import { invoke } from "@tauri-apps/api/core";
 import { useCallback, useState } from "react";
+import { formatBackendError } from "../utils/backendError";
 
 export type VerificationState =
 	| "idle"
@@ -99,7 +100,7 @@ export function useAppVerification(): UseAppVerificationReturn {
 				verified: false,
 				appId,
 				sources: [],
-				error: String(error),
+				error: formatBackendError(error),
 				isHashMismatch: false,
 				isUnsupportedPlatform: false,
 				isSourceUnavailable: false,
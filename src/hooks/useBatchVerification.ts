@@ -1,5 +1,6 @@
 import { invoke } from "@tauri-apps/api/core";
 import { useCallback, useRef, useState } from "react";
+import { formatBackendError } from "../utils/backendError";
 
 export interface SourceVerification {
 	url: string;
@@ -120,7 +121,7 @@ export function useBatchVerification(): UseBatchVerificationReturn {
 						isHashMismatch: false,
 						isUnsupportedPlatform: false,
 						isSourceUnavailable: false,
-						error: String(error),
+						error: formatBackendError(error),
 						sources: [],
 					};
 
@@ -12,6 +12,7 @@ interface InstalledAppRust {
 	summary?: string;
 	developer?: string;
 	installed_size?: number;
+	origin: string;
 }
 
 interface InstalledExtensionRust {
@@ -54,6 +55,7 @@ export const useInstalledApps = () => {
 						summary: app.summary,
 						developer: app.developer,
 						installedSize: app.installed_size,
+						origin: app.origin,
 						permissions: [], // Loaded on-demand
 					}),
 				);
@@ -1,14 +1,31 @@
 import { invoke } from "@tauri-apps/api/core";
 import { useEffect, useState } from "react";
+import { formatBackendError } from "../utils/backendError";
 
 export interface Dependency {
 	name: string;
 	download_size: string;
 	installed_size: string;
+	download_bytes: number;
+	installed_bytes: number;
+	// The size is an upper bound ("<"), render it as "~346 MB"
+	approximate: boolean;
+	// Only part of the ref is downloaded
+	partial: boolean;
+}
+
+export interface InstallDependencies {
+	items: Dependency[];
+	total_download_bytes: number;
+	total_installed_bytes: number;
+	is_partial: boolean;
+	// Nothing to install, don't read the empty list as a free install
+	already_installed: boolean;
 }
 
 export interface DependenciesCheckResult {
 	dependencies: Dependency[];
+	alreadyInstalled: boolean;
 	loading: boolean;
 	error: string | null;
 }
@@ -21,6 +38,7 @@ export interface DependenciesCheckResult {
  */
 export function useRuntimeCheck(appId: string): DependenciesCheckResult {
 	const [dependencies, setDependencies] = useState<Dependency[]>([]);
+	const [alreadyInstalled, setAlreadyInstalled] = useState(false);
 	const [loading, setLoading] = useState(true);
 	const [error, setError] = useState<string | null>(null);
 
@@ -30,15 +48,19 @@ export function useRuntimeCheck(appId: string): DependenciesCheckResult {
 				setLoading(true);
 				setError(null);
 
-				const deps = await invoke<Dependency[]>("get_install_dependencies", {
-					appId,
-				});
+				const deps = await invoke<InstallDependencies>(
+					"get_install_dependencies",
+					{
+						appId,
+					},
+				);
 
 				console.log("[useRuntimeCheck] Dependencies fetched:", deps);
-				setDependencies(deps);
+				setDependencies(deps.items);
+				setAlreadyInstalled(deps.already_installed);
 			} catch (err) {
 				console.error("[useRuntimeCheck] Error fetching dependencies:", err);
-				setError(String(err));
+				setError(formatBackendError(err));
 			} finally {
 				setLoading(false);
 			}
@@ -49,6 +71,7 @@ export function useRuntimeCheck(appId: string): DependenciesCheckResult {
 
 	return {
 		dependencies,
+		alreadyInstalled,
 		loading,
 		error,
 	};
@@ -1,4 +1,5 @@
 import { useCallback, useState } from "react";
+import { formatBackendError } from "../utils/backendError";
 import { uninstallFlatpakApp } from "../utils/flatpakOperations";
 
 interface UseUninstallAppReturn {
@@ -48,7 +49,7 @@ export function useUninstallApp(): UseUninstallAppReturn {
 			setUninstallOutput((prev) => [
 				...prev,
 				"",
-				`✗ Error al ejecutar comando: ${error}`,
+				`✗ Error al ejecutar comando: ${formatBackendError(error)}`,
 			]);
 			setIsUninstalling(false);
 			return false;
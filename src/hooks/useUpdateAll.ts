@@ -1,5 +1,6 @@
 import { useCallback, useState } from "react";
 import type { InstalledAppInfo } from "../store/installedAppsStore";
+import { formatBackendError } from "../utils/backendError";
 import { dbCacheManager } from "../utils/dbCache";
 import {
 	updateFlatpakApp,
@@ -118,7 +119,7 @@ export function useUpdateAll(onComplete?: () => void): UseUpdateAllReturn {
 					setUpdateAllOutput((prev) => [
 						...prev,
 						"",
-						`✗ Error al actualizar ${app.name}: ${error}`,
+						`✗ Error al actualizar ${app.name}: ${formatBackendError(error)}`,
 					]);
 				}
 
@@ -184,7 +185,7 @@ export function useUpdateAll(onComplete?: () => void): UseUpdateAllReturn {
 						setUpdateAllOutput((prev) => [
 							...prev,
 							"",
-							`✗ Error al actualizar paquetes del sistema: ${error}`,
+							`✗ Error al actualizar paquetes del sistema: ${formatBackendError(error)}`,
 						]);
 					}
 
@@ -1,4 +1,5 @@
 import { useCallback, useState } from "react";
+import { formatBackendError } from "../utils/backendError";
 import { dbCacheManager } from "../utils/dbCache";
 import { updateFlatpakApp } from "../utils/flatpakOperations";
 
@@ -65,7 +66,7 @@ export function useUpdateApp(): UseUpdateAppReturn {
 			setUpdateOutput((prev) => [
 				...prev,
 				"",
-				`✗ Error al ejecutar comando: ${error}`,
+				`✗ Error al ejecutar comando: ${formatBackendError(error)}`,
 			]);
 			setIsUpdating(false);
 			return false;
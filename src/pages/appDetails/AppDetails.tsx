@@ -32,11 +32,13 @@ import { DependencyInfoPopover } from "../../components/DependencyInfoPopover";
 import { DonationModal } from "../../components/DonationModal";
 import { GitHubStarBadge } from "../../components/GitHubStarBadge";
 import { Terminal } from "../../components/Terminal";
+import { useAppRequirements } from "../../hooks/useAppRequirements";
 import { useAppScreenshots } from "../../hooks/useAppScreenshots";
 import { useRepoStats } from "../../hooks/useRepoStats";
 import { useRuntimeCheck } from "../../hooks/useRuntimeCheck";
 import { useInstalledAppsStore } from "../../store/installedAppsStore";
-import type { AppStream, CategoryApp } from "../../types";
+import type { AppStream, CategoryApp, PtyTerminated } from "../../types";
+import { formatBackendError } from "../../utils/backendError";
 
 interface AppDetailsProps {
 	app: CategoryApp;
@@ -66,6 +68,7 @@ export const AppDetails = ({ app, onBack }: AppDetailsProps) => {
 	const { isAppInstalled, setInstalledApp } = useInstalledAppsStore();
 	const { stars, repoUrl } = useRepoStats(app.app_id, urls);
 	const { dependencies, loading: loadingDeps } = useRuntimeCheck(app.app_id);
+	const requirements = useAppRequirements(app.app_id);
 	const [currentImageIndex, setCurrentImageIndex] = useState(0);
 
 	// Generate stable UUIDs for screenshots
@@ -237,55 +240,65 @@ export const AppDetails = ({ app, onBack }: AppDetailsProps) => {
 			}
 		});
 
-		const unlistenPtyTerminated = listen<string>("pty-terminated", (event) => {
-			const isOurProcess =
-				event.payload === app.app_id ||
-				event.payload === installSessionId.current;
-			if (isOurProcess && isInstallingRef.current) {
-				console.log("[AppDetails] PTY terminated during install");
-				installSessionId.current = null;
+		const unlistenPtyTerminated = listen<PtyTerminated>(
+			"pty-terminated",
+			(event) => {
+				const isOurProcess =
+					event.payload.app_id === app.app_id ||
+					event.payload.app_id === installSessionId.current;
+				if (isOurProcess && isInstallingRef.current) {
+					console.log("[AppDetails] PTY terminated during install");
+					installSessionId.current = null;
+
+					// Process terminated, verify actual installation status
+					setTimeout(async () => {
+						try {
+							// Check if app was actually installed by querying the system
+							const installed = await invoke<{
+								apps: Array<{ app_id: string }>;
+							}>("get_installed_flatpaks");
+							const isNowInstalled = installed.apps.some(
+								(a) => a.app_id === app.app_id,
+							);
 
-				// Process terminated, verify actual installation status
-				setTimeout(async () => {
-					try {
-						// Check if app was actually installed by querying the system
-						const installed = await invoke<{ apps: Array<{ app_id: string }> }>(
-							"get_installed_flatpaks",
-						);
-						const isNowInstalled = installed.apps.some(
-							(a) => a.app_id === app.app_id,
-						);
-
-						setIsInstalling(false);
-
-						if (isNowInstalled) {
-							// Installation successful
-							setInstallStatus("success");
-							setInstallOutput((p) => {
-								if (
-									p.some(
-										(l) => l === t("appDetails.installationCompletedSuccess"),
+							setIsInstalling(false);
+
+							if (isNowInstalled) {
+								// Installation successful
+								setInstallStatus("success");
+								setInstallOutput((p) => {
+									if (
+										p.some(
+											(l) => l === t("appDetails.installationCompletedSuccess"),
+										)
 									)
-								)
-									return p;
-								return [...p, "", t("appDetails.installationCompletedSuccess")];
-							});
-							setInstalledApp(app.app_id, true);
-						} else {
-							// Installation failed
-							setInstallStatus("error");
+										return p;
+									return [
+										...p,
+										"",
+										t("appDetails.installationCompletedSuccess"),
+									];
+								});
+								setInstalledApp(app.app_id, true);
+							} else {
+								// Installation failed
+								setInstallStatus("error");
+								console.error(
+									"[AppDetails] Installation failed - app not found in installed list",
+								);
+							}
+						} catch (error) {
 							console.error(
-								"[AppDetails] Installation failed - app not found in installed list",
+								"[AppDetails] Error verifying installation:",
+								error,
 							);
+							setIsInstalling(false);
+							setInstallStatus("error");
 						}
-					} catch (error) {
-						console.error("[AppDetails] Error verifying installation:", error);
-						setIsInstalling(false);
-						setInstallStatus("error");
-					}
-				}, 500);
-			}
-		});
+					}, 500);
+				}
+			},
+		);
 
 		return () => {
 			unlistenPtyOutput.then((fn) => fn());
@@ -549,7 +562,7 @@ export const AppDetails = ({ app, onBack }: AppDetailsProps) => {
 			setVerificationResult({
 				verified: false,
 				sources: [],
-				error: String(error),
+				error: formatBackendError(error),
 				isHashMismatch: false,
 				isUnsupportedPlatform: false,
 			});
@@ -861,6 +874,15 @@ export const AppDetails = ({ app, onBack }: AppDetailsProps) => {
 								);
 							})()
 						) : null)}
+
+					{!isInstalled && requirements?.runtime && (
+						<Typography variant="caption" color="text.secondary">
+							{t("appDetails.requiresRuntime", {
+								runtime: requirements.runtime.id,
+								branch: requirements.runtime.branch,
+							})}
+						</Typography>
+					)}
 				</Box>
 			</Box>
 
@@ -1,5 +1,5 @@
 import { Box } from "@mui/material";
-import { createFileRoute, Outlet } from "@tanstack/react-router";
+import { createFileRoute, Outlet, useNavigate } from "@tanstack/react-router";
 import { listen } from "@tauri-apps/api/event";
 import { useEffect, useRef, useState } from "react";
 import { useTranslation } from "react-i18next";
@@ -16,6 +16,7 @@ export const Route = createFileRoute("/_layout")({
 
 function LayoutComponent() {
 	const { t } = useTranslation();
+	const navigate = useNavigate();
 	const { isFirstLaunch, isInitializing, error } = useAppInitialization();
 	const [showWelcome, setShowWelcome] = useState(true);
 	const [localFlatpakFile, setLocalFlatpakFile] = useState<string | null>(null);
@@ -40,6 +41,20 @@ function LayoutComponent() {
 		};
 	}, []);
 
+	// Listen for open-app events emitted for appstream://<app_id> links,
+	// whether the store was started by the link or was already running.
+	useEffect(() => {
+		let unlisten: (() => void) | undefined;
+		listen<string>("open-app", (event) => {
+			navigate({ to: "/app/$appId", params: { appId: event.payload } });
+		}).then((fn) => {
+			unlisten = fn;
+		});
+		return () => {
+			unlisten?.();
+		};
+	}, [navigate]);
+
 	const handleWelcomeComplete = () => {
 		setShowWelcome(false);
 	};
@@ -9,6 +9,7 @@ export interface InstalledAppInfo {
 	developer?: string; // Developer name extracted from app_id (e.g., "N3kosempai", "mozilla")
 	permissions?: string[]; // Flatpak permissions (e.g., "camera", "files", "storage")
 	installedSize?: number; // Installed size in bytes
+	origin?: string; // Remote the app was installed from (e.g., "flathub")
 }
 
 export interface InstalledExtensionInfo {
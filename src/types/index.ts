@@ -161,3 +161,26 @@ export interface SearchResponse {
 	facetDistribution?: Record<string, Record<string, number>>;
 	facetStats?: Record<string, Record<string, number>>;
 }
+
+// Payload of the backend's "pty-terminated" event
+export interface PtyTerminated {
+	app_id: string;
+	code: number | null; // null when killed by a signal
+	success: boolean;
+	timed_out: boolean;
+}
+
+// Payload of the backend's "pty-prompt" event, answer through send_to_pty
+export interface PtyPrompt {
+	app_id: string;
+	prompt_type: "trust" | "confirm" | "choice";
+	text: string;
+}
+
+// Payload of the backend's "pty-choice" event: the numbered list before a
+// choice prompt. Send the chosen index through send_to_pty
+export interface PtyChoice {
+	app_id: string;
+	options: { index: number; ref: string }[];
+	default: number | null;
+}
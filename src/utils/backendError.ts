@@ -0,0 +1,44 @@
+import i18n from "../i18n/config";
+
+// Error returned by every backend command (StoreError in src-tauri/src/error.rs)
+export interface BackendError {
+	code: string;
+	message: string;
+}
+
+export function isBackendError(err: unknown): err is BackendError {
+	return (
+		typeof err === "object" &&
+		err !== null &&
+		typeof (err as BackendError).code === "string" &&
+		typeof (err as BackendError).message === "string"
+	);
+}
+
+/**
+ * Stable error code ("FLATPAK_NOT_FOUND", "NETWORK_ERROR", ...) or null for non-backend errors
+ */
+export function getBackendErrorCode(err: unknown): string | null {
+	return isBackendError(err) ? err.code : null;
+}
+
+/**
+ * Untranslated error text, useful for logs and substring checks
+ */
+export function getBackendErrorMessage(err: unknown): string {
+	if (isBackendError(err)) return err.message;
+	if (err instanceof Error) return err.message;
+	return String(err);
+}
+
+/**
+ * User-facing error text, translated by code when a translation exists
+ */
+export function formatBackendError(err: unknown): string {
+	const message = getBackendErrorMessage(err);
+	if (!isBackendError(err)) return message;
+	return i18n.t(`backendErrors.${err.code}`, {
+		message,
+		defaultValue: message,
+	});
+}
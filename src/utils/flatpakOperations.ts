@@ -1,11 +1,19 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import i18n from "../i18n/config";
 
 export interface FlatpakOperationProgress {
 	output: string;
 	progress?: number;
 }
 
+// Translatable status line emitted by the backend ("install-status" event)
+interface BackendStatus {
+	key: string;
+	app_id: string | null;
+	message: string;
+}
+
 export interface FlatpakOperationResult {
 	success: boolean;
 	exitCode: number;
@@ -23,6 +31,7 @@ export async function executeFlatpakOperation(
 	console.log("[executeFlatpakOperation] Starting operation...");
 	const output: string[] = [];
 	let unlistenOutput: UnlistenFn | null = null;
+	let unlistenStatus: UnlistenFn | null = null;
 	let unlistenError: UnlistenFn | null = null;
 	let unlistenCompleted: UnlistenFn | null = null;
 
@@ -60,6 +69,19 @@ export async function executeFlatpakOperation(
 				}
 			});
 
+			// Listen to status events and translate them by key
+			unlistenStatus = await listen<BackendStatus>("install-status", (event) => {
+				const { key, app_id, message } = event.payload;
+				const line = i18n.t(`backendStatus.${key}`, {
+					appId: app_id ?? "",
+					defaultValue: message,
+				});
+				output.push(line);
+				if (onProgress) {
+					onProgress({ output: line });
+				}
+			});
+
 			// Listen to error events
 			unlistenError = await listen<string>("install-error", (event) => {
 				const errorMsg = `Error: ${event.payload}`;
@@ -81,6 +103,7 @@ export async function executeFlatpakOperation(
 
 				// Cleanup listeners
 				unlistenOutput?.();
+				unlistenStatus?.();
 				unlistenError?.();
 				unlistenCompleted?.();
 
@@ -104,6 +127,7 @@ export async function executeFlatpakOperation(
 			);
 			// Cleanup listeners on error
 			unlistenOutput?.();
+			unlistenStatus?.();
 			unlistenError?.();
 			unlistenCompleted?.();
 
@@ -1,4 +1,5 @@
 import { convertFileSrc, invoke } from "@tauri-apps/api/core";
+import { getBackendErrorCode, getBackendErrorMessage } from "./backendError";
 
 interface QueueItem {
 	appId: string;
@@ -117,7 +118,9 @@ export class ImageCacheManager {
 	}
 
 	private isTemporaryError(error: unknown): boolean {
-		const errorMsg = String(error).toLowerCase();
+		const code = getBackendErrorCode(error);
+		if (code === "NETWORK_ERROR" || code === "TIMEOUT") return true;
+		const errorMsg = getBackendErrorMessage(error).toLowerCase();
 		// Errores temporales: timeout, network, connection
 		return (
 			errorMsg.includes("timeout") ||
@@ -288,6 +291,14 @@ export class ImageCacheManager {
 		// Si no está en caché, descargar y cachear con prioridad
 		return await this.cacheImage(appId, imageUrl, priority);
 	}
+
+	// Mover la caché a otro directorio (null vuelve al predeterminado)
+	async setCacheDir(path: string | null): Promise<string> {
+		this.cacheDir = await invoke<string>("set_cache_dir", { path });
+		// Las rutas guardadas en memoria apuntan al directorio anterior
+		memoryCache.clear();
+		return this.cacheDir;
+	}
 }
 
 export const imageCacheManager = ImageCacheManager.getInstance();